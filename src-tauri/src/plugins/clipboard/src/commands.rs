@@ -9,7 +9,7 @@ use std::{
     sync::{Arc, Mutex},
     thread::spawn,
 };
-use tauri::{command, AppHandle, Emitter, Manager, Runtime, State};
+use tauri::{command, AppHandle, Manager, Runtime, State};
 
 mod audio;
 mod utils;
@@ -19,7 +19,9 @@ pub use utils::{save_clipboard_image, schedule_ocr_task};
 use tauri_plugin_eco_common::id::generate_id;
 
 // 引入 database 插件
-use tauri_plugin_eco_database::{DatabaseState, InsertItem};
+use tauri_plugin_eco_database::{
+    emit_changes, ChangeOperation, DatabaseChanges, DatabaseState, InsertItem,
+};
 
 // 引入 detector 插件
 use tauri_plugin_eco_detector::DetectorState;
@@ -98,6 +100,15 @@ impl ClipboardManager {
 }
 
 impl ClipboardManager {
+    /// 读取纯文本
+    pub fn read_text(&self) -> Result<String, String> {
+        self.context
+            .lock()
+            .map_err(|e| e.to_string())?
+            .get_text()
+            .map_err(|e| e.to_string())
+    }
+
     /// 写入纯文本并记录指纹
     /// 使用 set() 并只传入 Text 以清除其他格式（如 HTML/RTF）
     pub fn write_text(&self, value: String) -> Result<(), String> {
@@ -498,6 +509,9 @@ where
                         detect_color: true,
                         detect_code: true,
                         detect_markdown: true,
+                        detect_secret: tauri_plugin_eco_database::config::should_detect_sensitive(
+                            &app_handle,
+                        ),
                         code_min_length: 10,
                     },
                 );
@@ -521,7 +535,7 @@ where
                             // 代码类型：type = "code", subtype = 语言
                             ("code".to_string(), detection.code_language, None)
                         } else if let Some(s) = detection.subtype {
-                            // 其他子类型（url/email/path/color）
+                            // 其他子类型（url/email/path/color/sensitive）
                             // 如果是颜色类型，使用 color_normalized 作为 search 字段用于去重
                             let color_search = if s == "color" {
                                 detection.color_normalized
@@ -585,24 +599,28 @@ where
             source_app_name: None,
             source_app_icon: None,
             position: None,
+            expires_at: None,
+            expire_on_paste: false,
         };
 
         // 同步插入数据库
-        let db = db_state.blocking_lock();
+        let db = db_state.blocking_read();
         match db.insert_with_deduplication(&item, &app_handle) {
             Ok(result) => {
                 // 播放复制音效（反馈用户复制操作已完成）
                 play_copy_audio(&app_handle);
 
-                // 发送事件通知前端，携带重复数据的 ID（如果是更新操作）
-                let payload = if result.is_update {
-                    serde_json::json!({ "duplicate_id": result.insert_id })
+                // 发送变更事件，重复数据携带被更新的记录 ID
+                let operation = if result.is_update {
+                    ChangeOperation::Updated
                 } else {
-                    serde_json::json!({ "duplicate_id": null })
+                    ChangeOperation::Inserted
                 };
-                let _ = app_handle
-                    .emit("plugin:eco-clipboard://database_updated", payload)
-                    .map_err(|err| err.to_string());
+                let mut changes = DatabaseChanges::single(operation, &result.insert_id, &[]);
+                if result.is_update {
+                    changes.duplicate_id = result.insert_id.clone();
+                }
+                emit_changes(&app_handle, changes);
             }
             Err(e) => {
                 log::error!("插入剪贴板数据到数据库失败: {}", e);
@@ -1,9 +1,12 @@
 use std::path::PathBuf;
 
 use clipboard_rs::common::{RustImage, RustImageData};
-use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tauri::{AppHandle, Manager, Runtime};
 
 use tauri_plugin_eco_common::{file::get_file_size, id::generate_id};
+use tauri_plugin_eco_database::{
+    emit_changes, store_image_file, ChangeOperation, DatabaseChanges, DatabaseState,
+};
 
 /// 检查OCR配置是否开启
 pub fn should_enable_ocr<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
@@ -149,7 +152,7 @@ pub async fn perform_ocr_and_update<R: Runtime>(
 
         // 直接操作数据库并更新 change_tracker
         if let Some(db_state) = app_handle.try_state::<tauri_plugin_eco_database::DatabaseState>() {
-            let db = db_state.lock().await;
+            let db = db_state.read().await;
 
             // 通过文件路径查找正确的 item_id（因为去重可能导致ID变化）
             let actual_item_id = {
@@ -177,31 +180,29 @@ pub async fn perform_ocr_and_update<R: Runtime>(
                 }
             };
 
-            // 更新 search 字段
-            if let Err(e) = db.update_field(&actual_item_id, "search", &ocr_text) {
-                log::error!("[Clipboard] 更新 OCR search 字段失败: {}", e);
-                return;
-            }
-
             let current_time = chrono::Utc::now().timestamp_millis();
 
-            // 更新时间戳
-            if let Err(e) = db.update_field(&actual_item_id, "time", &current_time.to_string()) {
-                log::error!("[Clipboard] 更新时间戳失败: {}", e);
-            }
-
-            // 标记 change_tracker（search 字段更新是实质变更，需要同步）
-            if let Ok(conn) = db.get_connection() {
-                if let Err(e) = db.get_change_tracker().mark_item_changed(&conn, &actual_item_id, "search", false) {
-                    log::error!("[Clipboard] 标记 change_tracker 失败: {}", e);
-                }
+            // 更新 search 字段和时间戳，并记录变更（search 字段更新是实质变更，需要同步）
+            if let Err(e) = db.update_fields_tracked(
+                &actual_item_id,
+                &[("search", ocr_text), ("time", current_time.to_string())],
+                false,
+                "search",
+                false,
+            ) {
+                log::error!("[Clipboard] 更新 OCR search 字段失败: {}", e);
+                return;
             }
 
             // 发送事件通知前端刷新数据
-            let payload = serde_json::json!({ "duplicate_id": null });
-            let _ = app_handle
-                .emit("plugin:eco-clipboard://database_updated", payload)
-                .map_err(|err| log::error!("[Clipboard] 发送OCR更新事件失败: {}", err));
+            emit_changes(
+                app_handle,
+                DatabaseChanges::single(
+                    ChangeOperation::Updated,
+                    [actual_item_id.as_str()],
+                    &["search", "time"],
+                ),
+            );
         }
     } else {
         log::trace!("[Clipboard] OCR 识别无结果或失败");
@@ -242,17 +243,28 @@ pub fn save_clipboard_image<R: Runtime>(
     let images_dir = app_data_dir.join("images");
     let _ = std::fs::create_dir_all(&images_dir);
 
-    let image_path = images_dir.join(format!("{}.png", id));
+    // 先写入临时文件，计算内容哈希后再重命名为 <哈希>.png
+    let temp_path = images_dir.join(format!("{}.tmp.png", id));
 
     // 保存或复制图片
     if let Some(from_path) = copy_from {
-        std::fs::copy(from_path, &image_path).map_err(|e| e.to_string())?;
+        std::fs::copy(from_path, &temp_path).map_err(|e| e.to_string())?;
     } else if let Some(img) = image {
-        if let Some(path_str) = image_path.to_str() {
+        if let Some(path_str) = temp_path.to_str() {
             img.save_to_path(path_str).map_err(|e| e.to_string())?;
         }
     }
 
+    let stored = store_image_file(&images_dir, &temp_path)?;
+    let image_path = stored.path.clone();
+
+    // 登记图片，引用数随历史记录的写入和删除更新
+    if let Some(db_state) = app_handle.try_state::<DatabaseState>() {
+        if let Err(e) = db_state.blocking_read().register_stored_image(&stored) {
+            log::warn!("[Clipboard] 登记图片失败: {}", e);
+        }
+    }
+
     let (width, height) = if let Some(path_str) = image_path.to_str() {
         RustImageData::from_path(path_str)
             .map(|img| img.get_size())
@@ -0,0 +1,249 @@
+//! 凭据存储
+//! 服务器密码、加密口令等敏感信息保存在系统密钥环中（Windows 凭据管理器、
+//! macOS 钥匙串、Linux Secret Service / libsecret），不写入 server-config.json
+//!
+//! 系统没有可用的密钥环服务时（如未运行 gnome-keyring 的 Linux 桌面）回退到数据目录下的
+//! 加密文件。回退文件的密钥保存在同目录的随机密钥文件中，只能避免凭据以明文形式出现在
+//! 配置文件中，无法防御可以读取整个数据目录的程序
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::paths::{get_data_path, is_dev_mode, BUNDLE_ID};
+
+/// 服务器密码（WebDAV 密码 / S3 Secret Key）
+pub const SERVER_PASSWORD: &str = "server-password";
+/// 端到端加密口令
+pub const ENCRYPTION_PASSPHRASE: &str = "encryption-passphrase";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// 密钥环服务名（开发模式与正式版本分开保存）
+fn service_name() -> String {
+    if is_dev_mode() {
+        format!("{}.dev", BUNDLE_ID)
+    } else {
+        BUNDLE_ID.to_string()
+    }
+}
+
+fn keyring_entry(account: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(&service_name(), account)
+}
+
+/// 密钥环服务不可用，需要回退到加密文件
+fn is_unavailable(error: &keyring::Error) -> bool {
+    matches!(
+        error,
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+    )
+}
+
+/// 读取凭据，不存在时返回 None
+pub fn load_secret(account: &str) -> Result<Option<String>, String> {
+    match keyring_entry(account).and_then(|entry| entry.get_password()) {
+        Ok(secret) => return Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => {}
+        Err(e) if is_unavailable(&e) => {
+            log::debug!("[Credentials] 系统密钥环不可用，读取加密文件: {}", e);
+        }
+        Err(e) => return Err(format!("读取系统密钥环失败: {}", e)),
+    }
+
+    // 密钥环中没有时查找回退文件（可能是之前密钥环不可用时保存的）
+    FallbackStore::open()?.get(account)
+}
+
+/// 保存凭据
+pub fn save_secret(account: &str, secret: &str) -> Result<(), String> {
+    match keyring_entry(account).and_then(|entry| entry.set_password(secret)) {
+        Ok(()) => {
+            // 已保存到密钥环，清理回退文件中的旧值
+            FallbackStore::open()?.remove(account)
+        }
+        Err(e) if is_unavailable(&e) => {
+            log::warn!("[Credentials] 系统密钥环不可用，凭据保存到加密文件: {}", e);
+            FallbackStore::open()?.set(account, secret)
+        }
+        Err(e) => Err(format!("保存到系统密钥环失败: {}", e)),
+    }
+}
+
+/// 删除凭据
+pub fn delete_secret(account: &str) -> Result<(), String> {
+    match keyring_entry(account).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) if is_unavailable(&e) => {}
+        Err(e) => return Err(format!("从系统密钥环删除失败: {}", e)),
+    }
+
+    FallbackStore::open()?.remove(account)
+}
+
+/// 加密文件形式的凭据存储
+///
+/// 数据文件格式：`nonce(12) | AES-256-GCM(JSON { account: secret })`
+struct FallbackStore {
+    data_path: PathBuf,
+    key_path: PathBuf,
+}
+
+impl FallbackStore {
+    /// 打开数据目录下的回退存储
+    fn open() -> Result<Self, String> {
+        let dir = get_data_path().ok_or_else(|| "无法获取数据目录".to_string())?;
+        Ok(Self::in_dir(&dir))
+    }
+
+    fn in_dir(dir: &Path) -> Self {
+        let name = if is_dev_mode() {
+            "credentials.dev"
+        } else {
+            "credentials"
+        };
+
+        Self {
+            data_path: dir.join(format!("{}.bin", name)),
+            key_path: dir.join(format!("{}.key", name)),
+        }
+    }
+
+    fn get(&self, account: &str) -> Result<Option<String>, String> {
+        Ok(self.read_all()?.remove(account))
+    }
+
+    fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+        let mut secrets = self.read_all()?;
+        secrets.insert(account.to_string(), secret.to_string());
+        self.write_all(&secrets)
+    }
+
+    fn remove(&self, account: &str) -> Result<(), String> {
+        if !self.data_path.exists() {
+            return Ok(());
+        }
+
+        let mut secrets = self.read_all()?;
+        if secrets.remove(account).is_none() {
+            return Ok(());
+        }
+
+        if secrets.is_empty() {
+            std::fs::remove_file(&self.data_path).map_err(|e| format!("删除凭据文件失败: {}", e))
+        } else {
+            self.write_all(&secrets)
+        }
+    }
+
+    fn read_all(&self) -> Result<BTreeMap<String, String>, String> {
+        if !self.data_path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let data =
+            std::fs::read(&self.data_path).map_err(|e| format!("读取凭据文件失败: {}", e))?;
+        let key = std::fs::read(&self.key_path).map_err(|e| format!("读取凭据密钥失败: {}", e))?;
+
+        if data.len() < NONCE_LEN || key.len() != KEY_LEN {
+            return Err("凭据文件已损坏".to_string());
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "凭据文件已损坏".to_string())?;
+
+        serde_json::from_slice(&plaintext).map_err(|e| format!("解析凭据文件失败: {}", e))
+    }
+
+    fn write_all(&self, secrets: &BTreeMap<String, String>) -> Result<(), String> {
+        if let Some(parent) = self.data_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
+        }
+
+        let key = self.load_or_create_key()?;
+        let plaintext = serde_json::to_vec(secrets).map_err(|e| format!("序列化失败: {}", e))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|e| format!("加密凭据失败: {}", e))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        write_private_file(&self.data_path, &data)
+    }
+
+    fn load_or_create_key(&self) -> Result<Vec<u8>, String> {
+        if self.key_path.exists() {
+            return std::fs::read(&self.key_path).map_err(|e| format!("读取凭据密钥失败: {}", e));
+        }
+
+        let mut key = vec![0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        write_private_file(&self.key_path, &key)?;
+        Ok(key)
+    }
+}
+
+/// 写入仅当前用户可读写的文件
+fn write_private_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    use std::io::Write;
+
+    let temp_path = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&temp_path)
+        .map_err(|e| format!("写入凭据文件失败: {}", e))?;
+    file.write_all(data)
+        .map_err(|e| format!("写入凭据文件失败: {}", e))?;
+    drop(file);
+
+    std::fs::rename(&temp_path, path).map_err(|e| format!("写入凭据文件失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_store_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-credentials-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FallbackStore::in_dir(&dir);
+
+        store.set(SERVER_PASSWORD, "s3cret-password").unwrap();
+        store.set(ENCRYPTION_PASSPHRASE, "passphrase").unwrap();
+        assert_eq!(
+            store.get(SERVER_PASSWORD).unwrap().as_deref(),
+            Some("s3cret-password")
+        );
+
+        // 文件中不应出现明文
+        let raw = std::fs::read(&store.data_path).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("s3cret-password"));
+
+        store.remove(SERVER_PASSWORD).unwrap();
+        assert_eq!(store.get(SERVER_PASSWORD).unwrap(), None);
+        assert_eq!(
+            store.get(ENCRYPTION_PASSPHRASE).unwrap().as_deref(),
+            Some("passphrase")
+        );
+
+        store.remove(ENCRYPTION_PASSPHRASE).unwrap();
+        assert!(!store.data_path.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -3,6 +3,7 @@ pub mod audio;
 pub mod commands;
 pub mod config;
 pub mod constants;
+pub mod credentials;
 pub mod file;
 pub mod id;
 pub mod paths;
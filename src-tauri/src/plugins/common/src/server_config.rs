@@ -2,8 +2,15 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 
+use crate::credentials::{
+    delete_secret, load_secret, save_secret, ENCRYPTION_PASSPHRASE, SERVER_PASSWORD,
+};
 use crate::paths::get_server_config_path;
 
+/// 返回给前端的凭据占位符，表示已保存但不回显
+/// 保存配置时传回占位符表示沿用已保存的值
+pub const REDACTED_SECRET: &str = "********";
+
 /// 服务器配置缓存
 static SERVER_CONFIG_CACHE: Lazy<Mutex<Option<ServerConfigData>>> = Lazy::new(|| Mutex::new(None));
 
@@ -13,14 +20,77 @@ static SERVER_CONFIG_CACHE: Lazy<Mutex<Option<ServerConfigData>>> = Lazy::new(||
 pub struct ServerConfigData {
     pub url: String,
     pub username: String,
+    /// 服务器密码（保存在系统密钥环中，配置文件中始终为空）
     pub password: String,
     pub path: String,
     pub timeout: u64,
+    /// 端到端加密口令（为空时不加密，保存在系统密钥环中）
+    /// 保存配置时 None 表示沿用已保存的口令，空字符串表示清除
+    #[serde(default)]
+    pub encryption_passphrase: Option<String>,
+    /// 存储后端类型（webdav / local / s3，为空时使用 WebDAV）
+    #[serde(default)]
+    pub backend: Option<String>,
+    /// S3 存储桶
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 区域
+    #[serde(default)]
+    pub region: Option<String>,
     /// 上次同步时间（Unix 时间戳，秒）
     pub last_sync_time: Option<u64>,
 }
 
-/// 读取并解析服务器配置文件
+impl ServerConfigData {
+    /// 隐藏凭据后的配置（用于返回给前端）
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if !config.password.is_empty() {
+            config.password = REDACTED_SECRET.to_string();
+        }
+        if config.encryption_passphrase.as_deref().is_some_and(|p| !p.is_empty()) {
+            config.encryption_passphrase = Some(REDACTED_SECRET.to_string());
+        }
+        config
+    }
+
+    /// 配置文件中是否含有明文凭据（旧版本直接写入配置文件）
+    fn has_plaintext_secrets(&self) -> bool {
+        !self.password.is_empty()
+            || self.encryption_passphrase.as_deref().is_some_and(|p| !p.is_empty())
+    }
+}
+
+/// 是否为凭据占位符
+pub fn is_redacted_secret(value: &str) -> bool {
+    value == REDACTED_SECRET
+}
+
+/// 从凭据存储中读取密码和加密口令
+fn load_secrets(config: &mut ServerConfigData) {
+    match load_secret(SERVER_PASSWORD) {
+        Ok(password) => config.password = password.unwrap_or_default(),
+        Err(e) => log::warn!("[ServerConfig] 读取服务器密码失败: {}", e),
+    }
+    match load_secret(ENCRYPTION_PASSPHRASE) {
+        Ok(passphrase) => config.encryption_passphrase = passphrase,
+        Err(e) => log::warn!("[ServerConfig] 读取加密口令失败: {}", e),
+    }
+}
+
+/// 将明文凭据迁移到凭据存储，并重写不含凭据的配置文件
+fn migrate_plaintext_secrets(config: &ServerConfigData) -> Result<(), String> {
+    if !config.password.is_empty() {
+        save_secret(SERVER_PASSWORD, &config.password)?;
+    }
+    if let Some(passphrase) = config.encryption_passphrase.as_deref().filter(|p| !p.is_empty()) {
+        save_secret(ENCRYPTION_PASSPHRASE, passphrase)?;
+    }
+
+    write_server_config_file(config)
+}
+
+/// 读取并解析服务器配置文件（凭据从系统密钥环中读取）
 pub fn read_server_config() -> Result<ServerConfigData, String> {
     let config_path = match get_server_config_path() {
         Some(path) => path,
@@ -34,7 +104,22 @@ pub fn read_server_config() -> Result<ServerConfigData, String> {
     let content =
         std::fs::read_to_string(&config_path).map_err(|e| format!("读取配置文件失败: {}", e))?;
 
-    serde_json::from_str(&content).map_err(|e| format!("解析配置文件失败: {}", e))
+    let mut config: ServerConfigData =
+        serde_json::from_str(&content).map_err(|e| format!("解析配置文件失败: {}", e))?;
+
+    if config.has_plaintext_secrets() {
+        // 迁移失败时保留明文配置继续使用，下次启动再尝试
+        match migrate_plaintext_secrets(&config) {
+            Ok(()) => log::info!("[ServerConfig] 已将明文密码迁移到系统密钥环"),
+            Err(e) => {
+                log::warn!("[ServerConfig] 迁移明文密码失败: {}", e);
+                return Ok(config);
+            }
+        }
+    }
+
+    load_secrets(&mut config);
+    Ok(config)
 }
 
 /// 获取缓存的服务器配置（如果缓存为空则先读取）
@@ -82,8 +167,8 @@ pub fn update_last_sync_time(timestamp: u64) {
     let _ = save_server_config(config);
 }
 
-/// 保存服务器配置并更新缓存（接受所有权）
-pub fn save_server_config(config: ServerConfigData) -> Result<(), String> {
+/// 写入配置文件（不含凭据）
+fn write_server_config_file(config: &ServerConfigData) -> Result<(), String> {
     let config_path = match get_server_config_path() {
         Some(path) => path,
         None => return Err("无法获取数据目录".to_string()),
@@ -93,9 +178,56 @@ pub fn save_server_config(config: ServerConfigData) -> Result<(), String> {
         std::fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
     }
 
-    let json = serde_json::to_string_pretty(&config).map_err(|e| format!("序列化失败: {}", e))?;
+    let mut file_config = config.clone();
+    file_config.password = String::new();
+    file_config.encryption_passphrase = None;
+
+    let json =
+        serde_json::to_string_pretty(&file_config).map_err(|e| format!("序列化失败: {}", e))?;
+
+    std::fs::write(&config_path, json).map_err(|e| format!("写入失败: {}", e))
+}
+
+/// 保存服务器配置并更新缓存（接受所有权）
+/// 凭据写入系统密钥环，传入占位符时沿用已保存的值
+pub fn save_server_config(mut config: ServerConfigData) -> Result<(), String> {
+    let cached = SERVER_CONFIG_CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+
+    if is_redacted_secret(&config.password) {
+        config.password = match &cached {
+            Some(cached) => cached.password.clone(),
+            None => load_secret(SERVER_PASSWORD)?.unwrap_or_default(),
+        };
+    } else if cached.as_ref().is_some_and(|c| c.password == config.password) {
+        // 未修改（如仅更新同步时间），避免重复写入密钥环
+    } else if config.password.is_empty() {
+        delete_secret(SERVER_PASSWORD)?;
+    } else {
+        save_secret(SERVER_PASSWORD, &config.password)?;
+    }
+
+    match config.encryption_passphrase.as_deref() {
+        None | Some(REDACTED_SECRET) => {
+            config.encryption_passphrase = match &cached {
+                Some(cached) => cached.encryption_passphrase.clone(),
+                None => load_secret(ENCRYPTION_PASSPHRASE)?,
+            };
+        }
+        Some(passphrase)
+            if cached
+                .as_ref()
+                .is_some_and(|c| c.encryption_passphrase.as_deref() == Some(passphrase)) => {}
+        Some("") => {
+            delete_secret(ENCRYPTION_PASSPHRASE)?;
+            config.encryption_passphrase = None;
+        }
+        Some(passphrase) => save_secret(ENCRYPTION_PASSPHRASE, passphrase)?,
+    }
 
-    std::fs::write(&config_path, json).map_err(|e| format!("写入失败: {}", e))?;
+    write_server_config_file(&config)?;
 
     // 更新缓存
     {
@@ -0,0 +1,120 @@
+//! 连接池基准测试
+//! 对比每次操作都新开连接（旧实现，rollback journal）与连接池 + WAL 的插入、查询吞吐量
+//!
+//! 运行：`cargo bench -p tauri-plugin-eco-database --bench connection_pool`
+
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri_plugin_eco_database::{DatabaseManager, QueryOptions};
+
+const INSERT_COUNT: usize = 2_000;
+const QUERY_COUNT: usize = 500;
+const PAGE_SIZE: i32 = 50;
+
+const INSERT_SQL: &str =
+    "INSERT INTO history (id, type, [group], value, search, count, time, position)
+     VALUES (?1, 'text', 'text', ?2, ?2, ?3, ?4, ?4)";
+const QUERY_SQL: &str = "SELECT * FROM history WHERE (deleted IS NULL OR deleted = 0)
+     ORDER BY position DESC LIMIT ?1";
+
+fn setup(dir: &Path, name: &str) -> (DatabaseManager, PathBuf) {
+    let path = dir.join(name);
+    let mut db = DatabaseManager::new();
+    db.init(path.clone()).expect("初始化数据库失败");
+    (db, path)
+}
+
+fn item_value(i: usize) -> String {
+    format!("剪贴板内容 clipboard item #{} {}", i, "x".repeat(i % 64))
+}
+
+fn report(label: &str, count: usize, elapsed: Duration) {
+    println!(
+        "{:<28} {:>6} 次  {:>8.1} ms  {:>10.0} 次/秒",
+        label,
+        count,
+        elapsed.as_secs_f64() * 1000.0,
+        count as f64 / elapsed.as_secs_f64()
+    );
+}
+
+/// 旧实现：每次操作打开新连接，使用默认的 rollback journal
+fn bench_open_per_call(path: &Path) {
+    Connection::open(path)
+        .and_then(|conn| conn.query_row("PRAGMA journal_mode = DELETE", [], |_| Ok(())))
+        .expect("切换日志模式失败");
+
+    let start = Instant::now();
+    for i in 0..INSERT_COUNT {
+        let conn = Connection::open(path).expect("打开数据库失败");
+        let value = item_value(i);
+        conn.execute(
+            INSERT_SQL,
+            params![format!("before-{}", i), value, value.len(), i as i64],
+        )
+        .expect("插入失败");
+    }
+    report("新开连接 插入", INSERT_COUNT, start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..QUERY_COUNT {
+        let conn = Connection::open(path).expect("打开数据库失败");
+        let mut stmt = conn.prepare(QUERY_SQL).expect("准备查询失败");
+        let rows = stmt
+            .query_map(params![PAGE_SIZE], |row| row.get::<_, String>("id"))
+            .expect("查询失败")
+            .count();
+        assert_eq!(rows, PAGE_SIZE as usize);
+    }
+    report("新开连接 查询", QUERY_COUNT, start.elapsed());
+}
+
+/// 新实现：连接池 + WAL + 预编译语句缓存
+fn bench_pooled(db: &DatabaseManager) {
+    let start = Instant::now();
+    for i in 0..INSERT_COUNT {
+        let conn = db.get_connection().expect("获取连接失败");
+        let value = item_value(i);
+        conn.prepare_cached(INSERT_SQL)
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    format!("after-{}", i),
+                    value,
+                    value.len(),
+                    i as i64
+                ])
+            })
+            .expect("插入失败");
+    }
+    report("连接池 插入", INSERT_COUNT, start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..QUERY_COUNT {
+        let items = db
+            .query_history(QueryOptions {
+                limit: Some(PAGE_SIZE),
+                exclude_deleted: true,
+                ..Default::default()
+            })
+            .expect("查询失败");
+        assert_eq!(items.len(), PAGE_SIZE as usize);
+    }
+    report("连接池 查询", QUERY_COUNT, start.elapsed());
+}
+
+fn main() {
+    let dir = std::env::temp_dir().join(format!("ecopaste-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("创建临时目录失败");
+
+    let (before_db, before_path) = setup(&dir, "before.db");
+    drop(before_db);
+    bench_open_per_call(&before_path);
+
+    let (after_db, _) = setup(&dir, "after.db");
+    bench_pooled(&after_db);
+    drop(after_db);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,10 +1,32 @@
 const COMMANDS: &[&str] = &[
     "query_history_with_filter",
+    "get_item_content",
+    "search_history",
     "delete_items",
     "update_field",
     "cleanup_history",
+    "collect_media_garbage",
     "reset_database",
     "get_database_info",
+    "list_snapshots",
+    "rollback_snapshot",
+    "list_tags",
+    "create_tag",
+    "update_tag",
+    "delete_tag",
+    "get_item_tags",
+    "set_item_tags",
+    "list_snippets",
+    "create_snippet",
+    "update_snippet",
+    "delete_snippet",
+    "reveal_sensitive",
+    "set_item_expiry",
+    "mark_items_pasted",
+    "list_trash",
+    "restore_items",
+    "empty_trash",
+    "undo_delete",
 ];
 
 fn main() {
@@ -0,0 +1,432 @@
+//! 大内容外部存储
+//! 超过阈值的文本内容（日志、生成的 HTML、内嵌 base64 等）不直接保存在 history 表中，
+//! 而是压缩后按内容哈希（SHA-256）保存为 `blobs/<哈希>.gz`，相同内容只保存一份：
+//! 1. `valueBlob` / `searchBlob` 列记录完整内容的哈希，value 和 search 列只保留开头的摘要，
+//!    列表预览和搜索只使用摘要
+//! 2. 读取记录时自动从外部存储还原完整内容（预览查询除外）
+//! 3. 直接改写 value / search 列而未更新引用时，由触发器清除失效的引用
+//! 4. 不再被引用的文件由媒体垃圾回收删除（见 `gc`）
+//!
+//! 图片和文件记录的 value 是文件路径，不会转存
+
+use crate::database::DatabaseManager;
+use crate::models::HistoryItem;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 超过此大小（字节）的内容转存到外部存储
+pub const BLOB_THRESHOLD: usize = 64 * 1024;
+
+/// 转存后表中保留的摘要长度（字符）
+pub const BLOB_EXCERPT_CHARS: usize = 2048;
+
+/// 外部存储目录名（位于数据库文件所在目录）
+const BLOB_DIR_NAME: &str = "blobs";
+
+/// history 表中记录外部存储引用的列
+pub(crate) const BLOB_COLUMNS: &[(&str, &str)] = &[("valueBlob", "TEXT"), ("searchBlob", "TEXT")];
+
+/// 引用索引及清除失效引用的触发器
+pub(crate) const BLOB_SCHEMA: &str = r#"
+    CREATE INDEX IF NOT EXISTS idx_history_searchBlob ON history(searchBlob);
+
+    CREATE TRIGGER IF NOT EXISTS history_value_blob_stale AFTER UPDATE OF value ON history
+    WHEN new.valueBlob IS NOT NULL AND new.valueBlob IS old.valueBlob
+        AND new.value IS NOT old.value BEGIN
+        UPDATE history SET valueBlob = NULL WHERE rowid = new.rowid;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS history_search_blob_stale AFTER UPDATE OF search ON history
+    WHEN new.searchBlob IS NOT NULL AND new.searchBlob IS old.searchBlob
+        AND new.search IS NOT old.search BEGIN
+        UPDATE history SET searchBlob = NULL WHERE rowid = new.rowid;
+    END;
+"#;
+
+/// 写入 history 表的内容（大内容已替换为摘要和引用）
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoredContent {
+    pub value: Option<String>,
+    pub search: Option<String>,
+    /// value 完整内容的哈希
+    pub value_blob: Option<String>,
+    /// search 完整内容的哈希
+    pub search_blob: Option<String>,
+}
+
+/// 数据库文件对应的外部存储目录
+pub(crate) fn blob_dir_of(db_path: &Path) -> PathBuf {
+    db_path.parent().map_or_else(
+        || PathBuf::from(BLOB_DIR_NAME),
+        |dir| dir.join(BLOB_DIR_NAME),
+    )
+}
+
+/// 内容哈希对应的文件
+pub(crate) fn blob_path(blob_dir: &Path, hash: &str) -> PathBuf {
+    blob_dir.join(format!("{}.gz", hash))
+}
+
+/// 外部存储文件对应的内容哈希（不是外部存储文件时返回 None）
+pub(crate) fn blob_hash_of(blob_dir: &Path, path: &Path) -> Option<String> {
+    if path.parent() != Some(blob_dir) {
+        return None;
+    }
+    path.file_name()?
+        .to_str()?
+        .strip_suffix(".gz")
+        .map(str::to_string)
+}
+
+/// 压缩保存内容，返回内容哈希
+/// 相同内容已存在时直接复用，并更新修改时间（避免被垃圾回收删除）
+pub fn store_blob(blob_dir: &Path, content: &str) -> Result<String, String> {
+    let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    let path = blob_path(blob_dir, &hash);
+
+    if path.is_file() {
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        return Ok(hash);
+    }
+
+    std::fs::create_dir_all(blob_dir).map_err(|e| format!("创建外部存储目录失败: {}", e))?;
+
+    // 先写入临时文件，完成后再改名，避免读取到不完整的文件
+    let temp_path = blob_dir.join(format!("{}.tmp", hash));
+    let file =
+        std::fs::File::create(&temp_path).map_err(|e| format!("创建外部存储文件失败: {}", e))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(content.as_bytes())
+        .map_err(|e| format!("写入外部存储失败: {}", e))?;
+    encoder
+        .finish()
+        .map_err(|e| format!("写入外部存储失败: {}", e))?;
+
+    if let Err(e) = std::fs::rename(&temp_path, &path) {
+        let _ = std::fs::remove_file(&temp_path);
+        // 同时保存相同内容时，另一方已经写入
+        if !path.is_file() {
+            return Err(format!("保存外部存储失败: {}", e));
+        }
+    }
+
+    Ok(hash)
+}
+
+/// 读取外部存储的内容
+pub fn load_blob(blob_dir: &Path, hash: &str) -> Result<String, String> {
+    let file = std::fs::File::open(blob_path(blob_dir, hash))
+        .map_err(|e| format!("打开外部存储失败: {}", e))?;
+
+    let mut content = String::new();
+    GzDecoder::new(file)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("读取外部存储失败: {}", e))?;
+
+    Ok(content)
+}
+
+/// 单个字段超过阈值时转存，返回表中保存的摘要和引用
+fn offload_field(
+    blob_dir: &Path,
+    content: Option<&str>,
+) -> Result<(Option<String>, Option<String>), String> {
+    match content {
+        Some(content) if content.len() > BLOB_THRESHOLD => {
+            let hash = store_blob(blob_dir, content)?;
+            let excerpt = content.chars().take(BLOB_EXCERPT_CHARS).collect();
+            Ok((Some(excerpt), Some(hash)))
+        }
+        content => Ok((content.map(str::to_string), None)),
+    }
+}
+
+/// 准备写入的内容：超过阈值的 value 和 search 转存到外部存储
+///
+/// # Arguments
+/// * `blob_dir` - 外部存储目录（None 时不转存）
+/// * `item_type` - 内容类型，图片和文件的 value 是路径，不转存
+pub fn offload_content(
+    blob_dir: Option<&Path>,
+    item_type: Option<&str>,
+    value: Option<&str>,
+    search: Option<&str>,
+) -> Result<StoredContent, String> {
+    let blob_dir = match blob_dir {
+        Some(dir) if !matches!(item_type, Some("image") | Some("files")) => dir,
+        _ => {
+            return Ok(StoredContent {
+                value: value.map(str::to_string),
+                search: search.map(str::to_string),
+                ..Default::default()
+            })
+        }
+    };
+
+    let (value, value_blob) = offload_field(blob_dir, value)?;
+    let (search, search_blob) = offload_field(blob_dir, search)?;
+
+    Ok(StoredContent {
+        value,
+        search,
+        value_blob,
+        search_blob,
+    })
+}
+
+/// 从外部存储还原完整内容
+/// 读取失败时保留摘要，并标记为截断
+fn rehydrate_item(blob_dir: &Path, item: &mut HistoryItem) {
+    let fields = [
+        (item.value_blob.take(), &mut item.value),
+        (item.search_blob.take(), &mut item.search),
+    ];
+
+    for (hash, content) in fields {
+        let Some(hash) = hash else {
+            continue;
+        };
+        match load_blob(blob_dir, &hash) {
+            Ok(full) => *content = Some(full),
+            Err(e) => {
+                log::warn!("[Database] 还原大内容失败: {} ({})", hash, e);
+                item.truncated = true;
+            }
+        }
+    }
+}
+
+/// 将仍保存在表中的大内容转存到外部存储
+/// 用于升级前保存的记录，以及直接改写 value / search 列写入的大内容
+///
+/// # Returns
+/// 转存的记录数量
+pub(crate) fn offload_oversized_rows(conn: &Connection, blob_dir: &Path) -> Result<usize, String> {
+    let ids: Vec<String> = conn
+        .prepare(
+            "SELECT id FROM history
+             WHERE (type IS NULL OR type NOT IN ('image', 'files'))
+               AND ((valueBlob IS NULL AND length(CAST(value AS BLOB)) > ?1)
+                 OR (searchBlob IS NULL AND length(CAST(search AS BLOB)) > ?1))",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map(params![BLOB_THRESHOLD as i64], |row| row.get(0))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("查询大内容失败: {}", e))?;
+
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+
+    for id in &ids {
+        let (item_type, value, search): (Option<String>, Option<String>, Option<String>) = tx
+            .query_row(
+                "SELECT type, value, search FROM history WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| format!("读取记录失败: {}", e))?;
+
+        // 已转存的字段只剩摘要，不会再次转存，保留原有引用
+        let stored = offload_content(
+            Some(blob_dir),
+            item_type.as_deref(),
+            value.as_deref(),
+            search.as_deref(),
+        )?;
+        tx.execute(
+            "UPDATE history SET value = ?1, search = ?2,
+                valueBlob = COALESCE(?3, valueBlob), searchBlob = COALESCE(?4, searchBlob)
+             WHERE id = ?5",
+            params![
+                stored.value,
+                stored.search,
+                stored.value_blob,
+                stored.search_blob,
+                id
+            ],
+        )
+        .map_err(|e| format!("转存大内容失败: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+    Ok(ids.len())
+}
+
+impl DatabaseManager {
+    /// 外部存储目录（数据库未初始化时为 None）
+    pub fn blob_dir(&self) -> Option<PathBuf> {
+        self.get_db_path().map(|db_path| blob_dir_of(db_path))
+    }
+
+    /// 还原查询结果中转存到外部存储的内容
+    pub(crate) fn rehydrate<'a>(&self, items: impl IntoIterator<Item = &'a mut HistoryItem>) {
+        let mut items = items
+            .into_iter()
+            .filter(|item| item.value_blob.is_some() || item.search_blob.is_some())
+            .peekable();
+        if items.peek().is_none() {
+            return;
+        }
+
+        let Some(blob_dir) = self.blob_dir() else {
+            return;
+        };
+        for item in items {
+            rehydrate_item(&blob_dir, item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+
+    #[test]
+    fn test_offload_and_rehydrate() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-blobs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let large = "log line\n".repeat(BLOB_THRESHOLD / 8);
+        let stored = offload_content(
+            Some(dir.as_path()),
+            Some("text"),
+            Some(&large),
+            Some(&large),
+        )
+        .unwrap();
+        assert_eq!(stored.value_blob, stored.search_blob);
+        assert_eq!(
+            stored.value.as_ref().map(|v| v.chars().count()),
+            Some(BLOB_EXCERPT_CHARS)
+        );
+        // 纯文本的 value 和 search 相同，只保存一份
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let hash = stored.value_blob.clone().unwrap();
+        assert_eq!(load_blob(&dir, &hash).unwrap(), large);
+        assert_eq!(blob_hash_of(&dir, &blob_path(&dir, &hash)), Some(hash));
+
+        // 图片路径和小内容不转存
+        let image =
+            offload_content(Some(dir.as_path()), Some("image"), Some(&large), None).unwrap();
+        assert_eq!(image.value.as_ref(), Some(&large));
+        assert!(image.value_blob.is_none());
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, None).unwrap();
+        conn.execute(
+            "INSERT INTO history (id, type, value, search) VALUES ('big', 'text', ?1, ?1), ('small', 'text', 'hi', 'hi')",
+            params![large],
+        )
+        .unwrap();
+        assert_eq!(offload_oversized_rows(&conn, &dir).unwrap(), 1);
+        assert_eq!(offload_oversized_rows(&conn, &dir).unwrap(), 0);
+
+        let mut item = conn
+            .query_row(
+                "SELECT * FROM history WHERE id = 'big'",
+                [],
+                crate::database::history_item_from_row,
+            )
+            .unwrap();
+        assert_eq!(
+            item.search.as_ref().map(|s| s.len()),
+            Some(BLOB_EXCERPT_CHARS)
+        );
+        rehydrate_item(&dir, &mut item);
+        assert_eq!(item.value.as_ref(), Some(&large));
+        assert_eq!(item.search.as_ref(), Some(&large));
+        assert!(!item.truncated);
+
+        // 直接改写内容时清除失效的引用
+        conn.execute("UPDATE history SET value = 'edited' WHERE id = 'big'", [])
+            .unwrap();
+        let (value_blob, search_blob): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT valueBlob, searchBlob FROM history WHERE id = 'big'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert!(value_blob.is_none());
+        assert!(search_blob.is_some());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_edit_past_threshold_offloads() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-blob-edit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO history (id, type, value, search) VALUES ('1', 'text', 'hi', 'hi')",
+                [],
+            )
+            .unwrap();
+
+        // 编辑后的内容超过阈值时转存，表中只保留摘要
+        let large = "x".repeat(BLOB_THRESHOLD + 1);
+        db.update_fields_tracked(
+            "1",
+            &[("value", large.clone()), ("search", large.clone())],
+            false,
+            "content",
+            false,
+        )
+        .unwrap();
+
+        let (value, value_blob, search, search_blob): (
+            String,
+            Option<String>,
+            String,
+            Option<String>,
+        ) = db
+            .get_connection()
+            .unwrap()
+            .query_row(
+                "SELECT value, valueBlob, search, searchBlob FROM history WHERE id = '1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert!(value_blob.is_some());
+        assert!(search_blob.is_some());
+        assert_eq!(value.chars().count(), BLOB_EXCERPT_CHARS);
+        assert_eq!(search.chars().count(), BLOB_EXCERPT_CHARS);
+        assert_eq!(db.query_by_id("1").unwrap().unwrap().value, Some(large));
+
+        // 改回小内容时清除引用
+        db.update_fields_tracked("1", &[("value", "hi".to_string())], false, "content", false)
+            .unwrap();
+        let item = db.query_by_id("1").unwrap().unwrap();
+        assert_eq!(item.value.as_deref(), Some("hi"));
+        assert!(item.value_blob.is_none());
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
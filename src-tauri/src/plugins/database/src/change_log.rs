@@ -0,0 +1,234 @@
+//! 变更日志（outbox）
+//! 本地每次需要同步的修改都会在同一事务中追加一条记录，重启或崩溃后不会丢失
+//!
+//! 同步引擎按序号（游标）读取尚未确认的记录，同步成功后确认到已处理的最大序号，
+//! 已确认的记录随即被清理
+
+use crate::database::DatabaseManager;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_eco_common::id::generate_id;
+
+/// 变更日志相关表结构
+pub(crate) const CHANGE_LOG_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS history_changes (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id TEXT NOT NULL,
+        change_type TEXT NOT NULL,
+        fields TEXT NOT NULL DEFAULT '[]',
+        device_id TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_history_changes_item ON history_changes(item_id);
+
+    CREATE TABLE IF NOT EXISTS change_log_meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+"#;
+
+/// 本机设备ID
+const META_DEVICE_ID: &str = "device_id";
+/// 已确认的最大序号
+const META_CURSOR: &str = "cursor";
+
+/// 变更日志记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeLogEntry {
+    /// 单调递增的序号
+    pub seq: i64,
+    /// 项目ID
+    pub item_id: String,
+    /// 变更类型（insert / update / favorite / note / delete 等）
+    pub change_type: String,
+    /// 变更的字段（为空表示整条记录，如新增、删除）
+    pub fields: Vec<String>,
+    /// 产生变更的设备
+    pub device_id: String,
+    /// 产生时间
+    pub created_at: i64,
+}
+
+fn read_meta(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+    conn.prepare_cached("SELECT value FROM change_log_meta WHERE key = ?1")
+        .and_then(|mut stmt| stmt.query_row(params![key], |row| row.get(0)).optional())
+        .map_err(|e| format!("读取变更日志状态失败: {}", e))
+}
+
+fn write_meta(conn: &Connection, key: &str, value: &str) -> Result<(), String> {
+    conn.prepare_cached("INSERT OR REPLACE INTO change_log_meta (key, value) VALUES (?1, ?2)")
+        .and_then(|mut stmt| stmt.execute(params![key, value]))
+        .map_err(|e| format!("保存变更日志状态失败: {}", e))?;
+    Ok(())
+}
+
+/// 本机设备ID（首次使用时生成并保存在数据库中）
+pub(crate) fn local_device_id(conn: &Connection) -> Result<String, String> {
+    if let Some(device_id) = read_meta(conn, META_DEVICE_ID)? {
+        return Ok(device_id);
+    }
+
+    let device_id = format!("device-{}", generate_id());
+    write_meta(conn, META_DEVICE_ID, &device_id)?;
+    Ok(device_id)
+}
+
+/// 追加一条变更记录（应与数据修改使用同一连接、同一事务）
+///
+/// # Returns
+/// 新记录的序号
+pub(crate) fn append_change(
+    conn: &Connection,
+    item_id: &str,
+    change_type: &str,
+    fields: &[&str],
+) -> Result<i64, String> {
+    let device_id = local_device_id(conn)?;
+    let fields = serde_json::to_string(fields).map_err(|e| format!("序列化失败: {}", e))?;
+    let created_at = chrono::Utc::now().timestamp_millis();
+
+    conn.prepare_cached(
+        "INSERT INTO history_changes (item_id, change_type, fields, device_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )
+    .and_then(|mut stmt| stmt.execute(params![item_id, change_type, fields, device_id, created_at]))
+    .map_err(|e| format!("写入变更日志失败: {}", e))?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+/// 读取游标之后的变更记录
+fn read_changes_after(
+    conn: &Connection,
+    cursor: i64,
+    limit: Option<i64>,
+) -> Result<Vec<ChangeLogEntry>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT seq, item_id, change_type, fields, device_id, created_at
+             FROM history_changes WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![cursor, limit.unwrap_or(-1)], |row| {
+            let fields: String = row.get(3)?;
+            Ok(ChangeLogEntry {
+                seq: row.get(0)?,
+                item_id: row.get(1)?,
+                change_type: row.get(2)?,
+                fields: serde_json::from_str(&fields).unwrap_or_default(),
+                device_id: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("读取变更日志失败: {}", e))?;
+
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("读取变更日志失败: {}", e))
+}
+
+/// 读取已确认的游标
+fn read_cursor(conn: &Connection) -> Result<i64, String> {
+    Ok(read_meta(conn, META_CURSOR)?
+        .and_then(|cursor| cursor.parse().ok())
+        .unwrap_or(0))
+}
+
+/// 确认到指定序号，并清理已确认的记录
+fn acknowledge(conn: &Connection, seq: i64) -> Result<usize, String> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+
+    // 游标只前进不后退
+    if seq > read_cursor(&tx)? {
+        write_meta(&tx, META_CURSOR, &seq.to_string())?;
+    }
+
+    let removed = tx
+        .execute("DELETE FROM history_changes WHERE seq <= ?1", params![seq])
+        .map_err(|e| format!("清理变更日志失败: {}", e))?;
+
+    tx.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+    Ok(removed)
+}
+
+impl DatabaseManager {
+    /// 读取尚未确认的变更记录（按序号升序）
+    ///
+    /// # Arguments
+    /// * `limit` - 最多返回的数量（None 表示全部）
+    pub fn read_pending_changes(&self, limit: Option<i64>) -> Result<Vec<ChangeLogEntry>, String> {
+        let conn = self.get_connection()?;
+        let cursor = read_cursor(&conn)?;
+        read_changes_after(&conn, cursor, limit)
+    }
+
+    /// 确认已处理到指定序号的变更，并清理这些记录
+    /// 确认之后产生的新记录不受影响
+    ///
+    /// # Returns
+    /// 清理的记录数量
+    pub fn acknowledge_changes(&self, seq: i64) -> Result<usize, String> {
+        let conn = self.get_connection()?;
+        acknowledge(&conn, seq)
+    }
+
+    /// 获取本机设备ID
+    pub fn get_device_id(&self) -> Result<String, String> {
+        let conn = self.get_connection()?;
+        local_device_id(&conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(CHANGE_LOG_SCHEMA).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_changes_are_read_by_cursor_and_compacted() {
+        let conn = setup();
+
+        let first = append_change(&conn, "a", "insert", &[]).unwrap();
+        let second = append_change(&conn, "b", "note", &["note"]).unwrap();
+        assert!(second > first);
+
+        let pending = read_changes_after(&conn, read_cursor(&conn).unwrap(), None).unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[1].fields, vec!["note"]);
+        assert_eq!(pending[0].device_id, pending[1].device_id);
+
+        // 读取之后又产生的变更不会被确认
+        let third = append_change(&conn, "a", "favorite", &["favorite"]).unwrap();
+        assert_eq!(acknowledge(&conn, pending[1].seq).unwrap(), 2);
+
+        let pending = read_changes_after(&conn, read_cursor(&conn).unwrap(), None).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].seq, third);
+
+        // 清理后序号仍然递增
+        acknowledge(&conn, third).unwrap();
+        assert!(append_change(&conn, "c", "insert", &[]).unwrap() > third);
+    }
+
+    #[test]
+    fn test_rolled_back_mutation_leaves_no_entry() {
+        let conn = setup();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        append_change(&tx, "a", "insert", &[]).unwrap();
+        drop(tx);
+
+        assert!(read_changes_after(&conn, 0, None).unwrap().is_empty());
+    }
+}
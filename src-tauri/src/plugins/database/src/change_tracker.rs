@@ -1,10 +1,10 @@
 //! 数据库内部状态跟踪器
 //! 统一管理所有项目的变更跟踪和同步状态
+//! 需要同步的变更写入持久化的变更日志（见 change_log.rs）
 
+use crate::change_log::append_change;
 use rusqlite::{params, Connection};
 use serde::Serialize;
-use std::collections::HashSet;
-use std::sync::Mutex;
 
 /// 变更信息（用于事件通知）
 #[derive(Debug, Clone, Serialize)]
@@ -19,28 +19,20 @@ pub struct ChangeInfo {
 /// 数据库内部状态跟踪器
 /// 统一管理所有项目的变更跟踪和同步状态
 /// 当数据发生任何变更时（内容、类型、收藏、备注等），统一更新状态和时间戳
-#[derive(Debug)]
-pub struct ChangeTracker {
-    /// 存储已变更的项目ID
-    changed_items: Mutex<HashSet<String>>,
-}
+#[derive(Debug, Default)]
+pub struct ChangeTracker;
 
 impl ChangeTracker {
     /// 创建新的变更跟踪器
     pub fn new() -> Self {
-        Self {
-            changed_items: Mutex::new(HashSet::new()),
-        }
+        Self
     }
 
     /// 获取项目的当前同步状态
     fn get_current_sync_status(&self, conn: &Connection, item_id: &str) -> Result<String, String> {
         let status: String = conn
-            .query_row(
-                "SELECT syncStatus FROM history WHERE id = ?1",
-                params![item_id],
-                |row| row.get(0),
-            )
+            .prepare_cached("SELECT syncStatus FROM history WHERE id = ?1")
+            .and_then(|mut stmt| stmt.query_row(params![item_id], |row| row.get(0)))
             .unwrap_or_else(|_| "not_synced".to_string());
 
         Ok(status)
@@ -50,13 +42,16 @@ impl ChangeTracker {
     /// 当数据发生任何变更时，统一处理：
     /// 1. 更新时间戳
     /// 2. 如果当前状态是已同步且需要同步，则更新为已更改
-    /// 3. 记录变更
+    /// 3. 需要同步的变更追加到变更日志
     /// 4. 返回变更信息供前端通知
     ///
+    /// 调用方应在修改数据的同一事务中调用，保证数据和变更日志同时提交或回滚
+    ///
     /// # Arguments
-    /// * `conn` - 数据库连接
+    /// * `conn` - 数据库连接（或事务）
     /// * `item_id` - 项目ID
     /// * `change_type` - 变更类型（content, type, favorite, note等，用于日志记录）
+    /// * `fields` - 变更的字段
     /// * `ignore_for_sync` - 是否忽略此变更的同步标记（true=只更新时间戳，不标记为待同步）
     ///     - 适用于 time、position 等无实质内容变更的字段
     ///     - 不适用于 value、search、note、favorite 等有实质意义的变更
@@ -69,6 +64,7 @@ impl ChangeTracker {
         conn: &Connection,
         item_id: &str,
         change_type: &str,
+        fields: &[&str],
         ignore_for_sync: bool,
     ) -> Result<Option<ChangeInfo>, String> {
         let current_time = chrono::Utc::now().timestamp_millis();
@@ -77,19 +73,15 @@ impl ChangeTracker {
         let current_status = self.get_current_sync_status(conn, item_id)?;
 
         // 更新数据库：先更新时间戳
-        conn.execute(
-            "UPDATE history SET time = ?1 WHERE id = ?2",
-            params![current_time, item_id],
-        )
-        .map_err(|e| format!("更新时间戳失败: {}", e))?;
+        conn.prepare_cached("UPDATE history SET time = ?1 WHERE id = ?2")
+            .and_then(|mut stmt| stmt.execute(params![current_time, item_id]))
+            .map_err(|e| format!("更新时间戳失败: {}", e))?;
 
         // 如果当前状态是已同步且需要同步，则更新为已更改
         let new_status = if current_status == "synced" && !ignore_for_sync {
-            conn.execute(
-                "UPDATE history SET syncStatus = ?1 WHERE id = ?2",
-                params!["changed", item_id],
-            )
-            .map_err(|e| format!("更新同步状态失败: {}", e))?;
+            conn.prepare_cached("UPDATE history SET syncStatus = ?1 WHERE id = ?2")
+                .and_then(|mut stmt| stmt.execute(params!["changed", item_id]))
+                .map_err(|e| format!("更新同步状态失败: {}", e))?;
 
             log::info!("🔔 [{}] 项目已同步→已更改: {}", change_type, item_id);
             "changed".to_string()
@@ -103,10 +95,9 @@ impl ChangeTracker {
             current_status.clone()
         };
 
-        // 标记为已变更（只有需要同步的变更才加入变更列表）
+        // 记录到变更日志（只有需要同步的变更才记录）
         if !ignore_for_sync {
-            let mut items = self.changed_items.lock().unwrap();
-            items.insert(item_id.to_string());
+            append_change(conn, item_id, change_type, fields)?;
         }
 
         // 总是返回变更信息（任何字段变更都应该通知前端）
@@ -120,15 +111,9 @@ impl ChangeTracker {
 
     /// 标记项目为已同步（同步成功后调用）
     pub fn mark_item_synced(&self, conn: &Connection, item_id: &str) -> Result<(), String> {
-        conn.execute(
-            "UPDATE history SET syncStatus = ?1 WHERE id = ?2",
-            params!["synced", item_id],
-        )
-        .map_err(|e| format!("标记已同步失败: {}", e))?;
-
-        // 从变更列表中移除
-        let mut items = self.changed_items.lock().unwrap();
-        items.remove(item_id);
+        conn.prepare_cached("UPDATE history SET syncStatus = ?1 WHERE id = ?2")
+            .and_then(|mut stmt| stmt.execute(params!["synced", item_id]))
+            .map_err(|e| format!("标记已同步失败: {}", e))?;
 
         log::info!("🔔 项目已同步: {}", item_id);
 
@@ -142,27 +127,4 @@ impl ChangeTracker {
         }
         Ok(())
     }
-
-    /// 获取已变更项目的数量
-    pub fn count(&self) -> usize {
-        self.changed_items.lock().unwrap().len()
-    }
-
-    /// 获取所有已变更的项目ID
-    pub fn get_changed_items(&self) -> Vec<String> {
-        let items = self.changed_items.lock().unwrap();
-        items.iter().cloned().collect()
-    }
-
-    /// 清空所有变更记录
-    pub fn clear(&self) {
-        let mut items = self.changed_items.lock().unwrap();
-        items.clear();
-    }
-
-    /// 检查项目是否已变更
-    pub fn is_changed(&self, item_id: &str) -> bool {
-        let items = self.changed_items.lock().unwrap();
-        items.contains(item_id)
-    }
 }
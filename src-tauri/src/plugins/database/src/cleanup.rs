@@ -3,10 +3,34 @@
 //! 清理逻辑：
 //! 1. 清理超过保留天数的记录（不影响收藏）
 //! 2. 如果总数超过保留条数，清理最早的记录（不影响收藏）
-//! 3. 清理孤儿缓存文件（与历史记录关联的临时文件）
-
+//! 3. 按类型、子类型和来源应用匹配的保留策略，各自限制保留天数和条数（不影响收藏）
+//! 4. 图片和缓存文件超过磁盘配额时，从最早的非收藏记录开始清理
+//! 5. 彻底删除回收站中超过保留天数的记录
+//! 6. 回收不再被任何记录引用的媒体文件（见 `gc` 模块）
+//!
+//! 清理结果以 `CleanupReport` 返回，记录每条被删除的记录及原因
+//!
+//! 敏感内容另由后台任务按保留时长（`history.sensitiveTtl`，分钟）定期删除
+
+use crate::config::sensitive_ttl_minutes;
+use crate::delete::{DeleteManager, DeleteStrategy};
+use crate::events::{emit_changes, ChangeOperation, DatabaseChanges};
+use crate::filter::{SortField, SortInfo, SortOrder};
+use crate::gc::{get_cache_dir, media_dirs};
+use crate::models::{HistoryItem, QueryOptions};
+use crate::query::QueryCondition;
+use crate::sensitive::SENSITIVE_SUBTYPE;
+use crate::trash::TRASH_RETENTION_DAYS;
 use crate::DatabaseState;
-use tauri::State;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime, State};
+
+/// 敏感内容过期检查间隔
+const SENSITIVE_CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
 
 /// 清理规则配置
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -16,105 +40,263 @@ pub struct CleanupRule {
     pub retain_days: i32,
     /// 保留条数，0 表示不限制
     pub retain_count: i32,
+    /// 按类型、子类型和来源应用匹配的保留策略
+    #[serde(default)]
+    pub policies: Vec<RetentionPolicy>,
+    /// 图片和文件缓存的磁盘配额（字节），0 表示不限制
+    #[serde(default)]
+    pub max_media_bytes: u64,
 }
 
-/// 执行历史记录自动清理
-#[tauri::command]
-pub async fn cleanup_history(
-    rule: CleanupRule,
-    state: State<'_, DatabaseState>,
-) -> Result<(), String> {
-    let mut db = state.lock().await;
-    let mut deleted_count = 0usize;
+/// 保留策略
+/// 匹配条件为空时匹配所有记录，多个条件之间为 AND 关系
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// 内容类型（text / formatted / code / image / files 等）
+    #[serde(default, rename = "type")]
+    pub item_type: Option<String>,
+    /// 子类型
+    #[serde(default)]
+    pub subtype: Option<String>,
+    /// 来源应用名称
+    #[serde(default)]
+    pub source_app: Option<String>,
+    /// 保留天数，0 表示不限制
+    #[serde(default)]
+    pub retain_days: i32,
+    /// 保留条数，0 表示不限制
+    #[serde(default)]
+    pub retain_count: i32,
+}
 
-    // 1. 清理超过保留天数的记录（不影响收藏）
-    if rule.retain_days > 0 {
-        let cutoff_time = chrono::Utc::now() - chrono::Duration::days(rule.retain_days as i64);
+impl RetentionPolicy {
+    /// 查询策略匹配的记录（按时间从新到旧）
+    fn query_options(&self) -> QueryOptions {
+        let mut conditions = Vec::new();
+        if let Some(item_type) = &self.item_type {
+            conditions.push(QueryCondition::Type(item_type.clone()));
+        }
+        if let Some(subtype) = &self.subtype {
+            conditions.push(QueryCondition::SubtypeIn(vec![subtype.clone()]));
+        }
+        if let Some(source_app) = &self.source_app {
+            conditions.push(QueryCondition::SourceApp(source_app.clone()));
+        }
 
-        deleted_count += delete_items_by_time_condition(&mut db, cutoff_time.timestamp_millis())?;
+        QueryOptions {
+            conditions,
+            sort: Some(SortInfo {
+                field: SortField::Time,
+                order: SortOrder::Desc,
+            }),
+            exclude_deleted: true,
+            ..Default::default()
+        }
     }
+}
 
-    // 2. 如果总数超过保留条数，清理最早的记录（不影响收藏）
-    if rule.retain_count > 0 {
-        let current_count = get_active_count(&db)?;
-        let excess_count = current_count.saturating_sub(rule.retain_count as usize);
+/// 清理原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanupReason {
+    /// 超过全局保留天数
+    RetainDays,
+    /// 超过全局保留条数
+    RetainCount,
+    /// 超过保留策略的天数
+    PolicyDays,
+    /// 超过保留策略的条数
+    PolicyCount,
+    /// 超过磁盘配额
+    DiskQuota,
+}
 
-        if excess_count > 0 {
-            deleted_count += delete_oldest_non_favorites(&mut db, excess_count)?;
-        }
+/// 被清理的记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupEntry {
+    pub id: String,
+    pub item_type: Option<String>,
+    pub reason: CleanupReason,
+    /// 触发清理的保留策略（在 `CleanupRule::policies` 中的序号）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<usize>,
+    /// 释放的磁盘空间（字节，仅磁盘配额清理）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reclaimed_bytes: Option<u64>,
+}
+
+/// 清理报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    /// 被清理的记录
+    pub removed: Vec<CleanupEntry>,
+    /// 按配额清理释放的磁盘空间（字节）
+    pub reclaimed_bytes: u64,
+    /// 清理后媒体文件占用的空间（字节，未设置配额时为 0）
+    pub media_bytes: u64,
+    /// 删除的孤儿媒体文件数量
+    pub cache_files_removed: usize,
+}
+
+impl CleanupReport {
+    fn record(&mut self, items: &[HistoryItem], reason: CleanupReason, policy: Option<usize>) {
+        self.removed.extend(items.iter().map(|item| CleanupEntry {
+            id: item.id.clone(),
+            item_type: item.item_type.clone(),
+            reason,
+            policy,
+            reclaimed_bytes: None,
+        }));
     }
+}
 
-    // 3. 清理孤儿缓存文件
-    cleanup_stale_cache_files(&db);
+/// 执行历史记录自动清理，返回清理报告
+#[tauri::command]
+pub async fn cleanup_history<R: Runtime>(
+    rule: CleanupRule,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<CleanupReport, String> {
+    let db = state.read().await;
+    let report = run_cleanup(&db, &rule, &media_dirs(&app_handle))?;
 
     log::info!(
-        "历史记录自动清理完成: 删除 {} 条记录 (保留天数={}, 保留条数={})",
-        deleted_count,
+        "历史记录自动清理完成: 删除 {} 条记录，释放 {} 字节 (保留天数={}, 保留条数={}, 策略={}, 配额={})",
+        report.removed.len(),
+        report.reclaimed_bytes,
         rule.retain_days,
-        rule.retain_count
+        rule.retain_count,
+        rule.policies.len(),
+        rule.max_media_bytes
     );
 
-    Ok(())
+    // 清理的记录合并为一次变更事件
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(
+            ChangeOperation::Deleted,
+            report.removed.iter().map(|entry| entry.id.as_str()),
+            &[],
+        ),
+    );
+
+    Ok(report)
 }
 
-/// 删除指定时间之前的非收藏项目
-fn delete_items_by_time_condition(
-    db: &mut crate::DatabaseManager,
-    cutoff_time: i64,
-) -> Result<usize, String> {
-    let conn = db.get_connection()?;
+/// 按规则清理历史记录
+/// 1. 全局保留天数、保留条数（不影响收藏）
+/// 2. 逐条应用保留策略（不影响收藏）
+/// 3. 媒体文件超过磁盘配额时，从最早的非收藏记录开始清理
+/// 4. 彻底删除回收站中的过期记录
+/// 5. 回收媒体目录中的孤儿文件
+pub fn run_cleanup(
+    db: &crate::DatabaseManager,
+    rule: &CleanupRule,
+    media_dirs: &[PathBuf],
+) -> Result<CleanupReport, String> {
+    let mut report = CleanupReport::default();
+    let now = chrono::Utc::now().timestamp_millis();
+
+    let global = RetentionPolicy {
+        retain_days: rule.retain_days,
+        retain_count: rule.retain_count,
+        ..Default::default()
+    };
+    let (by_days, by_count) = apply_policy(db, &global, now)?;
+    report.record(&by_days, CleanupReason::RetainDays, None);
+    report.record(&by_count, CleanupReason::RetainCount, None);
+
+    for (index, policy) in rule.policies.iter().enumerate() {
+        let (by_days, by_count) = apply_policy(db, policy, now)?;
+        report.record(&by_days, CleanupReason::PolicyDays, Some(index));
+        report.record(&by_count, CleanupReason::PolicyCount, Some(index));
+    }
 
-    // 标记删除超过 cutoff_time 的非收藏记录
-    let sql = r#"
-        UPDATE history SET deleted = 1, time = ?
-        WHERE (deleted IS NULL OR deleted = 0)
-          AND favorite = 0
-          AND time < ?
-    "#;
+    if rule.max_media_bytes > 0 {
+        let (entries, media_bytes) = enforce_media_quota(db, rule.max_media_bytes)?;
+        report.reclaimed_bytes = entries.iter().filter_map(|e| e.reclaimed_bytes).sum();
+        report.media_bytes = media_bytes;
+        report.removed.extend(entries);
+    }
 
-    let current_time = chrono::Utc::now().timestamp_millis();
-    let count = conn
-        .execute(sql, [current_time, cutoff_time])
-        .map_err(|e| format!("删除失败: {}", e))?;
+    match db.purge_trash(TRASH_RETENTION_DAYS) {
+        Ok(ids) if ids.is_empty() => {}
+        Ok(ids) => log::info!("[Database] 回收站中 {} 条过期记录已彻底删除", ids.len()),
+        Err(e) => log::warn!("[Database] 清理回收站失败: {}", e),
+    }
 
-    Ok(count)
+    report.cache_files_removed = match db.collect_media_garbage(media_dirs, false) {
+        Ok(gc) => gc.removed.len(),
+        Err(e) => {
+            log::warn!("[Database] 回收孤儿媒体文件失败: {}", e);
+            0
+        }
+    };
+
+    Ok(report)
 }
 
-/// 删除最早的非收藏项目
-fn delete_oldest_non_favorites(
-    db: &mut crate::DatabaseManager,
-    limit: usize,
-) -> Result<usize, String> {
-    let conn = db.get_connection()?;
+/// 应用一条保留策略
+///
+/// # Returns
+/// 因超过保留天数、超过保留条数而删除的记录
+fn apply_policy(
+    db: &crate::DatabaseManager,
+    policy: &RetentionPolicy,
+    now: i64,
+) -> Result<(Vec<HistoryItem>, Vec<HistoryItem>), String> {
+    if policy.retain_days <= 0 && policy.retain_count <= 0 {
+        return Ok((Vec::new(), Vec::new()));
+    }
 
-    // 先查询出要删除的ID列表
-    let sql = r#"
-        SELECT id FROM history
-        WHERE (deleted IS NULL OR deleted = 0)
-          AND favorite = 0
-        ORDER BY time ASC
-        LIMIT ?
-    "#;
+    let items = db.query_history(policy.query_options())?;
+    let (by_days, by_count) = select_expired(items, policy, now);
 
-    let mut stmt = conn
-        .prepare(sql)
-        .map_err(|e| format!("准备查询失败: {}", e))?;
-    let rows = stmt
-        .query_map([limit as i32], |row| row.get::<_, String>(0))
-        .map_err(|e| format!("查询失败: {}", e))?;
-
-    let mut ids: Vec<String> = Vec::new();
-    for row in rows {
-        match row {
-            Ok(id) => ids.push(id),
-            Err(e) => log::warn!("解析ID失败: {}", e),
+    let ids: Vec<String> = by_days.iter().chain(&by_count).map(|i| i.id.clone()).collect();
+    mark_items_deleted(db, &ids)?;
+
+    Ok((by_days, by_count))
+}
+
+/// 从按时间从新到旧排列的记录中选出超过保留天数和保留条数的非收藏记录
+/// 收藏计入保留条数，但不会被删除
+fn select_expired(
+    items: Vec<HistoryItem>,
+    policy: &RetentionPolicy,
+    now: i64,
+) -> (Vec<HistoryItem>, Vec<HistoryItem>) {
+    let cutoff_time = (policy.retain_days > 0)
+        .then(|| now - chrono::Duration::days(policy.retain_days as i64).num_milliseconds());
+
+    let (by_days, mut remaining): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| {
+        item.favorite == 0 && cutoff_time.is_some_and(|cutoff| item.time < cutoff)
+    });
+
+    let mut by_count = Vec::new();
+    if policy.retain_count > 0 {
+        let excess = remaining.len().saturating_sub(policy.retain_count as usize);
+        while by_count.len() < excess {
+            match remaining.iter().rposition(|item| item.favorite == 0) {
+                Some(index) => by_count.push(remaining.remove(index)),
+                None => break,
+            }
         }
     }
 
+    (by_days, by_count)
+}
+
+/// 标记删除（软删除）
+fn mark_items_deleted(db: &crate::DatabaseManager, ids: &[String]) -> Result<usize, String> {
     if ids.is_empty() {
         return Ok(0);
     }
 
+    let conn = db.get_connection()?;
+
     // 批量标记删除
     let current_time = chrono::Utc::now().timestamp_millis();
     let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
@@ -124,7 +306,7 @@ fn delete_oldest_non_favorites(
     );
 
     let mut params: Vec<&dyn rusqlite::ToSql> = vec![&current_time];
-    for id in &ids {
+    for id in ids {
         params.push(id);
     }
 
@@ -135,119 +317,317 @@ fn delete_oldest_non_favorites(
     Ok(count)
 }
 
-/// 获取活跃记录总数（未删除）
-fn get_active_count(db: &crate::DatabaseManager) -> Result<usize, String> {
-    let conn = db.get_connection()?;
-
-    let count: i32 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM history WHERE deleted IS NULL OR deleted = 0",
-            [],
-            |row| row.get(0),
-        )
-        .map_err(|e| format!("查询失败: {}", e))?;
+/// 记录占用的媒体文件（图片，以及缓存目录中的文件）
+fn media_files_of(item: &HistoryItem, cache_dir: &Path) -> Vec<String> {
+    let Some(value) = item.value.as_deref() else {
+        return Vec::new();
+    };
 
-    Ok(count as usize)
+    match item.item_type.as_deref() {
+        Some("image") => vec![value.to_string()],
+        // 文件列表中只有缓存目录里的文件由应用管理，用户原始文件不计入配额
+        Some("files") => serde_json::from_str::<Vec<String>>(value)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|path| Path::new(path).starts_with(cache_dir))
+            .collect(),
+        _ => Vec::new(),
+    }
 }
 
-/// 获取缓存目录路径
-fn get_cache_dir() -> Result<std::path::PathBuf, String> {
-    let mut cache_dir = std::env::temp_dir();
-    cache_dir.push("eco-paste-files");
+/// 媒体文件超过磁盘配额时，从最早的非收藏记录开始删除，直到低于配额
+/// 记录直接硬删除（不进入回收站）以立即释放文件，墓碑在下次同步时删除云端副本
+///
+/// # Returns
+/// 被删除的记录，以及清理后媒体文件占用的空间
+fn enforce_media_quota(
+    db: &crate::DatabaseManager,
+    max_bytes: u64,
+) -> Result<(Vec<CleanupEntry>, u64), String> {
+    let cache_dir = get_cache_dir()?;
+    let options = QueryOptions {
+        sort: Some(SortInfo {
+            field: SortField::Time,
+            order: SortOrder::Asc,
+        }),
+        exclude_deleted: true,
+        ..Default::default()
+    };
+    let items: Vec<(HistoryItem, Vec<String>)> = db
+        .query_history(options)?
+        .into_iter()
+        .map(|item| {
+            let files = media_files_of(&item, &cache_dir);
+            (item, files)
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+
+    let (victims, media_bytes) = select_quota_victims(&items, max_bytes, |path| {
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    });
+
+    if media_bytes > max_bytes {
+        log::warn!(
+            "[Database] 清理后媒体文件仍超过配额（{} > {} 字节），剩余均为收藏",
+            media_bytes,
+            max_bytes
+        );
+    }
+
+    if victims.is_empty() {
+        return Ok((Vec::new(), media_bytes));
+    }
 
-    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+    let ids: Vec<String> = victims.iter().map(|(item, _)| item.id.clone()).collect();
+    let result = DeleteManager::delete_items(db, &ids, DeleteStrategy::Hard)?;
+    for error in &result.errors {
+        log::warn!("[Database] 配额清理失败: {}", error);
+    }
 
-    Ok(cache_dir)
+    let entries = victims
+        .into_iter()
+        .filter(|(item, _)| {
+            result.soft_deleted_ids.contains(&item.id) || result.hard_deleted_ids.contains(&item.id)
+        })
+        .map(|(item, reclaimed)| CleanupEntry {
+            id: item.id.clone(),
+            item_type: item.item_type.clone(),
+            reason: CleanupReason::DiskQuota,
+            policy: None,
+            reclaimed_bytes: Some(reclaimed),
+        })
+        .collect();
+
+    Ok((entries, media_bytes))
 }
 
-/// 递归收集目录中的所有文件
-fn collect_files_recursive(dir: &std::path::Path, files: &mut Vec<String>) {
-    if let Ok(entries) = std::fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(path_str) = path.to_str().map(|s| s.to_string()) {
-                    files.push(path_str);
+/// 选出需要删除的记录（按时间从旧到新排列，跳过收藏）
+/// 多条记录共用同一文件时（相同图片），只有最后一条引用被删除才释放空间
+///
+/// # Returns
+/// 被选中的记录及各自释放的空间，以及删除后的媒体文件总大小
+fn select_quota_victims<'a>(
+    items: &'a [(HistoryItem, Vec<String>)],
+    max_bytes: u64,
+    file_size: impl Fn(&str) -> u64,
+) -> (Vec<(&'a HistoryItem, u64)>, u64) {
+    let mut refs: HashMap<&str, (usize, u64)> = HashMap::new();
+    for (_, files) in items {
+        for path in files {
+            refs.entry(path.as_str())
+                .or_insert_with(|| (0, file_size(path)))
+                .0 += 1;
+        }
+    }
+
+    let mut total: u64 = refs.values().map(|(_, size)| size).sum();
+    let mut victims = Vec::new();
+
+    for (item, files) in items {
+        if total <= max_bytes {
+            break;
+        }
+        if item.favorite == 1 {
+            continue;
+        }
+
+        let mut reclaimed = 0;
+        for path in files {
+            if let Some((count, size)) = refs.get_mut(path.as_str()) {
+                *count -= 1;
+                if *count == 0 {
+                    reclaimed += *size;
                 }
-            } else if path.is_dir() {
-                collect_files_recursive(&path, files);
             }
         }
+
+        total -= reclaimed;
+        victims.push((item, reclaimed));
     }
+
+    (victims, total)
 }
 
-/// 清理孤儿缓存文件
-/// 递归扫描缓存目录，删除不在数据库中的文件（这些是已删除项目的缓存）
-fn cleanup_stale_cache_files(db: &crate::DatabaseManager) {
-    log::info!("[Database] 开始清理缓存文件...");
 
-    let cache_dir = match get_cache_dir() {
-        Ok(path) => path,
-        Err(e) => {
-            log::warn!("[Database] 无法获取缓存目录，跳过清理: {}", e);
-            return;
-        }
-    };
+/// 查询超过保留时长的敏感内容（不影响收藏）
+fn expired_sensitive_ids(conn: &Connection, cutoff_time: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id FROM history
+             WHERE subtype = ?1
+               AND (deleted IS NULL OR deleted = 0)
+               AND favorite = 0
+               AND time < ?2",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let ids = stmt
+        .query_map(params![SENSITIVE_SUBTYPE, cutoff_time], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(|e| format!("查询过期敏感内容失败: {}", e))?;
+
+    Ok(ids)
+}
 
-    if !cache_dir.exists() {
-        log::info!("[Database] 缓存目录不存在，无需清理");
-        return;
+/// 删除超过保留时长的敏感内容
+/// 直接硬删除（不进入回收站），墓碑在下次同步时删除云端副本
+///
+/// # Arguments
+/// * `ttl_minutes` - 保留时长（分钟），不大于 0 时不删除
+///
+/// # Returns
+/// 删除的记录ID
+pub fn delete_expired_sensitive(
+    db: &crate::DatabaseManager,
+    ttl_minutes: i64,
+) -> Result<Vec<String>, String> {
+    if ttl_minutes <= 0 {
+        return Ok(Vec::new());
     }
 
-    // 递归获取缓存目录中的所有文件（包括子目录）
-    let mut cache_files = Vec::new();
-    collect_files_recursive(&cache_dir, &mut cache_files);
+    let cutoff_time = chrono::Utc::now().timestamp_millis() - ttl_minutes * 60 * 1000;
+    let ids = {
+        let conn = db.get_connection()?;
+        expired_sensitive_ids(&conn, cutoff_time)?
+    };
 
-    if cache_files.is_empty() {
-        log::info!("[Database] 缓存目录为空，无需清理");
-        return;
+    if ids.is_empty() {
+        return Ok(Vec::new());
     }
 
-    log::info!("[Database] 缓存目录中有 {} 个文件", cache_files.len());
+    let result = DeleteManager::delete_items(db, &ids, DeleteStrategy::Hard)?;
+    let mut deleted = result.soft_deleted_ids;
+    deleted.extend(result.hard_deleted_ids);
+    Ok(deleted)
+}
 
-    // 获取数据库中所有文件记录的本地路径
-    let options = crate::QueryOptions {
-        where_clause: None,
-        order_by: None,
-        limit: None,
-        offset: None,
-        only_favorites: false,
-        exclude_deleted: false,
-        params: None,
-    };
+/// 启动敏感内容过期清理任务
+/// 每分钟检查一次，保留时长每次从配置中重新读取
+pub fn start_sensitive_cleanup<R: Runtime>(app_handle: AppHandle<R>, state: DatabaseState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SENSITIVE_CLEANUP_INTERVAL);
 
-    let cache_dir_str = cache_dir.to_string_lossy().to_string();
-    let db_files: std::collections::HashSet<String> = match db.query_history(options) {
-        Ok(items) => items
-            .iter()
-            .filter(|item| {
-                item.item_type.as_deref() == Some("files")
-                    || item.item_type.as_deref() == Some("image")
-            })
-            .filter_map(|item| item.value.clone())
-            .filter(|v| v.starts_with(&cache_dir_str))
-            .collect(),
-        Err(e) => {
-            log::error!("[Database] 查询数据库失败: {}", e);
-            return;
-        }
-    };
+        loop {
+            interval.tick().await;
 
-    // 找出不在数据库中的缓存文件（孤儿文件）
-    let mut orphaned_count = 0;
-    for cache_file in &cache_files {
-        if !db_files.contains(cache_file) {
-            match std::fs::remove_file(cache_file) {
-                Ok(_) => {
-                    log::info!("[Database] 已删除孤儿缓存: {}", cache_file);
-                    orphaned_count += 1;
-                }
-                Err(e) => {
-                    log::warn!("[Database] 删除缓存失败: {} ({})", cache_file, e);
+            let ttl_minutes = sensitive_ttl_minutes(&app_handle);
+            if ttl_minutes <= 0 {
+                continue;
+            }
+
+            let db = state.read().await;
+            if !db.is_initialized() {
+                continue;
+            }
+
+            match delete_expired_sensitive(&db, ttl_minutes) {
+                Ok(ids) if ids.is_empty() => {}
+                Ok(ids) => {
+                    log::info!("[Database] 已删除 {} 条过期敏感内容", ids.len());
+                    emit_changes(
+                        &app_handle,
+                        DatabaseChanges::single(ChangeOperation::Deleted, ids, &[]),
+                    );
                 }
+                Err(e) => log::error!("[Database] 清理过期敏感内容失败: {}", e),
             }
         }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HISTORY_SCHEMA;
+
+    #[test]
+    fn test_expired_sensitive_ids() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(HISTORY_SCHEMA).unwrap();
+
+        let rows = [
+            ("expired", Some(SENSITIVE_SUBTYPE), 0, 0, 100),
+            ("fresh", Some(SENSITIVE_SUBTYPE), 0, 0, 300),
+            ("favorite", Some(SENSITIVE_SUBTYPE), 1, 0, 100),
+            ("deleted", Some(SENSITIVE_SUBTYPE), 0, 1, 100),
+            ("plain", None, 0, 0, 100),
+        ];
+        for (id, subtype, favorite, deleted, time) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, value, subtype, favorite, deleted, time)
+                 VALUES (?1, 'text', 'secret', ?2, ?3, ?4, ?5)",
+                params![id, subtype, favorite, deleted, time],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            expired_sensitive_ids(&conn, 200).unwrap(),
+            vec!["expired".to_string()]
+        );
+    }
+
+    fn item(id: &str, favorite: i32, time: i64) -> HistoryItem {
+        HistoryItem {
+            id: id.to_string(),
+            favorite,
+            time,
+            ..Default::default()
+        }
     }
 
-    log::info!("[Database] 缓存清理完成，删除 {} 个文件", orphaned_count);
+    fn ids(items: &[HistoryItem]) -> Vec<&str> {
+        items.iter().map(|item| item.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_select_expired_keeps_favorites() {
+        let day = chrono::Duration::days(1).num_milliseconds();
+        let now = 10 * day;
+        // 按时间从新到旧
+        let items = vec![
+            item("a", 0, now),
+            item("b", 1, now - day),
+            item("c", 0, now - 2 * day),
+            item("d", 0, now - 3 * day),
+            item("old", 0, now - 8 * day),
+            item("old_favorite", 1, now - 9 * day),
+        ];
+
+        let policy = RetentionPolicy {
+            retain_days: 7,
+            retain_count: 3,
+            ..Default::default()
+        };
+        let (by_days, by_count) = select_expired(items, &policy, now);
+
+        assert_eq!(ids(&by_days), vec!["old"]);
+        // 剩余 5 条（含 2 条收藏）超出 2 条，删除最早的非收藏
+        assert_eq!(ids(&by_count), vec!["d", "c"]);
+    }
+
+    #[test]
+    fn test_quota_evicts_oldest_non_favorites() {
+        let files = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        // 按时间从旧到新，shared.png 被两条记录引用
+        let items = vec![
+            (item("favorite", 1, 1), files(&["f.png"])),
+            (item("first", 0, 2), files(&["shared.png"])),
+            (item("second", 0, 3), files(&["a.png"])),
+            (item("third", 0, 4), files(&["shared.png"])),
+            (item("newest", 0, 5), files(&["b.png"])),
+        ];
+        let size = |path: &str| if path == "shared.png" { 50 } else { 30 };
+
+        let (victims, total) = select_quota_victims(&items, 110, size);
+        let victim_ids: Vec<(&str, u64)> = victims
+            .iter()
+            .map(|(item, reclaimed)| (item.id.as_str(), *reclaimed))
+            .collect();
+
+        // 删除 first 不释放共享文件，继续删除 second 后低于配额
+        assert_eq!(victim_ids, vec![("first", 0), ("second", 30)]);
+        assert_eq!(total, 110);
+    }
 }
@@ -1,9 +1,13 @@
 //! 数据库命令实现
 //! 提供前端调用的完整 API
 
-use crate::{DatabaseState, HistoryItem, InsertItem, InsertResult, QueryOptions, SyncDataItem};
+use crate::{
+    emit_changes, mask_sensitive_items, mask_sensitive_results, record_delete_batch,
+    ChangeOperation, DatabaseChanges, DatabaseState, HistoryItem, InsertItem, InsertResult,
+    QueryCondition, QueryCursor, QueryOptions, SearchResult, SortField, SortInfo, SortOrder,
+    SyncDataItem,
+};
 use crate::config::should_auto_sort;
-use rusqlite::params;
 use tauri::State;
 
 /// 查询历史记录
@@ -15,17 +19,23 @@ pub fn query_history(
     offset: Option<i32>,
     state: State<'_, DatabaseState>,
 ) -> Result<Vec<HistoryItem>, String> {
-    let db = state.blocking_lock();
+    let db = state.blocking_read();
     let options = QueryOptions {
         only_favorites,
         exclude_deleted,
         limit,
         offset,
-        order_by: Some("time DESC".to_string()),
-        where_clause: None,
-        params: None,
+        sort: Some(SortInfo {
+            field: SortField::Time,
+            order: SortOrder::Desc,
+        }),
+        conditions: Vec::new(),
+        cursor: None,
+        preview_length: None,
     };
-    db.query_history(options)
+    let mut items = db.query_history(options)?;
+    mask_sensitive_items(&mut items);
+    Ok(items)
 }
 
 use serde::Deserialize;
@@ -36,23 +46,68 @@ pub fn query_history_with_filter(
     args: QueryFilterArgs,
     state: State<'_, DatabaseState>,
 ) -> Result<Vec<HistoryItem>, String> {
-    let db = state.blocking_lock();
+    let db = state.blocking_read();
     let options = QueryOptions {
         only_favorites: false,
         exclude_deleted: true,
-        limit: None,
-        offset: None,
-        order_by: None,
-        where_clause: args.where_clause,
-        params: args.params,
+        limit: args.limit,
+        offset: args.offset,
+        sort: args.sort,
+        conditions: args.conditions,
+        cursor: args.cursor,
+        preview_length: args.preview_length,
     };
-    db.query_history(options)
+    let mut items = db.query_history(options)?;
+    mask_sensitive_items(&mut items);
+    Ok(items)
 }
 
+/// 筛选查询参数（结构化条件，由后端生成参数化 SQL）
 #[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct QueryFilterArgs {
-    where_clause: Option<String>,
-    params: Option<Vec<String>>,
+    #[serde(default)]
+    conditions: Vec<QueryCondition>,
+    #[serde(default)]
+    sort: Option<SortInfo>,
+    #[serde(default)]
+    limit: Option<i32>,
+    #[serde(default)]
+    offset: Option<i32>,
+    /// 上一页最后一条记录，不能与 sort 和 offset 同时设置
+    #[serde(default)]
+    cursor: Option<QueryCursor>,
+    /// 预览长度，设置后文本内容被截断（`truncated` 为 true）
+    #[serde(default)]
+    preview_length: Option<u32>,
+}
+
+/// 读取记录的完整内容（用于预览查询中被截断的记录）
+/// 敏感内容同样被遮盖，原始内容通过 `reveal_sensitive` 读取
+#[tauri::command]
+pub async fn get_item_content(
+    id: String,
+    state: State<'_, DatabaseState>,
+) -> Result<HistoryItem, String> {
+    let db = state.read().await;
+    let mut item = db.get_item_content(&id)?;
+    item.mask_sensitive();
+    Ok(item)
+}
+
+/// 全文搜索历史记录
+/// 按相关度排序，返回匹配摘要及高亮区间
+#[tauri::command]
+pub fn search_history(
+    query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<SearchResult>, String> {
+    let db = state.blocking_read();
+    let mut results = db.search_history(&query, limit.unwrap_or(100), offset.unwrap_or(0))?;
+    mask_sensitive_results(&mut results);
+    Ok(results)
 }
 
 /// 插入数据（带去重功能）
@@ -62,31 +117,39 @@ pub fn insert_with_deduplication<R: tauri::Runtime>(
     state: State<'_, DatabaseState>,
     app_handle: tauri::AppHandle<R>,
 ) -> Result<InsertResult, String> {
-    let db = state.blocking_lock();
+    let db = state.blocking_read();
     db.insert_with_deduplication(&item, &app_handle)
 }
 
 /// 统一删除命令
 ///
-/// 根据同步状态决定删除方式：
-/// - 已同步 (sync_status == "synced")：软删除，标记 deleted=1，等待云端同步时删除
-/// - 未同步 (sync_status != "synced")：硬删除，直接从数据库删除
+/// 默认软删除（标记 deleted=1）放入回收站，可以恢复，最近一次删除可以撤销；
+/// 已同步的记录在下次同步时从云端删除
 ///
 /// # Arguments
 /// * `ids` - 要删除的项目ID列表（单个或批量）
-/// * `hard_delete` - 可选，是否强制硬删除（true=全部硬删除，false=全部软删除，None=根据sync_status自动判断）
+/// * `hard_delete` - 可选，是否强制硬删除（true=直接从数据库删除，false/None=放入回收站）
 #[tauri::command]
-pub fn delete_items(
+pub fn delete_items<R: tauri::Runtime>(
     ids: Vec<String>,
     state: State<'_, DatabaseState>,
     hard_delete: Option<bool>,
+    app_handle: tauri::AppHandle<R>,
 ) -> Result<crate::delete::DeleteResult, String> {
-    let mut db = state.blocking_lock();
-    crate::delete::DeleteManager::delete_items(
-        &mut db,
-        &ids,
-        crate::delete::DeleteStrategy::from_option(hard_delete),
-    )
+    let db = state.blocking_read();
+    let strategy = match hard_delete {
+        Some(true) => crate::delete::DeleteStrategy::Hard,
+        _ => crate::delete::DeleteStrategy::Soft,
+    };
+    let result = crate::delete::DeleteManager::delete_items(&db, &ids, strategy)?;
+    record_delete_batch(&result.soft_deleted_ids);
+
+    let mut changes = DatabaseChanges::new();
+    changes.record(ChangeOperation::Deleted, &result.soft_deleted_ids, &[]);
+    changes.record(ChangeOperation::Deleted, &result.hard_deleted_ids, &[]);
+    emit_changes(&app_handle, changes);
+
+    Ok(result)
 }
 
 /// 统一字段更新命令
@@ -99,147 +162,54 @@ pub fn update_field<R: tauri::Runtime>(
     state: State<'_, DatabaseState>,
     app_handle: tauri::AppHandle<R>,
 ) -> Result<(), String> {
-    let db = state.blocking_lock();
-    let current_time = chrono::Utc::now().timestamp_millis();
+    let db = state.blocking_read();
+    let current_time = chrono::Utc::now().timestamp_millis().to_string();
     let auto_sort = should_auto_sort(&app_handle);
 
-    // 验证字段名并更新
-    match field.as_str() {
+    // 验证字段名，确定需要更新的列
+    let (fields, ignore_for_sync): (Vec<(&str, String)>, bool) = match field.as_str() {
         "favorite" => {
             let bool_value = value == "1" || value.to_lowercase() == "true";
-            db.update_field(&id, "favorite", if bool_value { "1" } else { "0" })?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "favorite", false)?;
-        }
-        "note" => {
-            db.update_field(&id, "note", &value)?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "note", false)?;
+            let favorite = if bool_value { "1" } else { "0" };
+            (
+                vec![("favorite", favorite.to_string()), ("time", current_time)],
+                false,
+            )
         }
+        "note" => (vec![("note", value), ("time", current_time)], false),
         "content" => {
-            db.update_field(&id, "value", &value)?;
-            db.update_field(&id, "count", &value.len().to_string())?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "content", false)?;
-        }
-        "search" => {
-            db.update_field(&id, "search", &value)?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "search", false)?;
-        }
-        "type" => {
-            db.update_field(&id, "type", &value)?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "type", false)?;
-        }
-        "subtype" => {
-            db.update_field(&id, "subtype", &value)?;
-            db.update_field(&id, "time", &current_time.to_string())?;
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "subtype", false)?;
-        }
-        "time" => {
-            db.update_field(&id, "time", &value)?;
-            // 复制等操作更新时间戳时，根据 auto_sort 设置决定是否更新 position
-            if auto_sort {
-                let conn = db.get_connection()?;
-                let max_position: i32 = conn
-                    .query_row(
-                        "SELECT COALESCE(MAX(position), 0) FROM history",
-                        params![],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(0);
-                db.update_field(&id, "position", &(max_position + 1).to_string())?;
-            }
-            // 时间戳更新不标记为待同步（无实质内容变更）
-            let conn = db.get_connection()?;
-            db.get_change_tracker()
-                .mark_item_changed(&conn, &id, "time", true)?;
+            let count = value.len().to_string();
+            (
+                vec![("value", value), ("count", count), ("time", current_time)],
+                false,
+            )
         }
+        "search" => (vec![("search", value), ("time", current_time)], false),
+        "type" => (vec![("type", value), ("time", current_time)], false),
+        "subtype" => (vec![("subtype", value), ("time", current_time)], false),
+        // 复制等操作更新时间戳，不标记为待同步（无实质内容变更）
+        "time" => (vec![("time", value)], true),
         "syncStatus" => {
-            db.update_field(&id, "syncStatus", &value)?;
             // 同步状态变更本身不需要再次触发变更跟踪
+            db.update_field(&id, "syncStatus", &value)?;
+            emit_changes(
+                &app_handle,
+                DatabaseChanges::single(ChangeOperation::Synced, [id.as_str()], &["syncStatus"]),
+            );
+            return Ok(());
         }
         _ => return Err(format!("不支持的字段名: {}", field)),
-    }
+    };
+
+    // 字段更新、排序和变更日志在同一事务中提交
+    // 根据 auto_sort 设置决定是否将项目移动到顶部
+    db.update_fields_tracked(&id, &fields, auto_sort, &field, ignore_for_sync)?;
+
+    let columns: Vec<&str> = fields.iter().map(|(column, _)| *column).collect();
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(ChangeOperation::Updated, [id.as_str()], &columns),
+    );
 
     Ok(())
 }
@@ -251,15 +221,17 @@ pub fn query_for_sync(
     only_favorites: bool,
     include_images: bool,
     include_files: bool,
+    include_sensitive: Option<bool>,
     content_types: crate::ContentTypeFilter,
     sync_status_filter: Option<crate::SyncStatusFilter>,
     state: State<'_, DatabaseState>,
 ) -> Result<Vec<SyncDataItem>, String> {
-    let db = state.blocking_lock();
+    let db = state.blocking_read();
     db.query_for_sync(
         only_favorites,
         include_images,
         include_files,
+        include_sensitive.unwrap_or(false),
         content_types,
         sync_status_filter,
     )
@@ -84,6 +84,7 @@ pub struct ContentConfig {
     pub code_detection: Option<bool>,
     pub show_source_app: Option<bool>,
     pub color_detection: Option<bool>,
+    pub sensitive_detection: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -92,8 +93,13 @@ pub struct HistoryConfig {
     pub duration: Option<i32>,
     pub unit: Option<i32>,
     pub max_count: Option<i32>,
+    /// 敏感内容保留时长（分钟），0 表示不自动删除
+    pub sensitive_ttl: Option<i64>,
 }
 
+/// 敏感内容默认保留时长（分钟）
+pub const DEFAULT_SENSITIVE_TTL_MINUTES: i64 = 60;
+
 /// 读取完整配置
 pub fn read_config<R: Runtime>(app_handle: &AppHandle<R>) -> Result<AppConfig, String> {
     let config_path = get_config_path(app_handle).ok_or("无法获取配置路径".to_string())?;
@@ -156,3 +162,23 @@ pub fn should_paste_plain<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
         .and_then(|c| c.paste_plain)
         .unwrap_or(false) // 默认为 false
 }
+
+/// 检查是否开启敏感内容检测
+pub fn should_detect_sensitive<R: Runtime>(app_handle: &AppHandle<R>) -> bool {
+    read_config(app_handle)
+        .ok()
+        .and_then(|c| c.clipboard_store)
+        .and_then(|c| c.content)
+        .and_then(|c| c.sensitive_detection)
+        .unwrap_or(true) // 默认为 true
+}
+
+/// 获取敏感内容保留时长（分钟），0 表示不自动删除
+pub fn sensitive_ttl_minutes<R: Runtime>(app_handle: &AppHandle<R>) -> i64 {
+    read_config(app_handle)
+        .ok()
+        .and_then(|c| c.clipboard_store)
+        .and_then(|c| c.history)
+        .and_then(|c| c.sensitive_ttl)
+        .unwrap_or(DEFAULT_SENSITIVE_TTL_MINUTES)
+}
@@ -1,20 +1,97 @@
 //! 数据库管理器
 //! 提供 SQLite 数据库的统一访问接口
 
+use crate::blobs::{blob_dir_of, offload_content, offload_oversized_rows};
 use crate::config::{should_auto_sort, should_fetch_source_app};
 use crate::filter::{BaseFilter, ContentTypeFilter, DataFilter, SyncModeFilter, SyncStatusFilter};
+use crate::images::{find_duplicate_image, image_paths_of};
 use crate::models::{
     DatabaseStatistics, HistoryItem, InsertItem, InsertResult, QueryOptions, SyncDataItem,
 };
+use crate::migrations::run_migrations;
+use crate::pool::{enable_wal, ConnectionPool, PooledConnection};
+use crate::query::QueryCondition;
+use crate::snapshot::{snapshot_dir, take_snapshot, SnapshotKind};
 use crate::source_app::fetch_source_app_info_impl;
+use crate::tags::{item_tag_names, replace_item_tags_by_name};
+use crate::tombstone::record_tombstones;
 use crate::ChangeTracker;
 use rusqlite::{params, Connection};
+use tauri_plugin_eco_common::paths::is_dev_mode;
 use std::path::PathBuf;
 
+/// history 表结构
+pub(crate) const HISTORY_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS history (
+        id TEXT PRIMARY KEY,
+        type TEXT,
+        [group] TEXT,
+        value TEXT,
+        search TEXT,
+        count INTEGER DEFAULT 1,
+        width INTEGER,
+        height INTEGER,
+        favorite INTEGER DEFAULT 0,
+        time INTEGER DEFAULT 0,
+        note TEXT,
+        subtype TEXT,
+        deleted INTEGER DEFAULT 0,
+        syncStatus TEXT DEFAULT 'not_synced',
+        sourceAppName TEXT,
+        sourceAppIcon TEXT,
+        position INTEGER DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_history_deleted ON history(deleted);
+    CREATE INDEX IF NOT EXISTS idx_history_favorite ON history(favorite);
+    CREATE INDEX IF NOT EXISTS idx_history_syncStatus ON history(syncStatus);
+    CREATE INDEX IF NOT EXISTS idx_history_time ON history(time);
+"#;
+
+/// 将 history 表的一行转换为 HistoryItem
+/// 按列名读取，不依赖列的顺序，查询语句可以附加其他列
+pub(crate) fn history_item_from_row(row: &rusqlite::Row) -> rusqlite::Result<HistoryItem> {
+    Ok(HistoryItem {
+        id: row.get("id")?,
+        item_type: row.get("type").ok(),
+        group: row.get("group").ok(),
+        value: row.get("value").ok(),
+        search: row.get("search").ok(),
+        count: row.get("count").ok(),
+        width: row.get("width").ok(),
+        height: row.get("height").ok(),
+        favorite: row.get("favorite").unwrap_or(0),
+        time: row.get("time").unwrap_or(0),
+        note: row.get("note").ok(),
+        subtype: row.get("subtype").ok(),
+        deleted: row.get("deleted").ok(),
+        sync_status: row.get("syncStatus").ok(),
+        source_app_name: row.get("sourceAppName").ok().flatten(),
+        source_app_icon: row.get("sourceAppIcon").ok().flatten(),
+        position: row.get("position").ok().flatten(),
+        expires_at: row.get("expiresAt").ok().flatten(),
+        expire_on_paste: row
+            .get::<_, Option<i32>>("expireOnPaste")
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+            == 1,
+        truncated: row
+            .get::<_, Option<bool>>("truncated")
+            .ok()
+            .flatten()
+            .unwrap_or(false),
+        value_blob: row.get("valueBlob").ok().flatten(),
+        search_blob: row.get("searchBlob").ok().flatten(),
+    })
+}
+
 /// 数据库管理器
 pub struct DatabaseManager {
     /// 数据库路径
     db_path: Option<PathBuf>,
+    /// 连接池
+    pool: Option<ConnectionPool>,
     /// 是否已初始化
     initialized: bool,
     /// 内部状态跟踪器
@@ -26,6 +103,7 @@ impl DatabaseManager {
     pub fn new() -> Self {
         Self {
             db_path: None,
+            pool: None,
             initialized: false,
             change_tracker: ChangeTracker::new(),
         }
@@ -36,44 +114,25 @@ impl DatabaseManager {
     /// # Arguments
     /// * `db_path` - 数据库文件路径
     pub fn init(&mut self, db_path: PathBuf) -> Result<(), String> {
-        // 先克隆路径用于创建连接
-        let db_path_clone = db_path.clone();
+        // v0.6.x 旧版数据库改名备份，由迁移插件导入到新数据库
+        self.backup_legacy_database(&db_path)?;
 
-        // 创建数据库连接并初始化表结构
-        let conn =
-            Connection::open(&db_path_clone).map_err(|e| format!("打开数据库失败: {}", e))?;
-
-        // 创建 history 表
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS history (
-                id TEXT PRIMARY KEY,
-                type TEXT,
-                [group] TEXT,
-                value TEXT,
-                search TEXT,
-                count INTEGER DEFAULT 1,
-                width INTEGER,
-                height INTEGER,
-                favorite INTEGER DEFAULT 0,
-                time INTEGER DEFAULT 0,
-                note TEXT,
-                subtype TEXT,
-                deleted INTEGER DEFAULT 0,
-                syncStatus TEXT DEFAULT 'not_synced',
-                sourceAppName TEXT,
-                sourceAppIcon TEXT,
-                position INTEGER DEFAULT 0
-            );
+        // 创建数据库连接并迁移到最新的表结构
+        let mut conn =
+            Connection::open(&db_path).map_err(|e| format!("打开数据库失败: {}", e))?;
 
-            CREATE INDEX IF NOT EXISTS idx_history_deleted ON history(deleted);
-            CREATE INDEX IF NOT EXISTS idx_history_favorite ON history(favorite);
-            CREATE INDEX IF NOT EXISTS idx_history_syncStatus ON history(syncStatus);
-            CREATE INDEX IF NOT EXISTS idx_history_time ON history(time);
-        "#,
-        )
-        .map_err(|e| format!("创建数据库表失败: {}", e))?;
+        enable_wal(&conn)?;
+        run_migrations(&mut conn, Some(&db_path))?;
+
+        // 转存升级前保存在表中的大内容（转存失败不影响使用，下次启动时重试）
+        match offload_oversized_rows(&conn, &blob_dir_of(&db_path)) {
+            Ok(0) => {}
+            Ok(count) => log::info!("[Database] 已将 {} 条大内容转存到外部存储", count),
+            Err(e) => log::warn!("[Database] 转存大内容失败: {}", e),
+        }
+        drop(conn);
 
+        self.pool = Some(ConnectionPool::new(db_path.clone()));
         self.db_path = Some(db_path.clone());
         self.initialized = true;
 
@@ -82,13 +141,12 @@ impl DatabaseManager {
     }
 
     /// 获取数据库连接（公开方法，供外部使用）
-    pub fn get_connection(&self) -> Result<Connection, String> {
-        let path = self
-            .db_path
+    /// 连接来自连接池，离开作用域时自动归还
+    pub fn get_connection(&self) -> Result<PooledConnection, String> {
+        self.pool
             .as_ref()
-            .ok_or_else(|| "数据库路径未设置".to_string())?;
-
-        Connection::open(path).map_err(|e| format!("打开数据库失败: {}", e))
+            .ok_or_else(|| "数据库路径未设置".to_string())?
+            .get()
     }
 
     /// 检查是否已初始化
@@ -112,76 +170,19 @@ impl DatabaseManager {
     /// # Arguments
     /// * `options` - 查询选项
     pub fn query_history(&self, options: QueryOptions) -> Result<Vec<HistoryItem>, String> {
+        options.validate()?;
         let conn = self.get_connection()?;
 
-        let mut sql = String::from("SELECT * FROM history WHERE 1=1");
-
-        // 排除已删除
-        if options.exclude_deleted {
-            sql.push_str(" AND (deleted IS NULL OR deleted = 0)");
-        }
-
-        // 仅收藏
-        if options.only_favorites {
-            sql.push_str(" AND favorite = 1");
-        }
-
-        // 自定义条件
-        if let Some(where_clause) = &options.where_clause {
-            sql.push_str(&format!(" AND {}", where_clause));
-        }
-
-        // 排序
-        if let Some(order_by) = &options.order_by {
-            sql.push_str(&format!(" ORDER BY {}", order_by));
-        } else {
-            // 默认使用 position 排序（后端根据 autoSort 设置决定是否更新 position）
-            sql.push_str(" ORDER BY position DESC");
-        }
-
-        // 限制
-        if let Some(limit) = options.limit {
-            sql.push_str(&format!(" LIMIT {}", limit));
-        }
-
-        // 偏移
-        if let Some(offset) = options.offset {
-            sql.push_str(&format!(" OFFSET {}", offset));
-        }
+        let (sql, params) = options.to_sql();
+        let preview = options.preview_length.is_some();
 
+        // 同一组筛选条件生成的 SQL 相同，可以复用预编译语句
         let mut stmt = conn
-            .prepare(&sql)
+            .prepare_cached(&sql)
             .map_err(|e| format!("准备查询失败: {}", e))?;
 
-        // 构建查询参数
-        let params: Vec<&str> = options
-            .params
-            .as_ref()
-            .map(|p| p.iter().map(|s| s.as_str()).collect())
-            .unwrap_or_default();
-
         let rows = stmt
-            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
-                Ok(HistoryItem {
-                    id: row.get(0)?,
-                    item_type: row.get(1).ok(),
-                    group: row.get(2).ok(),
-                    value: row.get(3).ok(),
-                    search: row.get(4).ok(),
-                    count: row.get(5).ok(),
-                    width: row.get(6).ok(),
-                    height: row.get(7).ok(),
-                    favorite: row.get(8).unwrap_or(0),
-                    time: row.get(9).unwrap_or(0),
-                    note: row.get(10).ok(),
-                    subtype: row.get(11).ok(),
-                    deleted: row.get(12).ok(),
-                    sync_status: row.get(13).ok(),
-                    source_app_name: row.get(14).ok().flatten(),
-                    source_app_icon: row.get(15).ok().flatten(),
-                    position: row.get(16).ok().flatten(),
-                })
-            })
+            .query_map(rusqlite::params_from_iter(params), history_item_from_row)
             .map_err(|e| format!("查询失败: {}", e))?;
 
         let mut items = Vec::new();
@@ -192,15 +193,32 @@ impl DatabaseManager {
             }
         }
 
+        // 预览查询只需要摘要，不读取外部存储
+        if !preview {
+            self.rehydrate(&mut items);
+        }
+
         Ok(items)
     }
 
+    /// 读取记录的完整内容（预览查询中被截断的记录按需读取）
+    pub fn get_item_content(&self, id: &str) -> Result<HistoryItem, String> {
+        self.query_history(QueryOptions {
+            conditions: vec![QueryCondition::Id(id.to_string())],
+            limit: Some(1),
+            ..Default::default()
+        })?
+        .pop()
+        .ok_or_else(|| format!("记录不存在: {}", id))
+    }
+
     /// 根据同步模式和数据状态筛选查询数据（供同步引擎内部使用）
     ///
     /// # Arguments
     /// * `only_favorites` - 是否仅同步收藏项
     /// * `include_images` - 是否包含图片
     /// * `include_files` - 是否包含文件
+    /// * `include_sensitive` - 是否包含敏感内容
     /// * `content_types` - 内容类型筛选
     /// * `sync_status_filter` - 同步状态筛选（None=不过滤）
     pub fn query_for_sync(
@@ -208,6 +226,7 @@ impl DatabaseManager {
         only_favorites: bool,
         include_images: bool,
         include_files: bool,
+        include_sensitive: bool,
         content_types: ContentTypeFilter,
         sync_status_filter: Option<SyncStatusFilter>,
     ) -> Result<Vec<SyncDataItem>, String> {
@@ -215,36 +234,48 @@ impl DatabaseManager {
         let filter = DataFilter {
             base_filter: BaseFilter {
                 only_favorites,
-                exclude_deleted: false, // 同步需要包含已删除的项目
+                exclude_deleted: true, // 回收站中的项目不上传，云端删除由同步引擎单独处理
                 content_types: content_types.clone(),
             },
             group_filter: None,
+            tag_filter: None,
             search_filter: None,
             sync_filter: Some(SyncModeFilter {
                 only_favorites,
                 include_images,
                 include_files,
+                include_sensitive,
                 content_types,
             }),
             sync_status_filter,
         };
 
         let options = filter.to_query_options(None, None);
-        log::info!(
-            "🔍 查询SQL: where='{}'",
-            options.where_clause.as_deref().unwrap_or("none")
-        );
+        log::info!("🔍 查询条件: {:?}", options.conditions);
         let history_items = self.query_history(options)?;
 
         log::info!(
-            "🔍 同步查询: only_favorites={}, include_images={}, include_files={}, 结果={}",
+            "🔍 同步查询: only_favorites={}, include_images={}, include_files={}, include_sensitive={}, 结果={}",
             only_favorites,
             include_images,
             include_files,
+            include_sensitive,
             history_items.len()
         );
 
-        Ok(history_items.into_iter().map(SyncDataItem::from).collect())
+        let conn = self.get_connection()?;
+        let mut tag_names = item_tag_names(&conn)?;
+
+        Ok(history_items
+            .into_iter()
+            .map(|item| {
+                let tags = tag_names.remove(&item.id).unwrap_or_default();
+                SyncDataItem {
+                    tags,
+                    ..SyncDataItem::from(item)
+                }
+            })
+            .collect())
     }
 
     /// 更新同步状态
@@ -255,11 +286,9 @@ impl DatabaseManager {
     pub fn update_sync_status(&self, id: &str, status: &str) -> Result<(), String> {
         let conn = self.get_connection()?;
 
-        conn.execute(
-            "UPDATE history SET syncStatus = ?1 WHERE id = ?2",
-            params![status, id],
-        )
-        .map_err(|e| format!("更新同步状态失败: {}", e))?;
+        conn.prepare_cached("UPDATE history SET syncStatus = ?1 WHERE id = ?2")
+            .and_then(|mut stmt| stmt.execute(params![status, id]))
+            .map_err(|e| format!("更新同步状态失败: {}", e))?;
 
         Ok(())
     }
@@ -291,12 +320,92 @@ impl DatabaseManager {
         let conn = self.get_connection()?;
 
         let sql = format!("UPDATE history SET {} = ?1 WHERE id = ?2", field);
-        conn.execute(&sql, params![value, id])
+        conn.prepare_cached(&sql)
+            .and_then(|mut stmt| stmt.execute(params![value, id]))
             .map_err(|e| format!("更新字段 {} 失败: {}", field, e))?;
 
         Ok(())
     }
 
+    /// 在同一事务中更新多个字段并记录变更
+    ///
+    /// # Arguments
+    /// * `id` - 项目ID
+    /// * `fields` - 要更新的列和值（列名由调用方从固定的字段映射中选取）
+    /// * `move_to_top` - 是否将 position 更新为最大值（自动排序开启时）
+    /// * `change_type` - 变更类型
+    /// * `ignore_for_sync` - 是否只更新时间戳，不标记为待同步
+    pub fn update_fields_tracked(
+        &self,
+        id: &str,
+        fields: &[(&str, String)],
+        move_to_top: bool,
+        change_type: &str,
+        ignore_for_sync: bool,
+    ) -> Result<(), String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        for (field, value) in fields {
+            // 内容和搜索文本与插入时相同：超过阈值的转存到外部存储，表中只保存摘要
+            let blob_column = match *field {
+                "value" => "valueBlob",
+                "search" => "searchBlob",
+                _ => {
+                    let sql = format!("UPDATE history SET {} = ?1 WHERE id = ?2", field);
+                    conn.prepare_cached(&sql)
+                        .and_then(|mut stmt| stmt.execute(params![value, id]))
+                        .map_err(|e| format!("更新字段 {} 失败: {}", field, e))?;
+                    continue;
+                }
+            };
+
+            let item_type: Option<String> = conn
+                .query_row(
+                    "SELECT type FROM history WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .unwrap_or(None);
+            let stored = offload_content(
+                self.blob_dir().as_deref(),
+                item_type.as_deref(),
+                Some(value),
+                None,
+            )?;
+
+            let sql = format!(
+                "UPDATE history SET {} = ?1, {} = ?2 WHERE id = ?3",
+                field, blob_column
+            );
+            conn.prepare_cached(&sql)
+                .and_then(|mut stmt| stmt.execute(params![stored.value, stored.value_blob, id]))
+                .map_err(|e| format!("更新字段 {} 失败: {}", field, e))?;
+        }
+
+        if move_to_top {
+            conn.execute(
+                "UPDATE history SET position = (SELECT COALESCE(MAX(position), 0) + 1 FROM history) WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|e| format!("更新排序失败: {}", e))?;
+        }
+
+        // 时间戳属于排序信息，不记入变更字段
+        let changed_fields: Vec<&str> = fields
+            .iter()
+            .map(|(field, _)| *field)
+            .filter(|field| *field != "time")
+            .collect();
+        self.change_tracker
+            .mark_item_changed(&conn, id, change_type, &changed_fields, ignore_for_sync)?;
+
+        conn.commit()
+            .map_err(|e| format!("提交事务失败: {}", e))
+    }
+
     /// 批量更新同步状态
     ///
     /// # Arguments
@@ -370,6 +479,14 @@ impl DatabaseManager {
             None => (1, 0, 0),
         };
 
+        // 超过阈值的内容转存到外部存储，表中只保存摘要
+        let stored = offload_content(
+            self.blob_dir().as_deref(),
+            Some(item.item_type.as_str()),
+            item.value.as_deref(),
+            item.search.as_deref(),
+        )?;
+
         // 检查是否存在
         let exists: bool = conn
             .query_row(
@@ -385,12 +502,13 @@ impl DatabaseManager {
                 "UPDATE history SET
                     type = ?1, value = ?2, search = ?3, favorite = ?4, note = ?5,
                     syncStatus = ?6, deleted = ?7, time = ?8, count = ?9, subtype = ?10,
-                    width = ?11, height = ?12
-                WHERE id = ?13",
+                    width = ?11, height = ?12, expiresAt = ?13, expireOnPaste = ?14,
+                    valueBlob = ?15, searchBlob = ?16
+                WHERE id = ?17",
                 params![
                     item.item_type,
-                    item.value,
-                    item.search,
+                    stored.value,
+                    stored.search,
                     item.favorite,
                     item.note,
                     "synced",
@@ -400,6 +518,10 @@ impl DatabaseManager {
                     item.subtype,
                     width,
                     height,
+                    item.expires_at,
+                    item.expire_on_paste,
+                    stored.value_blob,
+                    stored.search_blob,
                     item.id,
                 ],
             )
@@ -407,13 +529,13 @@ impl DatabaseManager {
         } else {
             // 插入
             conn.execute(
-                "INSERT INTO history (id, type, value, search, favorite, note, time, syncStatus, deleted, count, subtype, width, height)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                "INSERT INTO history (id, type, value, search, favorite, note, time, syncStatus, deleted, count, subtype, width, height, expiresAt, expireOnPaste, valueBlob, searchBlob)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                 params![
                     item.id,
                     item.item_type,
-                    item.value,
-                    item.search,
+                    stored.value,
+                    stored.search,
                     item.favorite,
                     item.note,
                     item.time,
@@ -423,10 +545,16 @@ impl DatabaseManager {
                     item.subtype,
                     width,
                     height,
+                    item.expires_at,
+                    item.expire_on_paste,
+                    stored.value_blob,
+                    stored.search_blob,
                 ],
             ).map_err(|e| format!("插入云端数据失败: {}", e))?;
         }
 
+        replace_item_tags_by_name(&conn, &item.id, &item.tags)?;
+
         Ok(())
     }
 
@@ -444,16 +572,26 @@ impl DatabaseManager {
     }
 
     /// 彻底删除项目（硬删除）
-    /// 用于同步完成后清理本地删除标记
+    /// 用于同步完成后清理本地删除标记，同时写入墓碑使删除同步到其他设备
     ///
     /// # Arguments
     /// * `id` - 项目ID
     pub fn hard_delete(&self, id: &str) -> Result<(), String> {
-        let conn = self.get_connection()?;
-
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let ids = [id.to_string()];
+        let images = image_paths_of(&conn, &ids)?;
+        record_tombstones(&conn, &ids)?;
         conn.execute("DELETE FROM history WHERE id = ?1", params![id])
             .map_err(|e| format!("硬删除失败: {}", e))?;
 
+        conn.commit()
+            .map_err(|e| format!("提交事务失败: {}", e))?;
+
+        self.release_images(&images)?;
         Ok(())
     }
 
@@ -465,8 +603,8 @@ impl DatabaseManager {
         let conn = self.get_connection()?;
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, type, [group], value, search, count, width, height, favorite, time, note, subtype, deleted, syncStatus, sourceAppName, sourceAppIcon, position
+            .prepare_cached(
+                "SELECT id, type, [group], value, search, count, width, height, favorite, time, note, subtype, deleted, syncStatus, sourceAppName, sourceAppIcon, position, expiresAt, expireOnPaste, valueBlob, searchBlob
              FROM history WHERE id = ?1",
             )
             .map_err(|e| format!("准备查询失败: {}", e))?;
@@ -474,25 +612,9 @@ impl DatabaseManager {
         let mut rows = stmt.query(params![id]).map_err(|e| format!("查询失败: {}", e))?;
 
         if let Some(row) = rows.next().map_err(|e| format!("读取行失败: {}", e))? {
-            let item = HistoryItem {
-                id: row.get(0).map_err(|e| format!("读取id失败: {}", e))?,
-                item_type: row.get(1).ok(),
-                group: row.get(2).ok(),
-                value: row.get(3).ok(),
-                search: row.get(4).ok(),
-                count: row.get(5).ok(),
-                width: row.get(6).ok(),
-                height: row.get(7).ok(),
-                favorite: row.get(8).unwrap_or(0),
-                time: row.get(9).unwrap_or(0),
-                note: row.get(10).ok(),
-                subtype: row.get(11).ok(),
-                deleted: row.get(12).ok(),
-                sync_status: row.get(13).ok(),
-                source_app_name: row.get(14).ok().flatten(),
-                source_app_icon: row.get(15).ok().flatten(),
-                position: row.get(16).ok().flatten(),
-            };
+            let mut item =
+                history_item_from_row(row).map_err(|e| format!("解析行失败: {}", e))?;
+            self.rehydrate(std::iter::once(&mut item));
             Ok(Some(item))
         } else {
             Ok(None)
@@ -547,29 +669,43 @@ impl DatabaseManager {
         item: &InsertItem,
         app_handle: &tauri::AppHandle<R>,
     ) -> Result<InsertResult, String> {
-        let conn = self.get_connection()?;
+        let pooled = self.get_connection()?;
+        // 数据修改与变更日志在同一事务中提交
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        // 超过阈值的内容转存到外部存储，表中只保存摘要
+        let stored = offload_content(
+            self.blob_dir().as_deref(),
+            item.item_type.as_deref(),
+            item.value.as_deref(),
+            item.search.as_deref(),
+        )?;
 
         // 检查是否已存在（优先使用ID去重）
         let exists_by_id: bool = conn
-            .query_row(
-                "SELECT 1 FROM history WHERE id = ?1",
-                params![item.id],
-                |_| Ok(true),
-            )
+            .prepare_cached("SELECT 1 FROM history WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists(params![item.id]))
             .unwrap_or(false);
 
         if exists_by_id {
-            // 如果ID已存在，判断是否为重复内容
-            let existing_value: Option<String> = conn
+            // 如果ID已存在，判断是否为重复内容（已转存的大内容比较哈希）
+            let (existing_value, existing_blob): (Option<String>, Option<String>) = conn
                 .query_row(
-                    "SELECT value FROM history WHERE id = ?1",
+                    "SELECT value, valueBlob FROM history WHERE id = ?1",
                     params![item.id],
-                    |row| row.get(0),
+                    |row| Ok((row.get(0)?, row.get(1)?)),
                 )
-                .unwrap_or(None);
+                .unwrap_or((None, None));
 
-            let is_duplicate =
-                existing_value.as_ref() == Some(&item.value.clone().unwrap_or_default());
+            let is_duplicate = match existing_blob {
+                Some(existing_blob) => stored.value_blob.as_ref() == Some(&existing_blob),
+                None => {
+                    stored.value_blob.is_none()
+                        && existing_value.as_ref() == Some(&item.value.clone().unwrap_or_default())
+                }
+            };
 
             if is_duplicate {
                 // 如果内容和ID都相同，认为是重复操作，不执行任何操作
@@ -586,12 +722,14 @@ impl DatabaseManager {
                         width = ?5, height = ?6, favorite = ?7,
                         time = ?8, note = ?9, subtype = ?10,
                         deleted = ?11, syncStatus = ?12,
-                        sourceAppName = ?13, sourceAppIcon = ?14, position = ?15
-                    WHERE id = ?16",
+                        sourceAppName = ?13, sourceAppIcon = ?14, position = ?15,
+                        valueBlob = ?16, searchBlob = ?17,
+                        expiresAt = ?18, expireOnPaste = ?19
+                    WHERE id = ?20",
                     params![
                         item.item_type,
-                        item.value,
-                        item.search,
+                        stored.value,
+                        stored.search,
                         item.count.unwrap_or(1),
                         item.width,
                         item.height,
@@ -606,16 +744,20 @@ impl DatabaseManager {
                         item.source_app_name,
                         item.source_app_icon,
                         item.position.unwrap_or(0),
+                        stored.value_blob,
+                        stored.search_blob,
+                        item.expires_at,
+                        item.expire_on_paste,
                         item.id,
                     ],
                 )
                 .map_err(|e| format!("更新数据失败: {}", e))?;
 
                 // 使用统一变更跟踪器
-                let conn = self.get_connection()?;
-                let _ = self
-                    .change_tracker
-                    .mark_item_changed(&conn, &item.id, "update", false);
+                self.change_tracker
+                    .mark_item_changed(&conn, &item.id, "update", &[], false)?;
+                conn.commit()
+                    .map_err(|e| format!("提交事务失败: {}", e))?;
 
                 return Ok(InsertResult {
                     is_update: true,
@@ -651,6 +793,25 @@ impl DatabaseManager {
                 }
             }
             tauri_plugin_eco_detector::find_similar_color(new_search, &color_records)
+        } else if item_type_str == "image" {
+            // 图片类型：基于内容哈希匹配（旧版本保存的图片文件名不同，通过 images 表比较哈希）
+            let value = item.value.as_deref().unwrap_or("");
+            find_duplicate_image(&conn, value)?.or_else(|| {
+                conn.query_row(
+                    "SELECT id FROM history WHERE type = ?1 AND value = ?2 AND deleted = 0 LIMIT 1",
+                    params![item_type_str, value],
+                    |row| row.get(0),
+                )
+                .unwrap_or(None)
+            })
+        } else if let Some(search_blob) = &stored.search_blob {
+            // 已转存的大内容：search 列只有摘要，基于完整内容的哈希匹配
+            conn.query_row(
+                "SELECT id FROM history WHERE type = ?1 AND searchBlob = ?2 AND deleted = 0 LIMIT 1",
+                params![item_type_str, search_blob],
+                |row| row.get(0),
+            )
+            .unwrap_or(None)
         } else if item.search.is_some() {
             // 其他类型：基于 search 字段精确匹配
             conn.query_row(
@@ -659,6 +820,14 @@ impl DatabaseManager {
                 |row| row.get(0),
             )
             .unwrap_or(None)
+        } else if let Some(value_blob) = &stored.value_blob {
+            // 已转存的大内容：基于完整内容的哈希匹配
+            conn.query_row(
+                "SELECT id FROM history WHERE type = ?1 AND valueBlob = ?2 AND deleted = 0 LIMIT 1",
+                params![item_type_str, value_blob],
+                |row| row.get(0),
+            )
+            .unwrap_or(None)
         } else {
             // Fallback: 基于 value 去重（兼容没有 search 字段的类型）
             conn.query_row(
@@ -704,10 +873,17 @@ impl DatabaseManager {
             }
 
             // 使用统一变更跟踪器（去重只更新时间戳，不标记为待同步）
-            let conn = self.get_connection()?;
-            let _ = self
-                .change_tracker
-                .mark_item_changed(&conn, &existing_id, "dedup", true);
+            self.change_tracker
+                .mark_item_changed(&conn, &existing_id, "dedup", &[], true)?;
+            conn.commit()
+                .map_err(|e| format!("提交事务失败: {}", e))?;
+
+            // 重复图片与已有记录的文件不同时（旧版本保存的图片），删除新保存的文件
+            if item_type_str == "image" {
+                if let Some(value) = &item.value {
+                    self.release_images(std::slice::from_ref(value))?;
+                }
+            }
 
             return Ok(InsertResult {
                 is_update: true,
@@ -750,20 +926,24 @@ impl DatabaseManager {
                 width, height, favorite, time, note, subtype,
                 deleted,
                 syncStatus,
-                sourceAppName, sourceAppIcon, position
+                sourceAppName, sourceAppIcon, position,
+                valueBlob, searchBlob,
+                expiresAt, expireOnPaste
             ) VALUES (
                 ?1, ?2, ?3, ?4, ?5, ?6,
                 ?7, ?8, ?9, ?10, ?11, ?12,
                 ?13,
                 ?14,
-                ?15, ?16, ?17
+                ?15, ?16, ?17,
+                ?18, ?19,
+                ?20, ?21
             )",
             params![
                 item.id,
                 item.item_type,
                 item.group,
-                item.value,
-                item.search,
+                stored.value,
+                stored.search,
                 item.count.unwrap_or(1),
                 item.width,
                 item.height,
@@ -778,15 +958,19 @@ impl DatabaseManager {
                 source_info.as_ref().map(|s| s.app_name.clone()),
                 source_info.as_ref().and_then(|s| s.app_icon.clone()),
                 max_position + 1,
+                stored.value_blob,
+                stored.search_blob,
+                item.expires_at,
+                item.expire_on_paste,
             ],
         )
         .map_err(|e| format!("插入数据失败: {}", e))?;
 
         // 使用统一变更跟踪器（新插入的记录需要同步）
-        let conn = self.get_connection()?;
-        let _ = self
-            .change_tracker
-            .mark_item_changed(&conn, &item.id, "insert", false);
+        self.change_tracker
+            .mark_item_changed(&conn, &item.id, "insert", &[], false)?;
+        conn.commit()
+            .map_err(|e| format!("提交事务失败: {}", e))?;
 
         Ok(InsertResult {
             is_update: false,
@@ -816,36 +1000,46 @@ impl DatabaseManager {
         let db_path = Path::new(&save_data_dir).join(db_filename);
         let db_path_buf = PathBuf::from(db_path);
 
-        // 检查数据库版本，如果是旧版本则备份
-        if db_path_buf.exists() {
-            if self.is_old_database(&db_path_buf)? {
-                log::info!("[Database] 检测到旧版本数据库，开始备份...");
+        log::info!("设置数据库路径: {:?}", db_path_buf);
 
-                // 构建备份路径
-                let backup_extension = if is_dev { "dev.db.bak" } else { "db.bak" };
-                let backup_filename = format!("{}.{}", app_name, backup_extension);
-                let backup_path = Path::new(&save_data_dir).join(backup_filename);
+        self.init(db_path_buf)
+    }
 
-                // 删除旧备份（如果存在）
-                if backup_path.exists() {
-                    std::fs::remove_file(&backup_path)
-                        .map_err(|e| format!("删除旧备份失败: {}", e))?;
-                }
+    /// 如果是 v0.6.x 旧版数据库，改名为 `.bak` 并写入迁移标记
+    ///
+    /// # Arguments
+    /// * `db_path` - 数据库文件路径
+    fn backup_legacy_database(&self, db_path: &PathBuf) -> Result<(), String> {
+        if !self.is_old_database(db_path)? {
+            return Ok(());
+        }
 
-                // 重命名数据库为备份
-                std::fs::rename(&db_path_buf, &backup_path)
-                    .map_err(|e| format!("重命名数据库失败: {}", e))?;
+        log::info!("[Database] 检测到旧版本数据库，开始备份...");
 
-                log::info!("[Database] 旧数据库已备份到: {:?}", backup_path);
+        // 改名之前先生成安全快照
+        let conn = Connection::open(db_path).map_err(|e| format!("打开数据库失败: {}", e))?;
+        take_snapshot(&conn, &snapshot_dir(db_path), SnapshotKind::Safety)?;
+        drop(conn);
 
-                // 写入迁移标记
-                self.write_migration_marker(&save_data_dir, is_dev, &backup_path)?;
-            }
+        let backup_path = db_path.with_extension("db.bak");
+
+        // 删除旧备份（如果存在）
+        if backup_path.exists() {
+            std::fs::remove_file(&backup_path).map_err(|e| format!("删除旧备份失败: {}", e))?;
         }
 
-        log::info!("设置数据库路径: {:?}", db_path_buf);
+        // 重命名数据库为备份
+        std::fs::rename(db_path, &backup_path).map_err(|e| format!("重命名数据库失败: {}", e))?;
 
-        self.init(db_path_buf)
+        log::info!("[Database] 旧数据库已备份到: {:?}", backup_path);
+
+        // 写入迁移标记
+        let data_dir = db_path
+            .parent()
+            .ok_or_else(|| "数据库路径无效".to_string())?
+            .to_string_lossy()
+            .to_string();
+        self.write_migration_marker(&data_dir, is_dev_mode(), &backup_path)
     }
 
     /// 检查是否为旧版本数据库（通过是否存在 createTime 列判断）
@@ -1,6 +1,8 @@
 //! 调试专用模块
 //! 包含调试和开发时使用的命令，与生产环境隔离
 
+use crate::snapshot::SnapshotKind;
+use crate::tombstone::record_tombstones;
 use crate::DatabaseState;
 use serde::Serialize;
 use tauri::State;
@@ -22,7 +24,7 @@ pub struct DatabaseInfo {
 /// 获取数据库统计信息（调试用）
 #[tauri::command]
 pub fn get_database_info(state: State<'_, DatabaseState>) -> Result<DatabaseInfo, String> {
-    let db = state.blocking_lock();
+    let db = state.blocking_read();
     let conn = db.get_connection().map_err(|e| e.to_string())?;
 
     // 基本统计
@@ -91,14 +93,32 @@ pub fn get_database_info(state: State<'_, DatabaseState>) -> Result<DatabaseInfo
 /// 重置数据库（调试用）
 #[tauri::command]
 pub async fn reset_database(state: State<'_, DatabaseState>) -> Result<bool, String> {
-    let db = state.lock().await;
+    let db = state.read().await;
 
     log::warn!("🔄 开始重置数据库（调试操作）");
 
-    // 清空所有数据
+    // 重置前生成安全快照，可通过快照回滚
+    db.create_snapshot(SnapshotKind::Safety)?;
+
+    // 清空所有数据（写入墓碑，使其他设备同步删除）
     let conn = db.get_connection()?;
-    conn.execute_batch("DELETE FROM history;")
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("开始事务失败: {}", e))?;
+
+    let ids: Vec<String> = tx
+        .prepare("SELECT id FROM history")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("查询数据失败: {}", e))?;
+    record_tombstones(&tx, &ids)?;
+
+    tx.execute_batch("DELETE FROM history;")
         .map_err(|e| format!("清空数据失败: {}", e))?;
+    tx.commit()
+        .map_err(|e| format!("提交事务失败: {}", e))?;
 
     // 压缩数据库文件
     conn.execute_batch("VACUUM;")
@@ -4,6 +4,8 @@
 //! - 已同步 (sync_status == "synced")：软删除，标记 deleted=1，等待云端同步时删除
 //! - 未同步 (sync_status != "synced")：硬删除，直接从数据库删除
 
+use crate::images::image_paths_of;
+use crate::tombstone::record_tombstones;
 use crate::DatabaseManager;
 use serde::{Deserialize, Serialize};
 
@@ -75,7 +77,7 @@ impl DeleteManager {
     /// # Returns
     /// 删除结果
     pub fn delete_items(
-        db: &mut DatabaseManager,
+        db: &DatabaseManager,
         ids: &[String],
         strategy: DeleteStrategy,
     ) -> Result<DeleteResult, String> {
@@ -130,8 +132,7 @@ impl DeleteManager {
 
         // 根据策略决定每个项目的删除方式
         for id in ids {
-            match Self::delete_single_item(db, &conn, id, &sync_status_map, strategy, current_time)
-            {
+            match Self::delete_single_item(db, id, &sync_status_map, strategy, current_time) {
                 Ok(DeleteType::Soft) => soft_deleted_ids.push(id.clone()),
                 Ok(DeleteType::Hard) => hard_deleted_ids.push(id.clone()),
                 Err(e) => errors.push(format!("删除 {} 失败: {}", id, e)),
@@ -149,8 +150,7 @@ impl DeleteManager {
 
     /// 删除单个项目
     fn delete_single_item(
-        db: &mut DatabaseManager,
-        conn: &rusqlite::Connection,
+        db: &DatabaseManager,
         id: &str,
         sync_status_map: &std::collections::HashMap<String, Option<String>>,
         strategy: DeleteStrategy,
@@ -177,11 +177,14 @@ impl DeleteManager {
         // 执行删除
         match delete_type {
             DeleteType::Soft => {
-                // 软删除：标记 deleted = 1，更新时间，标记变更跟踪
-                db.update_field(id, "deleted", "1")?;
-                db.update_field(id, "time", &current_time.to_string())?;
-                db.get_change_tracker()
-                    .mark_item_changed(&conn, id, "delete", false)?;
+                // 软删除：标记 deleted = 1，更新时间，记录变更（同一事务）
+                db.update_fields_tracked(
+                    id,
+                    &[("deleted", "1".to_string()), ("time", current_time.to_string())],
+                    false,
+                    "delete",
+                    false,
+                )?;
                 Ok(DeleteType::Soft)
             }
             DeleteType::Hard => {
@@ -192,13 +195,37 @@ impl DeleteManager {
         }
     }
 
-    /// 批量硬删除（直接从数据库删除）
-    pub fn batch_hard_delete(db: &mut DatabaseManager, ids: &[String]) -> Result<usize, String> {
+    /// 批量硬删除（直接从数据库删除，同时写入墓碑）
+    pub fn batch_hard_delete(db: &DatabaseManager, ids: &[String]) -> Result<usize, String> {
+        Self::hard_delete_rows(db, ids, true)
+    }
+
+    /// 批量硬删除本机记录，不写入墓碑
+    ///
+    /// 删除不会同步到其他设备，云端仍存在的记录会在下次同步时重新下载。
+    /// 用于恢复备份等只影响本机数据的场景
+    pub fn batch_hard_delete_local(db: &DatabaseManager, ids: &[String]) -> Result<usize, String> {
+        Self::hard_delete_rows(db, ids, false)
+    }
+
+    fn hard_delete_rows(
+        db: &DatabaseManager,
+        ids: &[String],
+        with_tombstones: bool,
+    ) -> Result<usize, String> {
         if ids.is_empty() {
             return Ok(0);
         }
 
-        let conn = db.get_connection()?;
+        let pooled = db.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let images = image_paths_of(&conn, ids)?;
+        if with_tombstones {
+            record_tombstones(&conn, ids)?;
+        }
 
         // 使用 push_str 构建占位符，避免 collect 的特殊处理
         let mut placeholders = String::new();
@@ -217,6 +244,10 @@ impl DeleteManager {
             .execute(&query, rusqlite::params_from_iter(params.iter().copied()))
             .map_err(|e| format!("批量硬删除失败: {}", e))?;
 
+        conn.commit()
+            .map_err(|e| format!("提交事务失败: {}", e))?;
+
+        db.release_images(&images)?;
         Ok(count)
     }
 }
@@ -227,3 +258,41 @@ enum DeleteType {
     Soft,
     Hard,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{HistoryItem, SyncDataItem};
+
+    #[test]
+    fn test_local_hard_delete_skips_tombstones() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-delete-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+        for id in ["1", "2"] {
+            db.upsert_from_cloud(&SyncDataItem::from(HistoryItem {
+                id: id.to_string(),
+                item_type: Some("text".to_string()),
+                value: Some(id.to_string()),
+                ..Default::default()
+            }))
+            .unwrap();
+        }
+
+        let local = DeleteManager::batch_hard_delete_local(&db, &["1".to_string()]).unwrap();
+        assert_eq!(local, 1);
+        assert!(db.get_tombstones().unwrap().is_empty());
+
+        DeleteManager::batch_hard_delete(&db, &["2".to_string()]).unwrap();
+        let tombstones = db.get_tombstones().unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].id, "2");
+        assert!(db.query_by_id("1").unwrap().is_none());
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
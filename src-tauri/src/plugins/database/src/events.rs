@@ -0,0 +1,225 @@
+//! 数据库变更事件
+//! 写操作完成后以结构化事件通知变更：操作类型、受影响的记录ID和变更字段
+//! 1. 前端监听 `plugin:eco-clipboard://database_updated`，可按变更局部更新列表
+//! 2. 其他插件（托盘、快捷键等）通过 `subscribe_changes` 在 Rust 侧订阅，无需经过 webview
+//! 3. 批量操作（清理、同步）把变更累积到一个 `DatabaseChanges` 中，只发送一次事件
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::broadcast;
+
+/// 前端监听的数据库更新事件
+pub const DATABASE_UPDATED_EVENT: &str = "plugin:eco-clipboard://database_updated";
+
+/// 订阅通道容量，订阅者处理过慢时丢弃最早的事件
+const CHANGE_CHANNEL_CAPACITY: usize = 64;
+
+/// 变更操作类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeOperation {
+    /// 新增记录
+    Inserted,
+    /// 更新记录字段
+    Updated,
+    /// 删除记录（软删除或硬删除）
+    Deleted,
+    /// 从回收站恢复记录
+    Restored,
+    /// 与云端同步（上传完成或从云端下载）
+    Synced,
+}
+
+/// 单个变更：同一操作、同一组字段的记录合并为一条
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    pub operation: ChangeOperation,
+    /// 受影响的记录ID
+    pub ids: Vec<String>,
+    /// 变更的字段（为空表示整条记录）
+    pub fields: Vec<String>,
+}
+
+/// 数据库变更事件载荷
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatabaseChanges {
+    /// 复制了已存在的内容时，被更新的记录ID（兼容旧的事件载荷）
+    #[serde(rename = "duplicate_id")]
+    pub duplicate_id: Option<String>,
+    /// 变更列表
+    pub changes: Vec<ChangeEvent>,
+    /// 变更范围未知（如快照回滚、备份恢复），需要重新加载整个列表
+    pub reload: bool,
+}
+
+impl DatabaseChanges {
+    /// 创建空的变更集合，用于累积批量操作的变更
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 只包含一个变更
+    pub fn single<I, S>(operation: ChangeOperation, ids: I, fields: &[&str]) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut changes = Self::new();
+        changes.record(operation, ids, fields);
+        changes
+    }
+
+    /// 需要重新加载整个列表
+    pub fn reload() -> Self {
+        Self {
+            reload: true,
+            ..Self::default()
+        }
+    }
+
+    /// 记录变更，与已有的相同操作、相同字段的变更合并
+    pub fn record<I, S>(&mut self, operation: ChangeOperation, ids: I, fields: &[&str]) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let ids: Vec<String> = ids.into_iter().map(Into::into).collect();
+        if ids.is_empty() {
+            return self;
+        }
+
+        let index = match self
+            .changes
+            .iter()
+            .position(|c| c.operation == operation && c.fields == fields)
+        {
+            Some(index) => index,
+            None => {
+                self.changes.push(ChangeEvent {
+                    operation,
+                    ids: Vec::new(),
+                    fields: fields.iter().map(|f| f.to_string()).collect(),
+                });
+                self.changes.len() - 1
+            }
+        };
+
+        let event = &mut self.changes[index];
+        for id in ids {
+            if !event.ids.contains(&id) {
+                event.ids.push(id);
+            }
+        }
+
+        self
+    }
+
+    /// 合并另一组变更
+    pub fn extend(&mut self, other: DatabaseChanges) -> &mut Self {
+        if self.duplicate_id.is_none() {
+            self.duplicate_id = other.duplicate_id;
+        }
+        self.reload |= other.reload;
+        for event in other.changes {
+            let fields: Vec<&str> = event.fields.iter().map(String::as_str).collect();
+            self.record(event.operation, event.ids, &fields);
+        }
+        self
+    }
+
+    /// 是否没有任何变更
+    pub fn is_empty(&self) -> bool {
+        !self.reload && self.duplicate_id.is_none() && self.changes.is_empty()
+    }
+}
+
+/// 变更通知器（Rust 侧订阅），在插件初始化时注册为应用状态
+pub struct ChangeNotifier {
+    sender: broadcast::Sender<DatabaseChanges>,
+}
+
+impl ChangeNotifier {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANGE_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    /// 订阅变更事件
+    pub fn subscribe(&self) -> broadcast::Receiver<DatabaseChanges> {
+        self.sender.subscribe()
+    }
+
+    /// 发布变更（没有订阅者时直接忽略）
+    pub fn publish(&self, changes: DatabaseChanges) {
+        let _ = self.sender.send(changes);
+    }
+}
+
+impl Default for ChangeNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 订阅数据库变更事件（供其他插件使用）
+///
+/// # Returns
+/// 数据库插件尚未初始化时返回 None
+pub fn subscribe_changes<R: Runtime>(
+    app_handle: &AppHandle<R>,
+) -> Option<broadcast::Receiver<DatabaseChanges>> {
+    app_handle
+        .try_state::<ChangeNotifier>()
+        .map(|notifier| notifier.subscribe())
+}
+
+/// 发送变更事件：通知前端并发布给 Rust 侧订阅者
+/// 没有任何变更时不发送
+pub fn emit_changes<R: Runtime>(app_handle: &AppHandle<R>, changes: DatabaseChanges) {
+    if changes.is_empty() {
+        return;
+    }
+
+    if let Err(e) = app_handle.emit(DATABASE_UPDATED_EVENT, &changes) {
+        log::error!("[Database] 发送数据库更新事件失败: {}", e);
+    }
+
+    if let Some(notifier) = app_handle.try_state::<ChangeNotifier>() {
+        notifier.publish(changes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_coalesces_changes() {
+        let mut changes = DatabaseChanges::new();
+        changes.record(ChangeOperation::Deleted, ["a", "b"], &[]);
+        changes.record(ChangeOperation::Updated, ["a"], &["favorite"]);
+        changes.record(ChangeOperation::Deleted, ["b", "c"], &[]);
+        changes.record(ChangeOperation::Updated, ["b"], &["note"]);
+        changes.record(ChangeOperation::Synced, Vec::<String>::new(), &[]);
+
+        assert_eq!(changes.changes.len(), 3);
+        assert_eq!(changes.changes[0].ids, vec!["a", "b", "c"]);
+        assert_eq!(changes.changes[1].fields, vec!["favorite"]);
+
+        let mut batch = DatabaseChanges::new();
+        assert!(batch.is_empty());
+        batch.extend(changes.clone());
+        batch.extend(DatabaseChanges::single(
+            ChangeOperation::Updated,
+            ["c"],
+            &["favorite"],
+        ));
+        assert_eq!(batch.changes[1].ids, vec!["a", "c"]);
+
+        let payload = serde_json::to_value(DatabaseChanges::reload()).unwrap();
+        assert_eq!(payload["duplicate_id"], serde_json::Value::Null);
+        assert_eq!(payload["reload"], true);
+    }
+}
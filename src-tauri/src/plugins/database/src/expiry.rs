@@ -0,0 +1,279 @@
+//! 记录过期
+//! 每条记录可以设置过期时间（`expiresAt`，毫秒时间戳）或粘贴一次后过期（`expireOnPaste`）：
+//! 1. 后台任务定期软删除已过期的记录，删除随下次同步传播到云端
+//! 2. 过期设置作为同步字段上传，其他设备上的记录同样按时过期
+//! 3. 粘贴后过期的记录在粘贴完成后立即删除（见 `mark_items_pasted`）
+
+use crate::database::DatabaseManager;
+use crate::delete::{DeleteManager, DeleteStrategy};
+use crate::events::{emit_changes, ChangeOperation, DatabaseChanges};
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use std::time::Duration;
+use tauri::{AppHandle, Runtime, State};
+
+/// history 表中的过期相关列
+pub(crate) const EXPIRY_COLUMNS: &[(&str, &str)] = &[
+    ("expiresAt", "INTEGER"),
+    ("expireOnPaste", "INTEGER DEFAULT 0"),
+];
+
+/// 过期记录检查间隔
+const EXPIRY_REAPER_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 查询已过期且尚未删除的记录
+fn expired_ids(conn: &Connection, now: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id FROM history
+             WHERE expiresAt IS NOT NULL
+               AND expiresAt <= ?1
+               AND (deleted IS NULL OR deleted = 0)",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let ids = stmt
+        .query_map(params![now], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(|e| format!("查询过期记录失败: {}", e))?;
+
+    Ok(ids)
+}
+
+/// 在给定的记录中筛选出粘贴后过期的记录
+fn expire_on_paste_ids(conn: &Connection, ids: &[String]) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT id FROM history
+             WHERE id = ?1
+               AND expireOnPaste = 1
+               AND (deleted IS NULL OR deleted = 0)",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let mut matched = Vec::new();
+    for id in ids {
+        if stmt
+            .exists(params![id])
+            .map_err(|e| format!("查询过期设置失败: {}", e))?
+        {
+            matched.push(id.clone());
+        }
+    }
+
+    Ok(matched)
+}
+
+impl DatabaseManager {
+    /// 设置或清除记录的过期时间
+    ///
+    /// # Arguments
+    /// * `id` - 项目ID
+    /// * `expires_at` - 过期时间（毫秒时间戳），None 表示不按时间过期
+    /// * `expire_on_paste` - 是否在粘贴一次后过期
+    pub fn set_item_expiry(
+        &self,
+        id: &str,
+        expires_at: Option<i64>,
+        expire_on_paste: bool,
+    ) -> Result<(), String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let updated = conn
+            .execute(
+                "UPDATE history SET expiresAt = ?1, expireOnPaste = ?2 WHERE id = ?3",
+                params![expires_at, expire_on_paste, id],
+            )
+            .map_err(|e| format!("更新过期时间失败: {}", e))?;
+
+        if updated == 0 {
+            return Err(format!("记录不存在: {}", id));
+        }
+
+        self.get_change_tracker().mark_item_changed(
+            &conn,
+            id,
+            "expiry",
+            &["expiresAt", "expireOnPaste"],
+            false,
+        )?;
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))
+    }
+
+    /// 软删除已过期的记录
+    ///
+    /// # Returns
+    /// 删除的记录ID
+    pub fn delete_expired_items(&self) -> Result<Vec<String>, String> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let ids = {
+            let conn = self.get_connection()?;
+            expired_ids(&conn, now)?
+        };
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let result = DeleteManager::delete_items(self, &ids, DeleteStrategy::Soft)?;
+        for error in &result.errors {
+            log::warn!("[Database] 删除过期记录失败: {}", error);
+        }
+
+        Ok(result.soft_deleted_ids)
+    }
+
+    /// 记录已被粘贴，删除其中设置了粘贴后过期的记录
+    ///
+    /// # Returns
+    /// 删除的记录ID
+    pub fn expire_pasted_items(&self, ids: &[String]) -> Result<Vec<String>, String> {
+        let ids = {
+            let conn = self.get_connection()?;
+            expire_on_paste_ids(&conn, ids)?
+        };
+
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let result = DeleteManager::delete_items(self, &ids, DeleteStrategy::Soft)?;
+        Ok(result.soft_deleted_ids)
+    }
+}
+
+/// 处理已粘贴的记录：删除其中粘贴后过期的记录并通知前端
+/// 供前端命令和后端快速粘贴共用
+///
+/// # Returns
+/// 删除的记录数量
+pub fn handle_items_pasted<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    db: &DatabaseManager,
+    ids: &[String],
+) -> Result<usize, String> {
+    let deleted = db.expire_pasted_items(ids)?;
+
+    if !deleted.is_empty() {
+        log::info!("[Database] 已删除 {} 条粘贴后过期的记录", deleted.len());
+        emit_changes(
+            app_handle,
+            DatabaseChanges::single(ChangeOperation::Deleted, &deleted, &[]),
+        );
+    }
+
+    Ok(deleted.len())
+}
+
+/// 启动过期记录清理任务
+pub fn start_expiry_reaper<R: Runtime>(app_handle: AppHandle<R>, state: DatabaseState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_REAPER_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let db = state.read().await;
+            if !db.is_initialized() {
+                continue;
+            }
+
+            match db.delete_expired_items() {
+                Ok(ids) if ids.is_empty() => {}
+                Ok(ids) => {
+                    log::info!("[Database] 已删除 {} 条过期记录", ids.len());
+                    emit_changes(
+                        &app_handle,
+                        DatabaseChanges::single(ChangeOperation::Deleted, ids, &[]),
+                    );
+                }
+                Err(e) => log::error!("[Database] 清理过期记录失败: {}", e),
+            }
+        }
+    });
+}
+
+/// 设置或清除记录的过期时间
+#[tauri::command]
+pub async fn set_item_expiry<R: Runtime>(
+    id: String,
+    expires_at: Option<i64>,
+    expire_on_paste: Option<bool>,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<(), String> {
+    let db = state.read().await;
+    db.set_item_expiry(&id, expires_at, expire_on_paste.unwrap_or(false))?;
+
+    let mut changes = DatabaseChanges::single(
+        ChangeOperation::Updated,
+        [id.as_str()],
+        &["expiresAt", "expireOnPaste"],
+    );
+
+    // 过期时间已到时立即删除，不必等待下一次检查
+    if expires_at.is_some_and(|at| at <= chrono::Utc::now().timestamp_millis()) {
+        let deleted = db.delete_expired_items()?;
+        changes.record(ChangeOperation::Deleted, deleted, &[]);
+    }
+
+    emit_changes(&app_handle, changes);
+    Ok(())
+}
+
+/// 记录已被粘贴（删除粘贴后过期的记录）
+///
+/// # Returns
+/// 删除的记录数量
+#[tauri::command]
+pub async fn mark_items_pasted<R: Runtime>(
+    ids: Vec<String>,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<usize, String> {
+    let db = state.read().await;
+    handle_items_pasted(&app_handle, &db, &ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+
+    #[test]
+    fn test_expired_and_expire_on_paste_ids() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, None).unwrap();
+
+        let rows = [
+            ("expired", Some(100), 0, 0),
+            ("pending", Some(300), 0, 0),
+            ("deleted", Some(100), 0, 1),
+            ("on_paste", None, 1, 0),
+            ("plain", None, 0, 0),
+        ];
+        for (id, expires_at, expire_on_paste, deleted) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, value, expiresAt, expireOnPaste, deleted)
+                 VALUES (?1, 'text', 'hello', ?2, ?3, ?4)",
+                params![id, expires_at, expire_on_paste, deleted],
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            expired_ids(&conn, 200).unwrap(),
+            vec!["expired".to_string()]
+        );
+
+        let pasted = ["on_paste".to_string(), "plain".to_string()];
+        assert_eq!(
+            expire_on_paste_ids(&conn, &pasted).unwrap(),
+            vec!["on_paste".to_string()]
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! 包括用户操作（切换分组、搜索等）和同步模式筛选
 
 use crate::models::{HistoryItem, QueryOptions};
+use crate::query::QueryCondition;
+use crate::sensitive::SENSITIVE_SUBTYPE;
 use serde::{Deserialize, Serialize};
 
 /// 数据筛选器
@@ -12,6 +14,9 @@ pub struct DataFilter {
     pub base_filter: BaseFilter,
     /// 分组筛选
     pub group_filter: Option<GroupFilter>,
+    /// 标签筛选
+    #[serde(default)]
+    pub tag_filter: Option<TagFilter>,
     /// 搜索筛选
     pub search_filter: Option<SearchFilter>,
     /// 同步模式筛选
@@ -48,6 +53,24 @@ pub struct GroupFilter {
     pub group_name: Option<String>,
 }
 
+/// 标签筛选
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagFilter {
+    /// 标签ID
+    pub tag_ids: Vec<String>,
+    /// 匹配方式
+    pub mode: TagMatchMode,
+}
+
+/// 标签匹配方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TagMatchMode {
+    /// 带有其中任意一个标签
+    Any,
+    /// 带有全部标签
+    All,
+}
+
 /// 搜索筛选
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchFilter {
@@ -58,10 +81,12 @@ pub struct SearchFilter {
 }
 
 /// 搜索字段
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SearchField {
     /// 搜索内容值
     Value,
+    /// 仅搜索检索文本（search 列）
+    SearchText,
     /// 搜索备注
     Note,
     /// 搜索分组
@@ -80,6 +105,9 @@ pub struct SyncModeFilter {
     pub include_images: bool,
     /// 是否包含文件
     pub include_files: bool,
+    /// 是否包含敏感内容（默认不同步）
+    #[serde(default)]
+    pub include_sensitive: bool,
     /// 内容类型配置
     pub content_types: ContentTypeFilter,
 }
@@ -230,6 +258,7 @@ impl DataFilter {
                 },
             },
             group_filter: None,
+            tag_filter: None,
             search_filter: None,
             sync_filter: None,
             sync_status_filter: None,
@@ -262,6 +291,13 @@ impl DataFilter {
         filter
     }
 
+    /// 创建标签筛选器
+    pub fn with_tags(tag_ids: Vec<String>, mode: TagMatchMode) -> Self {
+        let mut filter = Self::default();
+        filter.tag_filter = Some(TagFilter { tag_ids, mode });
+        filter
+    }
+
     /// 创建同步模式筛选器
     pub fn with_sync_mode(sync_filter: SyncModeFilter) -> Self {
         let mut filter = Self::default();
@@ -275,81 +311,83 @@ impl DataFilter {
         pagination: Option<Pagination>,
         sort: Option<SortInfo>,
     ) -> QueryOptions {
-        let mut options = QueryOptions::default();
-
-        // 基础筛选
-        options.only_favorites = self.base_filter.only_favorites;
-        options.exclude_deleted = self.base_filter.exclude_deleted;
+        let mut options = QueryOptions {
+            only_favorites: self.base_filter.only_favorites,
+            exclude_deleted: self.base_filter.exclude_deleted,
+            ..Default::default()
+        };
 
         // 分组筛选
-        if let Some(group_filter) = &self.group_filter {
-            if let Some(group_name) = &group_filter.group_name {
-                let where_clause = format!("[group] = '{}'", group_name);
-                options.where_clause = Some(where_clause);
-            }
+        if let Some(group_name) = self
+            .group_filter
+            .as_ref()
+            .and_then(|g| g.group_name.clone())
+        {
+            options.conditions.push(QueryCondition::Group(group_name));
+        }
+
+        // 标签筛选
+        if let Some(tag_filter) = &self.tag_filter {
+            let tag_ids = tag_filter.tag_ids.clone();
+            options.conditions.push(match tag_filter.mode {
+                TagMatchMode::Any => QueryCondition::TagsAny(tag_ids),
+                TagMatchMode::All => QueryCondition::TagsAll(tag_ids),
+            });
         }
 
         // 搜索筛选
         if let Some(search_filter) = &self.search_filter {
-            let search_conditions = self.build_search_conditions(search_filter);
-            if let Some(existing_where) = &options.where_clause {
-                options.where_clause =
-                    Some(format!("{} AND {}", existing_where, search_conditions));
-            } else {
-                options.where_clause = Some(search_conditions);
-            }
+            options.conditions.push(QueryCondition::Search {
+                keyword: search_filter.keyword.clone(),
+                fields: search_filter.search_fields.clone(),
+            });
         }
 
-        // 同步状态筛选
+        // 同步状态筛选（包含列表为空表示不过滤）
         if let Some(sync_status_filter) = &self.sync_status_filter {
-            let status_conditions = self.build_sync_status_conditions(sync_status_filter);
-            if let Some(existing_where) = &options.where_clause {
-                options.where_clause =
-                    Some(format!("{} AND {}", existing_where, status_conditions));
-            } else {
-                options.where_clause = Some(status_conditions);
+            if !sync_status_filter.include.is_empty() {
+                options.conditions.push(QueryCondition::SyncStatusIn(
+                    sync_status_filter.include.clone(),
+                ));
+            }
+            if !sync_status_filter.exclude.is_empty() {
+                options.conditions.push(QueryCondition::SyncStatusNotIn(
+                    sync_status_filter.exclude.clone(),
+                ));
             }
         }
 
         // 同步模式内容类型筛选（从 sync_filter 中获取）
         if let Some(sync_filter) = &self.sync_filter {
-            // 构建内容类型条件
-            let mut type_conditions = Vec::new();
-
-            // 图片类型
             if !sync_filter.include_images {
-                type_conditions.push("[type] != 'image'".to_string());
+                options
+                    .conditions
+                    .push(QueryCondition::ExcludeType("image".to_string()));
             }
 
-            // 文件类型
             if !sync_filter.include_files {
-                type_conditions.push("[type] != 'files'".to_string());
+                options
+                    .conditions
+                    .push(QueryCondition::ExcludeType("files".to_string()));
             }
 
-            // 添加内容类型条件
-            if !type_conditions.is_empty() {
-                let type_condition = type_conditions.join(" AND ");
-                if let Some(existing_where) = &options.where_clause {
-                    options.where_clause =
-                        Some(format!("{} AND {}", existing_where, type_condition));
-                } else {
-                    options.where_clause = Some(type_condition);
-                }
+            if !sync_filter.include_sensitive {
+                options.conditions.push(QueryCondition::ExcludeSubtype(
+                    SENSITIVE_SUBTYPE.to_string(),
+                ));
             }
 
             // 处理收藏模式
             if sync_filter.only_favorites {
-                if let Some(existing_where) = &options.where_clause {
-                    options.where_clause = Some(format!("{} AND [favorite] = 1", existing_where));
-                } else {
-                    options.where_clause = Some("[favorite] = 1".to_string());
-                }
+                options.conditions.push(QueryCondition::Favorite(true));
             }
         }
 
-        // 排序
-        let sort_clause = self.build_sort_clause(sort);
-        options.order_by = sort_clause;
+        // 排序（默认按时间降序）
+        options.sort = Some(sort.unwrap_or(SortInfo {
+            field: SortField::Time,
+            order: SortOrder::Desc,
+        }));
 
         // 分页
         if let Some(pagination) = pagination {
@@ -360,93 +398,8 @@ impl DataFilter {
         options
     }
 
-    /// 构建同步状态筛选条件
-    fn build_sync_status_conditions(&self, filter: &SyncStatusFilter) -> String {
-        let mut conditions = Vec::new();
-
-        // 处理包含列表
-        if !filter.include.is_empty() {
-            let include_statuses: Vec<String> = filter
-                .include
-                .iter()
-                .map(|s| match s {
-                    SyncStatus::NotSynced => "'not_synced'".to_string(),
-                    SyncStatus::Synced => "'synced'".to_string(),
-                    SyncStatus::Changed => "'changed'".to_string(),
-                })
-                .collect();
-            conditions.push(format!("syncStatus IN ({})", include_statuses.join(", ")));
-        }
-
-        // 处理排除列表
-        for status in &filter.exclude {
-            let condition = match status {
-                SyncStatus::NotSynced => "syncStatus != 'not_synced'".to_string(),
-                SyncStatus::Synced => "syncStatus != 'synced'".to_string(),
-                SyncStatus::Changed => "syncStatus != 'changed'".to_string(),
-            };
-            conditions.push(condition);
-        }
-
-        conditions.join(" AND ")
-    }
-
-    /// 构建搜索条件
-    fn build_search_conditions(&self, search_filter: &SearchFilter) -> String {
-        let keyword = &search_filter.keyword;
-        let keyword_escaped = keyword.replace("'", "''"); // 转义单引号
-
-        let mut conditions = Vec::new();
-
-        for field in &search_filter.search_fields {
-            let condition = match field {
-                SearchField::Value => {
-                    format!(
-                        "(value LIKE '%{}%' OR search LIKE '%{}%')",
-                        keyword_escaped, keyword_escaped
-                    )
-                }
-                SearchField::Note => {
-                    format!("note LIKE '%{}%'", keyword_escaped)
-                }
-                SearchField::Group => {
-                    format!("[group] LIKE '%{}%'", keyword_escaped)
-                }
-                SearchField::All => {
-                    format!(
-                        "(value LIKE '%{}%' OR search LIKE '%{}%' OR note LIKE '%{}%' OR [group] LIKE '%{}%')",
-                        keyword_escaped, keyword_escaped, keyword_escaped, keyword_escaped
-                    )
-                }
-            };
-            conditions.push(condition);
-        }
-
-        conditions.join(" OR ")
-    }
-
-    /// 构建排序条件
-    fn build_sort_clause(&self, sort: Option<SortInfo>) -> Option<String> {
-        if let Some(sort_info) = sort {
-            let field = match sort_info.field {
-                SortField::Time => "time",
-                SortField::Type => "type",
-                SortField::Group => "[group]",
-                SortField::Favorite => "favorite",
-            };
-
-            let order = match sort_info.order {
-                SortOrder::Asc => "ASC",
-                SortOrder::Desc => "DESC",
-            };
-
-            Some(format!("{} {}", field, order))
-        } else {
-            Some("time DESC".to_string()) // 默认按时间降序
-        }
-    }
-
     /// 检查项目是否匹配筛选条件
+    /// 注意：HistoryItem 不包含标签，标签筛选只在数据库查询中生效
     pub fn matches(&self, item: &HistoryItem) -> bool {
         // 基础筛选
         if self.base_filter.only_favorites && item.favorite == 0 {
@@ -514,6 +467,9 @@ impl DataFilter {
                     item.value.as_ref().map_or(false, |v| v.contains(keyword))
                         || item.search.as_ref().map_or(false, |s| s.contains(keyword))
                 }
+                SearchField::SearchText => {
+                    item.search.as_ref().map_or(false, |s| s.contains(keyword))
+                }
                 SearchField::Note => item.note.as_ref().map_or(false, |n| n.contains(keyword)),
                 SearchField::Group => item.group.as_ref().map_or(false, |g| g.contains(keyword)),
                 SearchField::All => {
@@ -539,6 +495,11 @@ impl DataFilter {
             return false;
         }
 
+        // 敏感内容检查
+        if !sync_filter.include_sensitive && item.is_sensitive() {
+            return false;
+        }
+
         // 内容类型检查
         let item_type = item.item_type.as_deref().unwrap_or("text");
 
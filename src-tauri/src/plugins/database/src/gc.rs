@@ -0,0 +1,375 @@
+//! 媒体文件垃圾回收
+//! 扫描应用写入媒体文件的所有目录，删除不再被任何历史记录引用的文件：
+//! - 图片目录 `images`：剪贴板图片，以及保存失败时遗留的 `<ID>.tmp.png` 临时文件
+//! - 缓存目录 `eco-paste-files`：文件缓存，以及同步下载的 `images`、`files` 子目录
+//! - 外部存储目录 `blobs`：转存的大内容（见 `blobs`），按 `valueBlob` / `searchBlob` 引用
+//!
+//! 扫描期间可能有新记录写入，为避免误删：
+//! 1. 宽限期内修改过的文件一律跳过（保存图片和复用已有图片都会更新修改时间）
+//! 2. 删除前逐个文件重新确认未被引用，并再次检查修改时间
+//!
+//! 软删除的记录仍保留文件（等待同步删除或恢复），硬删除后才会被回收
+
+use crate::blobs::{blob_hash_of, blob_path};
+use crate::database::DatabaseManager;
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, Runtime, State};
+
+/// 宽限期：此时间内修改过的文件视为正在使用
+const GC_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// 垃圾回收报告
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaGcReport {
+    /// 是否为试运行（只统计，不删除）
+    pub dry_run: bool,
+    /// 扫描的文件数量
+    pub scanned: usize,
+    /// 因处于宽限期而跳过的孤儿文件数量
+    pub skipped_recent: usize,
+    /// 删除（试运行时为将要删除）的文件
+    pub removed: Vec<String>,
+    /// 释放（试运行时为可释放）的磁盘空间（字节）
+    pub reclaimed_bytes: u64,
+    /// 删除失败的文件
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// 获取缓存目录路径（同步下载的文件也保存在其子目录中）
+pub(crate) fn get_cache_dir() -> Result<PathBuf, String> {
+    let mut cache_dir = std::env::temp_dir();
+    cache_dir.push("eco-paste-files");
+
+    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+    Ok(cache_dir)
+}
+
+/// 应用写入媒体文件的所有目录
+pub fn media_dirs<R: Runtime>(app_handle: &AppHandle<R>) -> Vec<PathBuf> {
+    let mut dirs = vec![app_handle
+        .path()
+        .data_dir()
+        .unwrap_or_else(|_| PathBuf::from("./data"))
+        .join("images")];
+
+    match get_cache_dir() {
+        Ok(cache_dir) => dirs.push(cache_dir),
+        Err(e) => log::warn!("[GC] 无法获取缓存目录: {}", e),
+    }
+
+    dirs
+}
+
+/// 递归收集目录中的所有文件
+fn collect_files_recursive(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_file() {
+                files.push(path);
+            } else if path.is_dir() {
+                collect_files_recursive(&path, files);
+            }
+        }
+    }
+}
+
+/// 记录值中引用的文件路径
+/// 文件记录的值是 JSON 数组，同步下载后可能是单个路径
+fn referenced_paths(item_type: &str, value: &str) -> Vec<String> {
+    match item_type {
+        "image" => vec![value.to_string()],
+        "files" => {
+            serde_json::from_str::<Vec<String>>(value).unwrap_or_else(|_| vec![value.to_string()])
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// 收集所有记录（包括软删除的记录）、图片表和外部存储引用的文件
+fn collect_referenced(
+    conn: &Connection,
+    blob_dir: Option<&Path>,
+) -> Result<HashSet<String>, String> {
+    let mut referenced = HashSet::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT type, value FROM history
+             WHERE type IN ('image', 'files') AND value IS NOT NULL",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("查询文件引用失败: {}", e))?;
+    for (item_type, value) in rows {
+        referenced.extend(referenced_paths(&item_type, &value));
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT path FROM images WHERE ref_count > 0")
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+    let paths = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("查询图片引用失败: {}", e))?;
+    referenced.extend(paths);
+
+    if let Some(blob_dir) = blob_dir {
+        let mut stmt = conn
+            .prepare(
+                "SELECT valueBlob FROM history WHERE valueBlob IS NOT NULL
+                 UNION SELECT searchBlob FROM history WHERE searchBlob IS NOT NULL",
+            )
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+        let hashes = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("查询外部存储引用失败: {}", e))?;
+        referenced.extend(
+            hashes
+                .iter()
+                .map(|hash| blob_path(blob_dir, hash).to_string_lossy().to_string()),
+        );
+    }
+
+    Ok(referenced)
+}
+
+/// 删除前再次确认文件未被引用（扫描期间可能有新记录写入）
+fn is_referenced(conn: &Connection, path: &str, blob_dir: Option<&Path>) -> Result<bool, String> {
+    if let Some(hash) = blob_dir.and_then(|dir| blob_hash_of(dir, Path::new(path))) {
+        return conn
+            .prepare_cached(
+                "SELECT EXISTS(SELECT 1 FROM history WHERE valueBlob = ?1 OR searchBlob = ?1)",
+            )
+            .and_then(|mut stmt| stmt.query_row(params![hash], |row| row.get(0)))
+            .map_err(|e| format!("查询外部存储引用失败: {}", e));
+    }
+
+    // 文件记录中的路径以 JSON 字符串形式保存，需要按转义后的形式匹配
+    let escaped = serde_json::to_string(path).map_err(|e| format!("序列化路径失败: {}", e))?;
+
+    conn.prepare_cached(
+        "SELECT EXISTS(SELECT 1 FROM history WHERE value = ?1 OR (type = 'files' AND instr(value, ?2) > 0))
+             OR EXISTS(SELECT 1 FROM images WHERE path = ?1 AND ref_count > 0)",
+    )
+    .and_then(|mut stmt| stmt.query_row(params![path, escaped], |row| row.get(0)))
+    .map_err(|e| format!("查询文件引用失败: {}", e))
+}
+
+/// 文件是否在截止时间之后修改过（读取失败时视为刚修改，不删除）
+fn modified_after(path: &Path, cutoff: SystemTime) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| modified > cutoff)
+        .unwrap_or(true)
+}
+
+/// 回收指定目录中的孤儿文件
+///
+/// # Arguments
+/// * `dirs` - 要扫描的目录
+/// * `blob_dir` - 外部存储目录（其中的文件按内容哈希引用）
+/// * `dry_run` - 只统计，不删除
+/// * `cutoff` - 此时间之后修改过的文件不会被删除
+fn collect_garbage(
+    conn: &Connection,
+    dirs: &[PathBuf],
+    blob_dir: Option<&Path>,
+    dry_run: bool,
+    cutoff: SystemTime,
+) -> Result<MediaGcReport, String> {
+    let mut report = MediaGcReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        collect_files_recursive(dir, &mut files);
+    }
+    report.scanned = files.len();
+
+    let referenced = collect_referenced(conn, blob_dir)?;
+    let mut released = Vec::new();
+
+    for file in files {
+        let path = file.to_string_lossy().to_string();
+        if referenced.contains(&path) {
+            continue;
+        }
+        if modified_after(&file, cutoff) {
+            report.skipped_recent += 1;
+            continue;
+        }
+        if is_referenced(conn, &path, blob_dir)? {
+            continue;
+        }
+
+        let size = std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0);
+        if !dry_run {
+            // 扫描期间可能被复用，删除前再次检查修改时间
+            if modified_after(&file, cutoff) {
+                report.skipped_recent += 1;
+                continue;
+            }
+            match std::fs::remove_file(&file) {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    report.errors.push(format!("{} ({})", path, e));
+                    continue;
+                }
+            }
+            released.push(path.clone());
+        }
+
+        report.reclaimed_bytes += size;
+        report.removed.push(path);
+    }
+
+    // 文件已删除的图片记录不再需要
+    if !released.is_empty() {
+        let mut stmt = conn
+            .prepare_cached("DELETE FROM images WHERE path = ?1 AND ref_count <= 0")
+            .map_err(|e| format!("准备删除图片记录失败: {}", e))?;
+        for path in &released {
+            stmt.execute(params![path])
+                .map_err(|e| format!("删除图片记录失败: {}", e))?;
+        }
+    }
+
+    Ok(report)
+}
+
+impl DatabaseManager {
+    /// 回收媒体目录和外部存储目录中不再被任何记录引用的文件
+    ///
+    /// # Arguments
+    /// * `dirs` - 要扫描的目录（见 `media_dirs`，外部存储目录自动包含）
+    /// * `dry_run` - 只统计，不删除
+    pub fn collect_media_garbage(
+        &self,
+        dirs: &[PathBuf],
+        dry_run: bool,
+    ) -> Result<MediaGcReport, String> {
+        let cutoff = SystemTime::now()
+            .checked_sub(GC_GRACE_PERIOD)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+
+        let blob_dir = self.blob_dir();
+        let mut dirs = dirs.to_vec();
+        if let Some(blob_dir) = &blob_dir {
+            dirs.push(blob_dir.clone());
+        }
+
+        let conn = self.get_connection()?;
+        let report = collect_garbage(&conn, &dirs, blob_dir.as_deref(), dry_run, cutoff)?;
+
+        for error in &report.errors {
+            log::warn!("[GC] 删除文件失败: {}", error);
+        }
+        log::info!(
+            "[GC] 扫描 {} 个文件，{}{} 个孤儿文件，共 {} 字节（跳过 {} 个新文件）",
+            report.scanned,
+            if dry_run { "可删除 " } else { "已删除 " },
+            report.removed.len(),
+            report.reclaimed_bytes,
+            report.skipped_recent
+        );
+
+        Ok(report)
+    }
+}
+
+/// 回收孤儿媒体文件（图片、文件缓存、同步下载的文件）
+///
+/// # Arguments
+/// * `dry_run` - 只统计可回收的文件和空间，不删除
+#[tauri::command]
+pub async fn collect_media_garbage<R: Runtime>(
+    dry_run: Option<bool>,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<MediaGcReport, String> {
+    let db = state.read().await;
+    db.collect_media_garbage(&media_dirs(&app_handle), dry_run.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+
+    #[test]
+    fn test_collect_garbage_keeps_referenced_and_recent_files() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-gc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("files")).unwrap();
+
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let old = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        for name in [
+            "used.png",
+            "deleted.png",
+            "orphan.png",
+            "1.tmp.png",
+            "files/doc.txt",
+        ] {
+            std::fs::write(dir.join(name), b"data").unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(dir.join(name))
+                .and_then(|f| f.set_modified(old))
+                .unwrap();
+        }
+        std::fs::write(dir.join("fresh.png"), b"data").unwrap();
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, None).unwrap();
+        let files = serde_json::to_string(&[path("files/doc.txt")]).unwrap();
+        let rows = [
+            ("1", "image", path("used.png"), 0),
+            ("2", "image", path("deleted.png"), 1),
+            ("3", "files", files, 0),
+        ];
+        for (id, item_type, value, deleted) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, value, deleted) VALUES (?1, ?2, ?3, ?4)",
+                params![id, item_type, value, deleted],
+            )
+            .unwrap();
+        }
+
+        let cutoff = SystemTime::now() - Duration::from_secs(60);
+        let dry = collect_garbage(&conn, &[dir.clone()], None, true, cutoff).unwrap();
+        assert_eq!(dry.scanned, 6);
+        assert_eq!(dry.skipped_recent, 1);
+        assert_eq!(dry.removed.len(), 2);
+        assert_eq!(dry.reclaimed_bytes, 8);
+        assert!(dir.join("orphan.png").exists());
+
+        let report = collect_garbage(&conn, &[dir.clone()], None, false, cutoff).unwrap();
+        let mut removed = report.removed.clone();
+        removed.sort();
+        assert_eq!(removed, vec![path("1.tmp.png"), path("orphan.png")]);
+        assert!(dir.join("used.png").exists());
+        assert!(dir.join("deleted.png").exists());
+        assert!(dir.join("files/doc.txt").exists());
+        assert!(dir.join("fresh.png").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
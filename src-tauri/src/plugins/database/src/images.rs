@@ -0,0 +1,329 @@
+//! 图片存储
+//! 剪贴板图片按内容哈希（SHA-256）命名保存为 `images/<哈希>.png`，相同内容只保存一份
+//!
+//! `images` 表记录每个图片文件的哈希和引用数，引用数由 history 表上的触发器维护，
+//! 所有删除路径（硬删除、墓碑、重置）都会自动减少引用。
+//! 硬删除后调用 `release_images`，引用数归零的文件会被删除
+
+use crate::database::DatabaseManager;
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// 图片表结构及维护引用数的触发器
+pub(crate) const IMAGES_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS images (
+        path TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        size INTEGER NOT NULL DEFAULT 0,
+        ref_count INTEGER NOT NULL DEFAULT 0,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_images_hash ON images(hash);
+
+    CREATE TRIGGER IF NOT EXISTS images_ref_insert AFTER INSERT ON history
+    WHEN new.type = 'image' BEGIN
+        UPDATE images SET ref_count = ref_count + 1 WHERE path = new.value;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS images_ref_delete AFTER DELETE ON history
+    WHEN old.type = 'image' BEGIN
+        UPDATE images SET ref_count = ref_count - 1 WHERE path = old.value;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS images_ref_update AFTER UPDATE OF type, value ON history BEGIN
+        UPDATE images SET ref_count = ref_count - 1
+        WHERE old.type = 'image' AND path = old.value;
+        UPDATE images SET ref_count = ref_count + 1
+        WHERE new.type = 'image' AND path = new.value;
+    END;
+"#;
+
+/// 已保存的图片
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredImage {
+    /// 图片文件路径
+    pub path: PathBuf,
+    /// 内容哈希（SHA-256 十六进制）
+    pub hash: String,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+/// 计算文件内容的 SHA-256 哈希
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 8192];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 将临时文件按内容哈希保存到图片目录
+/// 相同内容的图片已存在时直接复用，并删除临时文件
+///
+/// # Arguments
+/// * `images_dir` - 图片目录
+/// * `temp_path` - 已写入的临时文件
+pub fn store_image_file(images_dir: &Path, temp_path: &Path) -> Result<StoredImage, String> {
+    let hash = hash_file(temp_path)?;
+    let extension = temp_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    let path = images_dir.join(format!("{}.{}", hash, extension));
+
+    if path.exists() {
+        let _ = std::fs::remove_file(temp_path);
+        // 更新修改时间，避免正在进行的垃圾回收把即将被复用的文件当作孤儿删除
+        let _ = std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(std::time::SystemTime::now()));
+    } else {
+        std::fs::rename(temp_path, &path).map_err(|e| format!("保存图片失败: {}", e))?;
+    }
+
+    let size = std::fs::metadata(&path)
+        .map(|m| m.len())
+        .map_err(|e| format!("读取图片信息失败: {}", e))?;
+
+    Ok(StoredImage { path, hash, size })
+}
+
+/// 登记图片文件（已登记时不做修改）
+/// 引用数按当前引用该路径的历史记录计算，登记前后写入的记录都会被计入
+pub(crate) fn register_image(
+    conn: &Connection,
+    path: &str,
+    hash: &str,
+    size: u64,
+) -> Result<(), String> {
+    conn.prepare_cached(
+        "INSERT INTO images (path, hash, size, ref_count, created_at)
+         VALUES (?1, ?2, ?3, (SELECT COUNT(*) FROM history WHERE type = 'image' AND value = ?1), ?4)
+         ON CONFLICT(path) DO NOTHING",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute(params![
+            path,
+            hash,
+            size as i64,
+            chrono::Utc::now().timestamp_millis()
+        ])
+    })
+    .map_err(|e| format!("登记图片失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 查找与指定图片内容相同的未删除记录
+pub(crate) fn find_duplicate_image(
+    conn: &Connection,
+    path: &str,
+) -> Result<Option<String>, String> {
+    conn.prepare_cached(
+        "SELECT h.id FROM history h
+         JOIN images i ON i.path = h.value
+         WHERE h.type = 'image' AND (h.deleted IS NULL OR h.deleted = 0)
+           AND i.hash = (SELECT hash FROM images WHERE path = ?1)
+         ORDER BY h.time DESC
+         LIMIT 1",
+    )
+    .and_then(|mut stmt| stmt.query_row(params![path], |row| row.get(0)).optional())
+    .map_err(|e| format!("查询重复图片失败: {}", e))
+}
+
+/// 获取项目引用的图片路径
+pub(crate) fn image_paths_of(conn: &Connection, ids: &[String]) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT value FROM history WHERE id = ?1 AND type = 'image'")
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let mut paths = Vec::new();
+    for id in ids {
+        let path: Option<Option<String>> = stmt
+            .query_row(params![id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("查询图片路径失败: {}", e))?;
+        if let Some(Some(path)) = path {
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// 删除引用数归零的图片记录
+///
+/// # Returns
+/// 需要删除的图片文件路径
+fn remove_unreferenced(conn: &Connection, paths: &[String]) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached("DELETE FROM images WHERE path = ?1 AND ref_count <= 0")
+        .map_err(|e| format!("准备删除图片记录失败: {}", e))?;
+
+    let mut removed = Vec::new();
+    for path in paths {
+        let deleted = stmt
+            .execute(params![path])
+            .map_err(|e| format!("删除图片记录失败: {}", e))?;
+        if deleted > 0 {
+            removed.push(path.clone());
+        }
+    }
+
+    Ok(removed)
+}
+
+impl DatabaseManager {
+    /// 登记图片文件（计算内容哈希）
+    pub fn register_image(&self, path: &Path) -> Result<(), String> {
+        let hash = hash_file(path)?;
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+
+        let conn = self.get_connection()?;
+        register_image(&conn, &path.to_string_lossy(), &hash, size)
+    }
+
+    /// 登记已按内容哈希保存的图片
+    pub fn register_stored_image(&self, image: &StoredImage) -> Result<(), String> {
+        let conn = self.get_connection()?;
+        register_image(
+            &conn,
+            &image.path.to_string_lossy(),
+            &image.hash,
+            image.size,
+        )
+    }
+
+    /// 释放图片：删除不再被任何历史记录引用的图片文件
+    ///
+    /// 只处理传入的路径，避免误删刚保存、尚未写入历史记录的图片
+    ///
+    /// # Returns
+    /// 删除的文件数量
+    pub fn release_images(&self, paths: &[String]) -> Result<usize, String> {
+        if paths.is_empty() {
+            return Ok(0);
+        }
+
+        let removed = {
+            let conn = self.get_connection()?;
+            remove_unreferenced(&conn, paths)?
+        };
+
+        let mut count = 0;
+        for path in &removed {
+            match std::fs::remove_file(path) {
+                Ok(_) => count += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("[Images] 删除图片文件失败: {} ({})", path, e),
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HISTORY_SCHEMA;
+
+    fn ref_count(conn: &Connection, path: &str) -> Option<i64> {
+        conn.query_row(
+            "SELECT ref_count FROM images WHERE path = ?1",
+            params![path],
+            |row| row.get(0),
+        )
+        .optional()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ref_count_and_duplicate_lookup() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(HISTORY_SCHEMA).unwrap();
+        conn.execute_batch(IMAGES_SCHEMA).unwrap();
+
+        // 登记前已存在的引用也会被计入
+        conn.execute(
+            "INSERT INTO history (id, type, value, time) VALUES ('1', 'image', 'a.png', 1)",
+            [],
+        )
+        .unwrap();
+        register_image(&conn, "a.png", "hash-a", 10).unwrap();
+        register_image(&conn, "legacy.png", "hash-a", 10).unwrap();
+        assert_eq!(ref_count(&conn, "a.png"), Some(1));
+
+        conn.execute(
+            "INSERT INTO history (id, type, value, time) VALUES ('2', 'image', 'a.png', 2)",
+            [],
+        )
+        .unwrap();
+        assert_eq!(ref_count(&conn, "a.png"), Some(2));
+
+        // 不同路径、相同内容的图片被识别为重复
+        assert_eq!(
+            find_duplicate_image(&conn, "legacy.png").unwrap(),
+            Some("2".to_string())
+        );
+
+        // 旧数据的 deleted 可能为 NULL，同样视为未删除
+        conn.execute("UPDATE history SET deleted = NULL WHERE id = '2'", [])
+            .unwrap();
+        assert_eq!(
+            find_duplicate_image(&conn, "legacy.png").unwrap(),
+            Some("2".to_string())
+        );
+
+        conn.execute("DELETE FROM history WHERE id = '1'", [])
+            .unwrap();
+        assert!(remove_unreferenced(&conn, &["a.png".to_string()])
+            .unwrap()
+            .is_empty());
+
+        conn.execute("UPDATE history SET value = 'legacy.png' WHERE id = '2'", [])
+            .unwrap();
+        assert_eq!(ref_count(&conn, "a.png"), Some(0));
+        assert_eq!(ref_count(&conn, "legacy.png"), Some(1));
+        assert_eq!(
+            remove_unreferenced(&conn, &["a.png".to_string()]).unwrap(),
+            vec!["a.png".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_store_image_file_dedups_by_content() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-images-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let first = dir.join("first.tmp.png");
+        let second = dir.join("second.tmp.png");
+        std::fs::write(&first, b"same image").unwrap();
+        std::fs::write(&second, b"same image").unwrap();
+
+        let a = store_image_file(&dir, &first).unwrap();
+        let b = store_image_file(&dir, &second).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.size, 10);
+        assert!(a.path.ends_with(format!("{}.png", a.hash)));
+        assert!(!first.exists() && !second.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,6 +1,8 @@
 //! 数据库插件核心模块
 //! 提供统一的 SQLite 数据库访问接口
 
+mod blobs;
+mod change_log;
 mod change_tracker;
 mod cleanup;
 mod commands;
@@ -8,10 +10,27 @@ pub mod config;
 mod database;
 mod debug;
 mod delete;
+mod events;
+mod expiry;
 mod filter;
+mod gc;
+mod images;
+mod migrations;
 mod models;
+mod pool;
+mod query;
+mod search;
+mod sensitive;
+mod snapshot;
+mod snippets;
 pub mod source_app;
+mod sync_state;
+mod tags;
+mod tombstone;
+mod trash;
 
+pub use blobs::*;
+pub use change_log::*;
 pub use change_tracker::*;
 pub use cleanup::*;
 pub use commands::*;
@@ -19,9 +38,23 @@ pub use config::*;
 pub use database::*;
 pub use debug::*;
 pub use delete::*;
+pub use events::*;
+pub use expiry::*;
 pub use filter::*;
+pub use gc::*;
+pub use images::*;
 pub use models::*;
+pub use pool::*;
+pub use query::*;
+pub use search::*;
+pub use sensitive::*;
+pub use snapshot::*;
+pub use snippets::*;
 pub use source_app::*;
+pub use sync_state::*;
+pub use tags::*;
+pub use tombstone::*;
+pub use trash::*;
 
 use std::sync::Arc;
 use tauri::{
@@ -29,14 +62,15 @@ use tauri::{
     Manager, Runtime,
 };
 use tauri_plugin_eco_common::paths::get_database_path;
-use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 
 /// 数据库状态类型
-pub type DatabaseState = Arc<Mutex<DatabaseManager>>;
+/// 初始化之后只需读锁：连接来自连接池，并发读写由 SQLite（WAL 模式）处理
+pub type DatabaseState = Arc<RwLock<DatabaseManager>>;
 
 /// 创建共享的数据库管理器
 pub fn create_shared_database() -> DatabaseState {
-    Arc::new(Mutex::new(DatabaseManager::new()))
+    Arc::new(RwLock::new(DatabaseManager::new()))
 }
 
 /// 初始化插件
@@ -44,11 +78,33 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("eco-database")
         .invoke_handler(tauri::generate_handler![
             commands::query_history_with_filter,
+            commands::get_item_content,
+            commands::search_history,
             commands::delete_items,
             commands::update_field,
             cleanup::cleanup_history,
+            gc::collect_media_garbage,
             debug::get_database_info,
-            debug::reset_database
+            debug::reset_database,
+            snapshot::list_snapshots,
+            snapshot::rollback_snapshot,
+            tags::list_tags,
+            tags::create_tag,
+            tags::update_tag,
+            tags::delete_tag,
+            tags::get_item_tags,
+            tags::set_item_tags,
+            snippets::list_snippets,
+            snippets::create_snippet,
+            snippets::update_snippet,
+            snippets::delete_snippet,
+            sensitive::reveal_sensitive,
+            expiry::set_item_expiry,
+            expiry::mark_items_pasted,
+            trash::list_trash,
+            trash::restore_items,
+            trash::empty_trash,
+            trash::undo_delete
         ])
         .setup(|app_handle, _webview_manager| {
             // 在插件初始化时自动设置数据库路径并注册状态
@@ -57,8 +113,11 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             // 将数据库状态注册到应用中，供命令使用
             app_handle.manage(database_state.clone());
 
+            // 注册变更通知器，供其他插件订阅数据库变更
+            app_handle.manage(ChangeNotifier::new());
+
             // 初始化数据库
-            let mut db = database_state.blocking_lock();
+            let mut db = database_state.blocking_write();
 
             // 使用标准路径（与前端 appDataDir 对应）
             let db_path = get_database_path().ok_or_else(|| "无法获取数据库路径".to_string())?;
@@ -74,6 +133,16 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
                     log::error!("❌ 数据库初始化失败: {}", e);
                     e
                 })?;
+            drop(db);
+
+            // 定时删除过期的敏感内容
+            start_sensitive_cleanup(app_handle.clone(), database_state.clone());
+
+            // 定时删除已过期的记录
+            start_expiry_reaper(app_handle.clone(), database_state.clone());
+
+            // 定时生成数据库快照
+            start_snapshot_scheduler(database_state);
 
             Ok(())
         })
@@ -0,0 +1,439 @@
+//! 数据库结构迁移
+//! 使用 `PRAGMA user_version` 记录结构版本，初始化时按顺序执行尚未执行的迁移步骤
+//! 每个步骤与版本号更新在同一事务中提交，失败时回滚到该步骤之前的状态
+//!
+//! 已有数据需要迁移时，先在数据库文件旁保存一份备份（`<数据库文件>.v<版本>.bak`）
+//!
+//! 注意：v0.6.x 旧版数据库（存在 createTime 列）不走这里的迁移，
+//! 由 `DatabaseManager::init` 改名为 `.bak` 后交给 `tauri_plugin_eco_migration` 导入
+
+use crate::blobs::{BLOB_COLUMNS, BLOB_SCHEMA};
+use crate::change_log::{local_device_id, CHANGE_LOG_SCHEMA};
+use crate::database::HISTORY_SCHEMA;
+use crate::expiry::EXPIRY_COLUMNS;
+use crate::images::{hash_file, register_image, IMAGES_SCHEMA};
+use crate::search::create_fts_index;
+use crate::snippets::SNIPPETS_SCHEMA;
+use crate::sync_state::SYNC_STATE_SCHEMA;
+use crate::tags::{import_bookmark_groups, TAGS_SCHEMA};
+use crate::tombstone::TOMBSTONE_SCHEMA;
+use rusqlite::{params, Connection, Transaction};
+use std::path::{Path, PathBuf};
+use tauri_plugin_eco_common::paths::get_data_path;
+
+/// 迁移步骤
+struct Migration {
+    /// 执行后的结构版本
+    version: i32,
+    /// 说明（用于日志）
+    description: &'static str,
+    /// 迁移内容
+    up: fn(&Transaction) -> Result<(), String>,
+}
+
+/// 所有迁移步骤，按版本号递增排列
+///
+/// 已发布的步骤不能修改，结构变更需要追加新的步骤
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "创建 history 表并补齐旧版本缺少的列",
+        up: migrate_history_table,
+    },
+    Migration {
+        version: 2,
+        description: "创建同步状态表",
+        up: |tx| {
+            tx.execute_batch(SYNC_STATE_SCHEMA)
+                .map_err(|e| format!("创建同步状态表失败: {}", e))
+        },
+    },
+    Migration {
+        version: 3,
+        description: "创建全文索引",
+        up: |tx| create_fts_index(tx),
+    },
+    Migration {
+        version: 4,
+        description: "创建变更日志",
+        up: migrate_change_log,
+    },
+    Migration {
+        version: 5,
+        description: "创建删除记录（墓碑）表",
+        up: |tx| {
+            tx.execute_batch(TOMBSTONE_SCHEMA)
+                .map_err(|e| format!("创建墓碑表失败: {}", e))
+        },
+    },
+    Migration {
+        version: 6,
+        description: "创建标签表并导入书签分组",
+        up: migrate_tags,
+    },
+    Migration {
+        version: 7,
+        description: "创建片段表",
+        up: |tx| {
+            tx.execute_batch(SNIPPETS_SCHEMA)
+                .map_err(|e| format!("创建片段表失败: {}", e))
+        },
+    },
+    Migration {
+        version: 8,
+        description: "创建图片表并登记已有图片",
+        up: migrate_images,
+    },
+    Migration {
+        version: 9,
+        description: "添加记录过期时间列",
+        up: migrate_expiry,
+    },
+    Migration {
+        version: 10,
+        description: "创建游标分页索引",
+        up: |tx| {
+            // 游标按 (position, id) 比较，position 为空的记录无法翻页到
+            tx.execute_batch(
+                "UPDATE history SET position = 0 WHERE position IS NULL;
+                 CREATE INDEX IF NOT EXISTS idx_history_position ON history(position, id);",
+            )
+            .map_err(|e| format!("创建游标分页索引失败: {}", e))
+        },
+    },
+    Migration {
+        version: 11,
+        description: "添加大内容外部存储引用列",
+        up: migrate_blobs,
+    },
+];
+
+/// history 表中后续版本新增的列（旧数据库可能缺少）
+const HISTORY_ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("count", "INTEGER DEFAULT 1"),
+    ("width", "INTEGER"),
+    ("height", "INTEGER"),
+    ("note", "TEXT"),
+    ("subtype", "TEXT"),
+    ("deleted", "INTEGER DEFAULT 0"),
+    ("syncStatus", "TEXT DEFAULT 'not_synced'"),
+    ("sourceAppName", "TEXT"),
+    ("sourceAppIcon", "TEXT"),
+    ("position", "INTEGER DEFAULT 0"),
+];
+
+/// 当前代码对应的结构版本
+pub(crate) fn latest_version() -> i32 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// 读取数据库结构版本
+pub(crate) fn schema_version(conn: &Connection) -> Result<i32, String> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("读取数据库版本失败: {}", e))
+}
+
+/// 表中是否存在指定列
+fn has_column(tx: &Transaction, table: &str, column: &str) -> Result<bool, String> {
+    let count: i64 = tx
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            params![table, column],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+
+    Ok(count > 0)
+}
+
+/// history 表是否存在
+fn has_history_table(conn: &Connection) -> Result<bool, String> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'history'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("读取表结构失败: {}", e))?;
+
+    Ok(count > 0)
+}
+
+/// v1：创建 history 表，并为早期版本创建的表补齐缺少的列
+fn migrate_history_table(tx: &Transaction) -> Result<(), String> {
+    // 先补齐列，索引依赖的列（deleted、syncStatus）在旧表中可能不存在
+    if has_history_table(tx)? {
+        for (column, definition) in HISTORY_ADDED_COLUMNS {
+            if !has_column(tx, "history", column)? {
+                log::info!("[Database] 补齐 history 列: {}", column);
+                tx.execute_batch(&format!(
+                    "ALTER TABLE history ADD COLUMN {} {}",
+                    column, definition
+                ))
+                .map_err(|e| format!("添加列 {} 失败: {}", column, e))?;
+            }
+        }
+    }
+
+    tx.execute_batch(HISTORY_SCHEMA)
+        .map_err(|e| format!("创建数据库表失败: {}", e))
+}
+
+/// v4：创建变更日志，并为已修改但尚未同步的项目补充记录
+fn migrate_change_log(tx: &Transaction) -> Result<(), String> {
+    tx.execute_batch(CHANGE_LOG_SCHEMA)
+        .map_err(|e| format!("创建变更日志失败: {}", e))?;
+
+    let device_id = local_device_id(tx)?;
+    tx.execute(
+        "INSERT INTO history_changes (item_id, change_type, fields, device_id, created_at)
+         SELECT id, 'update', '[]', ?1, time FROM history
+         WHERE syncStatus = 'changed' ORDER BY time",
+        params![device_id],
+    )
+    .map_err(|e| format!("初始化变更日志失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 创建标签表，并将 sync 插件保存的书签分组导入为标签
+fn migrate_tags(tx: &Transaction) -> Result<(), String> {
+    tx.execute_batch(TAGS_SCHEMA)
+        .map_err(|e| format!("创建标签表失败: {}", e))?;
+
+    if let Some(data_dir) = get_data_path() {
+        import_bookmark_groups(tx, &data_dir.join("bookmark-data.json"))?;
+    }
+
+    Ok(())
+}
+
+/// 创建图片表，并登记已有的图片文件
+/// 已有文件保持原来的文件名，按内容哈希参与去重
+fn migrate_images(tx: &Transaction) -> Result<(), String> {
+    tx.execute_batch(IMAGES_SCHEMA)
+        .map_err(|e| format!("创建图片表失败: {}", e))?;
+
+    let paths: Vec<String> = tx
+        .prepare("SELECT DISTINCT value FROM history WHERE type = 'image' AND value IS NOT NULL")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("查询图片失败: {}", e))?;
+
+    for path in paths {
+        let file = Path::new(&path);
+        if !file.is_file() {
+            continue;
+        }
+        match hash_file(file) {
+            Ok(hash) => {
+                let size = std::fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+                register_image(tx, &path, &hash, size)?;
+            }
+            Err(e) => log::warn!("[Database] 登记图片失败: {} ({})", path, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// 为 history 表添加过期时间列
+fn migrate_expiry(tx: &Transaction) -> Result<(), String> {
+    for (column, definition) in EXPIRY_COLUMNS {
+        if !has_column(tx, "history", column)? {
+            tx.execute_batch(&format!(
+                "ALTER TABLE history ADD COLUMN {} {}",
+                column, definition
+            ))
+            .map_err(|e| format!("添加列 {} 失败: {}", column, e))?;
+        }
+    }
+
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS idx_history_expiresAt ON history(expiresAt)")
+        .map_err(|e| format!("创建索引失败: {}", e))
+}
+
+/// 为 history 表添加外部存储引用列
+/// 已有的大内容需要写入外部存储目录，由 `DatabaseManager::init` 在迁移后转存
+fn migrate_blobs(tx: &Transaction) -> Result<(), String> {
+    for (column, definition) in BLOB_COLUMNS {
+        if !has_column(tx, "history", column)? {
+            tx.execute_batch(&format!(
+                "ALTER TABLE history ADD COLUMN {} {}",
+                column, definition
+            ))
+            .map_err(|e| format!("添加列 {} 失败: {}", column, e))?;
+        }
+    }
+
+    tx.execute_batch(BLOB_SCHEMA)
+        .map_err(|e| format!("创建外部存储触发器失败: {}", e))
+}
+
+/// 迁移前备份数据库
+fn backup_database(conn: &Connection, db_path: &Path, version: i32) -> Result<PathBuf, String> {
+    let file_name = db_path
+        .file_name()
+        .ok_or_else(|| "数据库路径无效".to_string())?
+        .to_string_lossy();
+    let backup_path = db_path.with_file_name(format!("{}.v{}.bak", file_name, version));
+
+    // VACUUM INTO 要求目标文件不存在
+    if backup_path.exists() {
+        std::fs::remove_file(&backup_path).map_err(|e| format!("删除旧备份失败: {}", e))?;
+    }
+
+    conn.execute("VACUUM INTO ?1", params![backup_path.to_string_lossy()])
+        .map_err(|e| format!("备份数据库失败: {}", e))?;
+
+    Ok(backup_path)
+}
+
+/// 执行所有未执行的迁移步骤
+///
+/// # Arguments
+/// * `conn` - 数据库连接
+/// * `db_path` - 数据库文件路径（用于保存迁移前备份，内存数据库传 None）
+pub(crate) fn run_migrations(conn: &mut Connection, db_path: Option<&Path>) -> Result<(), String> {
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        // 数据库由更新版本的应用创建，按列名读取仍可兼容，不做降级
+        log::warn!(
+            "[Database] 数据库版本 ({}) 高于当前支持的版本 ({})，跳过迁移",
+            current,
+            latest
+        );
+        return Ok(());
+    }
+
+    if current == latest {
+        return Ok(());
+    }
+
+    // 已有数据时先备份（全新数据库无需备份）
+    if let (true, Some(db_path)) = (has_history_table(conn)?, db_path) {
+        let backup_path = backup_database(conn, db_path, current)?;
+        log::info!("[Database] 迁移前已备份数据库: {}", backup_path.display());
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        log::info!(
+            "[Database] 执行迁移 v{}: {}",
+            migration.version,
+            migration.description
+        );
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        (migration.up)(&tx).map_err(|e| format!("迁移 v{} 失败: {}", migration.version, e))?;
+
+        tx.pragma_update(None, "user_version", migration.version)
+            .map_err(|e| format!("更新数据库版本失败: {}", e))?;
+        tx.commit()
+            .map_err(|e| format!("提交迁移 v{} 失败: {}", migration.version, e))?;
+    }
+
+    log::info!("[Database] 数据库结构已更新: v{} -> v{}", current, latest);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrates_empty_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, None).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+
+        // 再次执行不会重复迁移
+        run_migrations(&mut conn, None).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn test_adds_missing_columns_and_backs_up() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-migrations-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("history.db");
+
+        // 早期版本的表结构：缺少来源应用、排序等列
+        let mut conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE history (
+                id TEXT PRIMARY KEY,
+                type TEXT,
+                [group] TEXT,
+                value TEXT,
+                search TEXT,
+                favorite INTEGER DEFAULT 0,
+                time INTEGER DEFAULT 0
+            );
+            INSERT INTO history (id, type, value, search, time)
+            VALUES ('1', 'text', 'hello world', 'hello world', 1);",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn, Some(&db_path)).unwrap();
+
+        let item = conn
+            .query_row(
+                "SELECT * FROM history WHERE id = '1'",
+                [],
+                crate::database::history_item_from_row,
+            )
+            .unwrap();
+        assert_eq!(item.value.as_deref(), Some("hello world"));
+        assert_eq!(item.sync_status.as_deref(), Some("not_synced"));
+        assert_eq!(item.position, Some(0));
+
+        // 已有数据写入全文索引
+        let matched: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM history_fts WHERE history_fts MATCH 'world'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(matched, 1);
+
+        // 备份保留迁移前的数据
+        let backup = Connection::open(dir.join("history.db.v0.bak")).unwrap();
+        assert_eq!(schema_version(&backup).unwrap(), 0);
+        assert!(backup.prepare("SELECT position FROM history").is_err());
+
+        drop(conn);
+        drop(backup);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_failed_step_is_rolled_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        // 与 sync_conflicts 同名的视图会让 v2 建索引失败
+        conn.execute_batch("CREATE VIEW sync_conflicts AS SELECT 1 AS item_id")
+            .unwrap();
+
+        assert!(run_migrations(&mut conn, None).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+
+        // 同一步骤中已创建的表随事务回滚
+        let created: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'sync_base'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(created, 0);
+    }
+}
@@ -1,6 +1,8 @@
 //! 数据模型定义
 //! 与前端数据库表结构保持一致
 
+use crate::filter::SortInfo;
+use crate::query::QueryCondition;
 use serde::{Deserialize, Serialize};
 
 /// 剪贴板历史记录项
@@ -26,6 +28,21 @@ pub struct HistoryItem {
     pub source_app_name: Option<String>,
     pub source_app_icon: Option<String>,
     pub position: Option<i32>,
+    /// 过期时间（毫秒时间戳），None 表示不过期
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// 是否在粘贴一次后过期
+    #[serde(default)]
+    pub expire_on_paste: bool,
+    /// 预览查询中 value 是否被截断（完整内容通过 `get_item_content` 读取）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// value 转存到外部存储时完整内容的哈希（读取时自动还原，不返回给前端）
+    #[serde(skip)]
+    pub value_blob: Option<String>,
+    /// search 转存到外部存储时完整内容的哈希
+    #[serde(skip)]
+    pub search_blob: Option<String>,
 }
 
 impl Default for HistoryItem {
@@ -48,6 +65,11 @@ impl Default for HistoryItem {
             source_app_name: None,
             source_app_icon: None,
             position: Some(0),
+            expires_at: None,
+            expire_on_paste: false,
+            truncated: false,
+            value_blob: None,
+            search_blob: None,
         }
     }
 }
@@ -65,6 +87,15 @@ pub struct SyncDataItem {
     pub favorite: bool,
     pub note: Option<String>,
     pub time: i64,
+    /// 标签名称（按名称排序，逐个标签合并）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 过期时间（毫秒时间戳）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// 是否在粘贴一次后过期
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub expire_on_paste: bool,
 }
 
 impl From<HistoryItem> for SyncDataItem {
@@ -78,6 +109,10 @@ impl From<HistoryItem> for SyncDataItem {
             favorite: item.favorite == 1,
             note: item.note,
             time: item.time,
+            // 标签不在 history 表中，由调用方补充
+            tags: Vec::new(),
+            expires_at: item.expires_at,
+            expire_on_paste: item.expire_on_paste,
             // 所有元数据都保存在 value 字段中（JSON格式）
         }
     }
@@ -106,6 +141,12 @@ pub struct InsertItem {
     pub source_app_name: Option<String>,
     pub source_app_icon: Option<String>,
     pub position: Option<i32>,
+    /// 过期时间（毫秒时间戳），None 表示不过期
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// 是否在粘贴一次后过期
+    #[serde(default)]
+    pub expire_on_paste: bool,
 }
 
 /// 插入结果
@@ -116,24 +157,35 @@ pub struct InsertResult {
 }
 
 /// 查询选项
+/// 所有筛选、排序和分页条件均为结构化字段，由 `to_sql` 生成参数化查询
 #[derive(Debug, Clone, Default)]
 pub struct QueryOptions {
     /// 筛选条件
-    pub where_clause: Option<String>,
-    /// 筛选参数
-    pub params: Option<Vec<String>>,
-    /// 排序
-    pub order_by: Option<String>,
+    pub conditions: Vec<QueryCondition>,
+    /// 排序（为空时按 position 降序）
+    pub sort: Option<SortInfo>,
     /// 限制数量
     pub limit: Option<i32>,
     /// 偏移量
     pub offset: Option<i32>,
+    /// 游标（上一页最后一条记录），设置后按 (position, id) 降序分页，不能与排序和偏移量同时使用
+    pub cursor: Option<QueryCursor>,
+    /// 预览长度，设置后文本内容只返回前若干个字符
+    pub preview_length: Option<u32>,
     /// 仅收藏
     pub only_favorites: bool,
     /// 排除已删除
     pub exclude_deleted: bool,
 }
 
+/// 分页游标：上一页最后一条记录的位置和ID
+/// 新记录插入到顶部不会影响后续页面，翻页时不会跳过或重复记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueryCursor {
+    pub position: i64,
+    pub id: String,
+}
+
 /// 数据库统计信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseStatistics {
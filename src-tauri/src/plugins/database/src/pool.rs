@@ -0,0 +1,181 @@
+//! 数据库连接池
+//! 复用已打开的 SQLite 连接，避免每次操作都重新打开数据库文件和解析表结构
+//!
+//! 数据库使用 WAL 日志模式：读操作不会被写操作阻塞，写操作之间由 SQLite 串行执行，
+//! 遇到锁时在 busy timeout 内等待而不是立即返回 `database is locked`
+
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 等待数据库锁的最长时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// 每个连接缓存的预编译语句数量
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+/// 池中保留的空闲连接数量（超出的连接用完后直接关闭）
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// 启用 WAL 日志模式（journal_mode 会持久保存在数据库文件中）
+pub(crate) fn enable_wal(conn: &Connection) -> Result<(), String> {
+    let mode: String = conn
+        .query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))
+        .map_err(|e| format!("设置 WAL 模式失败: {}", e))?;
+
+    if !mode.eq_ignore_ascii_case("wal") {
+        log::warn!("[Database] 数据库不支持 WAL 模式，当前日志模式: {}", mode);
+    }
+
+    Ok(())
+}
+
+/// 打开并配置一个连接
+fn open_connection(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("打开数据库失败: {}", e))?;
+
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| format!("设置数据库超时失败: {}", e))?;
+    // WAL 模式下 NORMAL 已能保证数据库不会损坏，只是断电时可能丢失最后的事务
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| format!("设置数据库同步模式失败: {}", e))?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+
+    Ok(conn)
+}
+
+/// 数据库连接池
+#[derive(Clone)]
+pub struct ConnectionPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ConnectionPool {
+    /// 创建连接池（连接在首次使用时打开）
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            inner: Arc::new(PoolInner {
+                path,
+                idle: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// 数据库文件路径
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    /// 取出一个连接，没有空闲连接时打开新连接
+    ///
+    /// 连接在离开作用域时自动归还；同一线程可以同时持有多个连接，不会死锁
+    pub fn get(&self) -> Result<PooledConnection, String> {
+        let idle = self
+            .inner
+            .idle
+            .lock()
+            .map_err(|_| "数据库连接池已损坏".to_string())?
+            .pop();
+
+        let conn = match idle {
+            Some(conn) => conn,
+            None => open_connection(&self.inner.path)?,
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// 关闭所有空闲连接（如重置数据库、替换数据库文件之前）
+    pub fn clear(&self) {
+        if let Ok(mut idle) = self.inner.idle.lock() {
+            idle.clear();
+        }
+    }
+}
+
+/// 从连接池取出的连接，可以像 `Connection` 一样使用
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    pool: Arc<PoolInner>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("连接已归还")
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("连接已归还")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+
+        // 未结束的事务（如出错提前返回）不能带回池中
+        if !conn.is_autocommit() {
+            return;
+        }
+
+        if let Ok(mut idle) = self.pool.idle.lock() {
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push(conn);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connections_are_reused_and_see_writes() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-pool-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let pool = ConnectionPool::new(dir.join("pool.db"));
+        {
+            let conn = pool.get().unwrap();
+            enable_wal(&conn).unwrap();
+            conn.execute_batch("CREATE TABLE t (v INTEGER)").unwrap();
+        }
+
+        // 同时持有读连接和写连接
+        let reader = pool.get().unwrap();
+        let writer = pool.get().unwrap();
+        writer.execute("INSERT INTO t (v) VALUES (1)", []).unwrap();
+        let count: i64 = reader
+            .query_row("SELECT COUNT(*) FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let mode: String = reader
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        drop(reader);
+        drop(writer);
+        assert_eq!(pool.inner.idle.lock().unwrap().len(), 2);
+
+        pool.clear();
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
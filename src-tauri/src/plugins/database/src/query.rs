@@ -0,0 +1,546 @@
+//! 参数化查询构建
+//! 将结构化的筛选、排序和分页条件转换为 SQL 语句和绑定参数
+//! 列名和运算符只来自代码中的固定映射，所有外部输入都作为参数绑定，不拼接进 SQL
+
+use crate::filter::{SearchField, SortField, SortInfo, SortOrder, SyncStatus};
+use crate::models::QueryOptions;
+use rusqlite::types::Value;
+use serde::{Deserialize, Serialize};
+
+/// LIKE 匹配使用的转义字符
+const LIKE_ESCAPE: char = '\\';
+
+/// 查询条件（多个条件之间为 AND 关系）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum QueryCondition {
+    /// 指定 ID
+    Id(String),
+    /// 指定分组
+    Group(String),
+    /// 指定内容类型
+    Type(String),
+    /// 排除内容类型
+    ExcludeType(String),
+    /// 子类型属于其中之一
+    SubtypeIn(Vec<String>),
+    /// 排除子类型
+    ExcludeSubtype(String),
+    /// 指定来源应用
+    SourceApp(String),
+    /// 纯文本（无子类型的文本或格式文本）
+    PlainText,
+    /// 收藏状态
+    Favorite(bool),
+    /// 删除状态
+    Deleted(bool),
+    /// 同步状态属于其中之一
+    SyncStatusIn(Vec<SyncStatus>),
+    /// 同步状态不属于其中任何一个
+    SyncStatusNotIn(Vec<SyncStatus>),
+    /// 关键词搜索（子串匹配，关键词中的 % 和 _ 按普通字符处理）
+    Search {
+        keyword: String,
+        fields: Vec<SearchField>,
+    },
+    /// 带有其中任意一个标签（标签ID）
+    TagsAny(Vec<String>),
+    /// 带有全部标签（标签ID）
+    TagsAll(Vec<String>),
+}
+
+/// 转义 LIKE 通配符，返回子串匹配模式
+pub(crate) fn like_pattern(keyword: &str) -> String {
+    let mut pattern = String::with_capacity(keyword.len() + 2);
+    pattern.push('%');
+    for c in keyword.chars() {
+        if c == '%' || c == '_' || c == LIKE_ESCAPE {
+            pattern.push(LIKE_ESCAPE);
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// 生成 `IN (?, ?, ...)` 占位符
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+impl SyncStatus {
+    /// 数据库中保存的同步状态值
+    pub fn as_db_value(&self) -> &'static str {
+        match self {
+            SyncStatus::NotSynced => "not_synced",
+            SyncStatus::Synced => "synced",
+            SyncStatus::Changed => "changed",
+        }
+    }
+}
+
+impl SearchField {
+    /// 搜索字段对应的列
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            SearchField::Value => &["value", "search"],
+            SearchField::SearchText => &["search"],
+            SearchField::Note => &["note"],
+            SearchField::Group => &["[group]"],
+            SearchField::All => &["value", "search", "note", "[group]"],
+        }
+    }
+}
+
+impl SortInfo {
+    /// 生成 ORDER BY 子句内容
+    fn to_sql(&self) -> String {
+        let field = match self.field {
+            SortField::Time => "time",
+            SortField::Type => "type",
+            SortField::Group => "[group]",
+            SortField::Favorite => "favorite",
+        };
+
+        let order = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+
+        format!("{} {}", field, order)
+    }
+}
+
+impl QueryCondition {
+    /// 生成条件 SQL，并追加对应的绑定参数
+    fn to_sql(&self, params: &mut Vec<Value>) -> String {
+        match self {
+            QueryCondition::Id(id) => {
+                params.push(Value::Text(id.clone()));
+                "id = ?".to_string()
+            }
+            QueryCondition::Group(group) => {
+                params.push(Value::Text(group.clone()));
+                "[group] = ?".to_string()
+            }
+            QueryCondition::Type(item_type) => {
+                params.push(Value::Text(item_type.clone()));
+                "type = ?".to_string()
+            }
+            QueryCondition::ExcludeType(item_type) => {
+                params.push(Value::Text(item_type.clone()));
+                "(type IS NULL OR type != ?)".to_string()
+            }
+            QueryCondition::ExcludeSubtype(subtype) => {
+                params.push(Value::Text(subtype.clone()));
+                "(subtype IS NULL OR subtype != ?)".to_string()
+            }
+            QueryCondition::SourceApp(app_name) => {
+                params.push(Value::Text(app_name.clone()));
+                "sourceAppName = ?".to_string()
+            }
+            QueryCondition::SubtypeIn(subtypes) => {
+                if subtypes.is_empty() {
+                    return "0".to_string();
+                }
+                params.extend(subtypes.iter().cloned().map(Value::Text));
+                format!("subtype IN ({})", placeholders(subtypes.len()))
+            }
+            QueryCondition::PlainText => {
+                "((type = 'text' AND (subtype IS NULL OR subtype = '')) OR type = 'formatted')"
+                    .to_string()
+            }
+            QueryCondition::Favorite(favorite) => {
+                params.push(Value::Integer(*favorite as i64));
+                "favorite = ?".to_string()
+            }
+            QueryCondition::Deleted(true) => "deleted = 1".to_string(),
+            QueryCondition::Deleted(false) => "(deleted IS NULL OR deleted = 0)".to_string(),
+            QueryCondition::SyncStatusIn(statuses) => {
+                if statuses.is_empty() {
+                    return "0".to_string();
+                }
+                params.extend(
+                    statuses
+                        .iter()
+                        .map(|s| Value::Text(s.as_db_value().to_string())),
+                );
+                format!("syncStatus IN ({})", placeholders(statuses.len()))
+            }
+            QueryCondition::SyncStatusNotIn(statuses) => {
+                if statuses.is_empty() {
+                    return "1".to_string();
+                }
+                params.extend(
+                    statuses
+                        .iter()
+                        .map(|s| Value::Text(s.as_db_value().to_string())),
+                );
+                format!("syncStatus NOT IN ({})", placeholders(statuses.len()))
+            }
+            QueryCondition::Search { keyword, fields } => {
+                let mut columns: Vec<&str> = Vec::new();
+                let fields = if fields.is_empty() {
+                    &[SearchField::All][..]
+                } else {
+                    fields.as_slice()
+                };
+                for column in fields.iter().flat_map(|f| f.columns()) {
+                    if !columns.contains(column) {
+                        columns.push(column);
+                    }
+                }
+
+                let pattern = like_pattern(keyword);
+                let conditions: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        params.push(Value::Text(pattern.clone()));
+                        format!("{} LIKE ? ESCAPE '{}'", column, LIKE_ESCAPE)
+                    })
+                    .collect();
+
+                format!("({})", conditions.join(" OR "))
+            }
+            QueryCondition::TagsAny(tag_ids) => {
+                if tag_ids.is_empty() {
+                    return "1".to_string();
+                }
+                params.extend(tag_ids.iter().cloned().map(Value::Text));
+                format!(
+                    "id IN (SELECT item_id FROM item_tags WHERE tag_id IN ({}))",
+                    placeholders(tag_ids.len())
+                )
+            }
+            QueryCondition::TagsAll(tag_ids) => {
+                let mut tag_ids = tag_ids.clone();
+                tag_ids.sort();
+                tag_ids.dedup();
+                if tag_ids.is_empty() {
+                    return "1".to_string();
+                }
+                let count = tag_ids.len();
+                params.extend(tag_ids.into_iter().map(Value::Text));
+                params.push(Value::Integer(count as i64));
+                format!(
+                    "id IN (SELECT item_id FROM item_tags WHERE tag_id IN ({}) \
+                     GROUP BY item_id HAVING COUNT(*) = ?)",
+                    placeholders(count)
+                )
+            }
+        }
+    }
+}
+
+/// 生成预览查询的 SELECT 子句
+/// 文本内容只取前 `length` 个字符，图片和文件的 value 为路径，保持完整
+/// 转存到外部存储的内容只有摘要，始终标记为截断
+fn preview_select(length: u32, params: &mut Vec<Value>) -> String {
+    let length = Value::Integer(i64::from(length));
+    params.extend([length.clone(), length.clone(), length]);
+
+    String::from(
+        "SELECT id, type, [group],
+            CASE WHEN type IN ('image', 'files') THEN value ELSE substr(value, 1, ?) END AS value,
+            substr(search, 1, ?) AS search,
+            count, width, height, favorite, time, note, subtype, deleted, syncStatus,
+            sourceAppName, sourceAppIcon, position, expiresAt, expireOnPaste,
+            CASE WHEN valueBlob IS NOT NULL THEN 1
+                 WHEN type IN ('image', 'files') THEN 0
+                 ELSE length(value) > ? END AS truncated
+         FROM history",
+    )
+}
+
+impl QueryOptions {
+    /// 检查查询选项的组合是否有效
+    ///
+    /// 游标分页固定按 (position, id) 降序，不能与自定义排序或偏移量同时使用
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cursor.is_some() && (self.sort.is_some() || self.offset.is_some()) {
+            return Err("游标分页不能与排序或偏移量同时使用".to_string());
+        }
+        Ok(())
+    }
+
+    /// 生成历史记录查询语句和绑定参数
+    pub fn to_sql(&self) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        let mut sql = match self.preview_length {
+            Some(length) => preview_select(length, &mut params),
+            None => String::from("SELECT * FROM history"),
+        };
+        sql.push_str(" WHERE 1=1");
+
+        // 排除已删除
+        if self.exclude_deleted {
+            sql.push_str(" AND (deleted IS NULL OR deleted = 0)");
+        }
+
+        // 仅收藏
+        if self.only_favorites {
+            sql.push_str(" AND favorite = 1");
+        }
+
+        // 筛选条件
+        for condition in &self.conditions {
+            sql.push_str(" AND ");
+            sql.push_str(&condition.to_sql(&mut params));
+        }
+
+        // 游标：只返回排在上一页最后一条记录之后的记录
+        if let Some(cursor) = &self.cursor {
+            sql.push_str(" AND (position < ? OR (position = ? AND id < ?))");
+            params.push(Value::Integer(cursor.position));
+            params.push(Value::Integer(cursor.position));
+            params.push(Value::Text(cursor.id.clone()));
+        }
+
+        // 排序
+        match &self.sort {
+            Some(sort) => {
+                sql.push_str(" ORDER BY ");
+                sql.push_str(&sort.to_sql());
+            }
+            // 默认使用 position 排序（后端根据 autoSort 设置决定是否更新 position）
+            // position 相同时按 id 排序，保证游标分页的顺序稳定
+            _ => sql.push_str(" ORDER BY position DESC, id DESC"),
+        }
+
+        // 分页（SQLite 中 OFFSET 必须跟在 LIMIT 之后，-1 表示不限制数量）
+        if self.limit.is_some() || self.offset.is_some() {
+            sql.push_str(" LIMIT ?");
+            params.push(Value::Integer(self.limit.map_or(-1, i64::from)));
+        }
+        if let Some(offset) = self.offset {
+            sql.push_str(" OFFSET ?");
+            params.push(Value::Integer(offset as i64));
+        }
+
+        (sql, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HISTORY_SCHEMA;
+    use crate::filter::DataFilter;
+    use crate::models::QueryCursor;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(HISTORY_SCHEMA).unwrap();
+
+        let rows = [
+            ("1", "text", "text", "plain", 0, 100, 1),
+            ("2", "text", "it's", "quote's value", 1, 200, 2),
+            ("3", "image", "image", "C:/100%.png", 0, 300, 3),
+            ("4", "text", "text", "a_b", 1, 400, 4),
+        ];
+        for (id, item_type, group, value, favorite, time, position) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, [group], value, search, favorite, time, position)
+                 VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6, ?7)",
+                rusqlite::params![id, item_type, group, value, favorite, time, position],
+            )
+            .unwrap();
+        }
+
+        conn
+    }
+
+    fn query_ids(conn: &Connection, options: &QueryOptions) -> Vec<String> {
+        let (sql, params) = options.to_sql();
+        let mut stmt = conn.prepare(&sql).unwrap();
+        stmt.query_map(rusqlite::params_from_iter(params), |row| row.get(0))
+            .unwrap()
+            .map(|id| id.unwrap())
+            .collect()
+    }
+
+    fn with_conditions(conditions: Vec<QueryCondition>) -> QueryOptions {
+        QueryOptions {
+            conditions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quotes_are_bound_not_interpolated() {
+        let conn = setup();
+
+        let ids = query_ids(
+            &conn,
+            &DataFilter::with_group("it's".to_string()).to_query_options(None, None),
+        );
+        assert_eq!(ids, vec!["2"]);
+
+        let injected = query_ids(
+            &conn,
+            &with_conditions(vec![QueryCondition::Group("x' OR '1'='1".to_string())]),
+        );
+        assert!(injected.is_empty());
+    }
+
+    #[test]
+    fn test_hostile_search_keyword() {
+        let conn = setup();
+
+        let ids = query_ids(
+            &conn,
+            &DataFilter::with_search("'; DROP TABLE history; --".to_string())
+                .to_query_options(None, None),
+        );
+        assert!(ids.is_empty());
+
+        // 表仍然存在且数据完整
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 4);
+    }
+
+    #[test]
+    fn test_like_wildcards_are_literal() {
+        let conn = setup();
+        let search = |keyword: &str| {
+            query_ids(
+                &conn,
+                &with_conditions(vec![QueryCondition::Search {
+                    keyword: keyword.to_string(),
+                    fields: vec![SearchField::Value],
+                }]),
+            )
+        };
+
+        assert_eq!(search("%"), vec!["3"]);
+        assert_eq!(search("_"), vec!["4"]);
+        assert!(search("\\").is_empty());
+    }
+
+    #[test]
+    fn test_sort_and_pagination_are_bound() {
+        let conn = setup();
+        let options = QueryOptions {
+            sort: Some(SortInfo {
+                field: SortField::Time,
+                order: SortOrder::Asc,
+            }),
+            limit: Some(2),
+            offset: Some(1),
+            ..Default::default()
+        };
+
+        let (sql, params) = options.to_sql();
+        assert!(sql.ends_with("ORDER BY time ASC LIMIT ? OFFSET ?"));
+        assert_eq!(params, vec![Value::Integer(2), Value::Integer(1)]);
+        assert_eq!(query_ids(&conn, &options), vec!["2", "3"]);
+
+        // 只有偏移量时同样有效
+        let offset_only = QueryOptions {
+            offset: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(query_ids(&conn, &offset_only), vec!["1"]);
+    }
+
+    #[test]
+    fn test_cursor_pagination_and_preview() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        crate::migrations::run_migrations(&mut conn, None).unwrap();
+
+        let long_text = "x".repeat(100);
+        let rows = [
+            ("a", "text", long_text.as_str(), 3),
+            ("c", "image", "C:/very/long/path/image.png", 2),
+            ("b", "text", "short", 2),
+            ("d", "text", "old", 1),
+        ];
+        for (id, item_type, value, position) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, value, search, position) VALUES (?1, ?2, ?3, ?3, ?4)",
+                rusqlite::params![id, item_type, value, position],
+            )
+            .unwrap();
+        }
+
+        let page = |cursor: Option<QueryCursor>| QueryOptions {
+            cursor,
+            limit: Some(2),
+            preview_length: Some(10),
+            ..Default::default()
+        };
+
+        let (sql, params) = page(None).to_sql();
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let items: Vec<_> = stmt
+            .query_map(
+                rusqlite::params_from_iter(params),
+                crate::database::history_item_from_row,
+            )
+            .unwrap()
+            .map(|item| item.unwrap())
+            .collect();
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].value.as_deref(), Some("xxxxxxxxxx"));
+        assert_eq!(items[0].search.as_deref(), Some("xxxxxxxxxx"));
+        assert!(items[0].truncated);
+        // 图片路径保持完整
+        assert_eq!(
+            items[1].value.as_deref(),
+            Some("C:/very/long/path/image.png")
+        );
+        assert!(!items[1].truncated);
+
+        // 第一页之后插入的新记录不影响后续页面
+        conn.execute(
+            "INSERT INTO history (id, type, value, position) VALUES ('e', 'text', 'new', 4)",
+            [],
+        )
+        .unwrap();
+        let cursor = QueryCursor {
+            position: items[1].position.unwrap_or(0) as i64,
+            id: items[1].id.clone(),
+        };
+        assert_eq!(
+            query_ids(&conn, &page(Some(cursor.clone()))),
+            vec!["b", "d"]
+        );
+        assert!(page(Some(cursor.clone())).validate().is_ok());
+
+        // 游标不能与排序或偏移量同时使用
+        let with_sort = QueryOptions {
+            sort: Some(SortInfo {
+                field: SortField::Time,
+                order: SortOrder::Asc,
+            }),
+            ..page(Some(cursor.clone()))
+        };
+        assert!(with_sort.validate().is_err());
+        let with_offset = QueryOptions {
+            offset: Some(10),
+            ..page(Some(cursor))
+        };
+        assert!(with_offset.validate().is_err());
+        assert!(QueryOptions {
+            cursor: None,
+            ..with_offset
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_sort_field() {
+        let sort = serde_json::from_str::<SortInfo>(
+            r#"{"field":"time; DROP TABLE history","order":"Desc"}"#,
+        );
+        assert!(sort.is_err());
+
+        let condition = serde_json::from_str::<QueryCondition>(r#"{"type":"where","value":"1=1"}"#);
+        assert!(condition.is_err());
+    }
+}
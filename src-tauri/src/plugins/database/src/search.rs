@@ -0,0 +1,400 @@
+//! 全文搜索
+//! 基于 SQLite FTS5（trigram 分词器，支持中日韩文本的子串匹配）索引 search 和 note 字段，
+//! 索引表通过触发器与 history 表保持同步，所有写入路径（去重插入、字段更新、云端同步、
+//! OCR 结果回写等）都会自动更新索引
+//!
+//! 查询支持 FTS5 语法：短语（`"foo bar"`）、前缀（`foo*`）、布尔运算（`AND` / `OR` / `NOT`）
+//! 和括号分组；语法无效时按整句短语匹配。trigram 至少需要 3 个字符，
+//! 更短的关键词（如两个汉字）改用 LIKE 匹配
+
+use crate::database::{history_item_from_row, DatabaseManager};
+use crate::models::HistoryItem;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// 全文索引表结构及同步触发器
+pub(crate) const FTS_SCHEMA: &str = r#"
+    CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+        search,
+        note,
+        content = 'history',
+        content_rowid = 'rowid',
+        tokenize = 'trigram'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS history_fts_insert AFTER INSERT ON history BEGIN
+        INSERT INTO history_fts(rowid, search, note) VALUES (new.rowid, new.search, new.note);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS history_fts_delete AFTER DELETE ON history BEGIN
+        INSERT INTO history_fts(history_fts, rowid, search, note)
+        VALUES ('delete', old.rowid, old.search, old.note);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS history_fts_update AFTER UPDATE OF search, note ON history BEGIN
+        INSERT INTO history_fts(history_fts, rowid, search, note)
+        VALUES ('delete', old.rowid, old.search, old.note);
+        INSERT INTO history_fts(rowid, search, note) VALUES (new.rowid, new.search, new.note);
+    END;
+"#;
+
+/// trigram 分词器的最小匹配长度
+const MIN_TRIGRAM_CHARS: usize = 3;
+/// 摘要中高亮开始、结束标记（仅内部使用，返回前转换为高亮区间）
+const MARK_START: char = '\u{1}';
+const MARK_END: char = '\u{2}';
+/// 摘要省略号
+const ELLIPSIS: &str = "…";
+/// 摘要长度（FTS5 按词计算，trigram 下约为字符数）
+const SNIPPET_TOKENS: i32 = 32;
+/// LIKE 匹配时摘要两侧保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 16;
+
+/// 搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// 匹配的历史记录
+    pub item: HistoryItem,
+    /// 相关度（越大越相关）
+    pub score: f64,
+    /// 匹配内容摘要
+    pub snippet: String,
+    /// 摘要中需要高亮的区间（按字符计算，左闭右开）
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// 创建全文索引，并为已有数据建立索引
+pub(crate) fn create_fts_index(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(FTS_SCHEMA)
+        .map_err(|e| format!("创建全文索引失败: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO history_fts(history_fts) VALUES ('rebuild')",
+        [],
+    )
+    .map_err(|e| format!("重建全文索引失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 是否包含 FTS5 查询语法
+fn has_query_syntax(query: &str) -> bool {
+    query.contains(['"', '*', '(', ')'])
+        || query
+            .split_whitespace()
+            .any(|word| matches!(word, "AND" | "OR" | "NOT"))
+}
+
+/// 将整句作为短语匹配
+fn quote_phrase(query: &str) -> String {
+    format!("\"{}\"", query.replace('"', "\"\""))
+}
+
+/// 解析带标记的摘要，返回纯文本和高亮区间
+fn parse_marked_snippet(marked: &str) -> (String, Vec<(usize, usize)>) {
+    let mut snippet = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut start = None;
+    let mut position = 0;
+
+    for c in marked.chars() {
+        match c {
+            MARK_START => start = Some(position),
+            MARK_END => {
+                if let Some(start) = start.take() {
+                    if start < position {
+                        highlights.push((start, position));
+                    }
+                }
+            }
+            _ => {
+                snippet.push(c);
+                position += 1;
+            }
+        }
+    }
+
+    (snippet, highlights)
+}
+
+/// 在文本中查找关键词（忽略大小写），生成摘要和高亮区间
+fn like_snippet(text: &str, keyword: &str) -> Option<(String, Vec<(usize, usize)>)> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    let needle: Vec<char> = keyword.chars().flat_map(|c| c.to_lowercase()).collect();
+
+    // 大小写转换改变了字符数时无法对应位置，退化为从头截取
+    if needle.is_empty() || lower.len() != chars.len() {
+        return None;
+    }
+
+    let found = lower
+        .windows(needle.len())
+        .position(|w| w == needle.as_slice())?;
+    let begin = found.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (found + needle.len() + SNIPPET_CONTEXT_CHARS).min(chars.len());
+
+    let mut snippet = String::new();
+    let mut offset = 0;
+    if begin > 0 {
+        snippet.push_str(ELLIPSIS);
+        offset = ELLIPSIS.chars().count();
+    }
+    snippet.extend(&chars[begin..end]);
+    if end < chars.len() {
+        snippet.push_str(ELLIPSIS);
+    }
+
+    let start = offset + found - begin;
+    Some((snippet, vec![(start, start + needle.len())]))
+}
+
+/// 转义 LIKE 通配符
+fn escape_like(keyword: &str) -> String {
+    let mut escaped = String::with_capacity(keyword.len());
+    for c in keyword.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// 使用 FTS5 MATCH 查询，按 bm25 相关度排序
+fn search_match(
+    conn: &Connection,
+    query: &str,
+    limit: i64,
+    offset: i64,
+) -> rusqlite::Result<Vec<SearchResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT h.*, bm25(history_fts) AS rank,
+                snippet(history_fts, -1, char(1), char(2), ?2, ?3) AS snippet
+         FROM history_fts
+         JOIN history h ON h.rowid = history_fts.rowid
+         WHERE history_fts MATCH ?1 AND (h.deleted IS NULL OR h.deleted = 0)
+         ORDER BY rank
+         LIMIT ?4 OFFSET ?5",
+    )?;
+
+    let rows = stmt.query_map(
+        params![query, ELLIPSIS, SNIPPET_TOKENS, limit, offset],
+        |row| {
+            let item = history_item_from_row(row)?;
+            let rank: f64 = row.get("rank")?;
+            let marked: String = row.get("snippet")?;
+            let (snippet, highlights) = parse_marked_snippet(&marked);
+
+            Ok(SearchResult {
+                item,
+                // bm25 越小越相关，取反便于前端理解
+                score: -rank,
+                snippet,
+                highlights,
+            })
+        },
+    )?;
+
+    rows.collect()
+}
+
+/// 关键词过短时使用 LIKE 匹配，按时间排序
+fn search_like(
+    conn: &Connection,
+    keyword: &str,
+    limit: i64,
+    offset: i64,
+) -> rusqlite::Result<Vec<SearchResult>> {
+    let mut stmt = conn.prepare(
+        "SELECT * FROM history
+         WHERE (deleted IS NULL OR deleted = 0)
+           AND (search LIKE ?1 ESCAPE '\\' OR note LIKE ?1 ESCAPE '\\')
+         ORDER BY time DESC
+         LIMIT ?2 OFFSET ?3",
+    )?;
+
+    let pattern = format!("%{}%", escape_like(keyword));
+    let rows = stmt.query_map(params![pattern, limit, offset], |row| {
+        let item = history_item_from_row(row)?;
+
+        let (snippet, highlights) = [item.search.as_deref(), item.note.as_deref()]
+            .into_iter()
+            .flatten()
+            .find_map(|text| like_snippet(text, keyword))
+            .unwrap_or_else(|| {
+                let text = item.search.as_deref().unwrap_or_default();
+                (
+                    text.chars().take(SNIPPET_CONTEXT_CHARS * 2).collect(),
+                    vec![],
+                )
+            });
+
+        Ok(SearchResult {
+            item,
+            score: 0.0,
+            snippet,
+            highlights,
+        })
+    })?;
+
+    rows.collect()
+}
+
+/// 全文搜索
+pub(crate) fn search_history_with_conn(
+    conn: &Connection,
+    query: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<SearchResult>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    if !has_query_syntax(query) {
+        if query.chars().count() < MIN_TRIGRAM_CHARS {
+            return search_like(conn, query, limit, offset).map_err(|e| format!("搜索失败: {}", e));
+        }
+
+        // 普通输入按短语匹配，避免空格分隔的词被拆开匹配或被误解析为列名
+        return search_match(conn, &quote_phrase(query), limit, offset)
+            .map_err(|e| format!("搜索失败: {}", e));
+    }
+
+    match search_match(conn, query, limit, offset) {
+        Ok(results) => Ok(results),
+        Err(e) => {
+            // 语法无效（如引号不成对）时按整句短语匹配
+            log::debug!("[Database] 搜索语法无效，按短语匹配: {}", e);
+            search_match(conn, &quote_phrase(query), limit, offset)
+                .map_err(|e| format!("搜索失败: {}", e))
+        }
+    }
+}
+
+impl DatabaseManager {
+    /// 全文搜索历史记录，按相关度排序
+    ///
+    /// # Arguments
+    /// * `query` - 搜索语句（支持 FTS5 短语、前缀和布尔语法）
+    /// * `limit` - 返回数量
+    /// * `offset` - 偏移量
+    pub fn search_history(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SearchResult>, String> {
+        let conn = self.get_connection()?;
+        let mut results = search_history_with_conn(&conn, query, limit, offset)?;
+        self.rehydrate(results.iter_mut().map(|result| &mut result.item));
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HISTORY_SCHEMA;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(HISTORY_SCHEMA).unwrap();
+        create_fts_index(&conn).unwrap();
+
+        let rows = [
+            ("1", "hello world from rust", None, 100),
+            ("2", "world peace", Some("rust note"), 200),
+            ("3", "剪贴板同步工具", None, 300),
+            ("4", "deleted hello", None, 400),
+        ];
+        for (id, search, note, time) in rows {
+            conn.execute(
+                "INSERT INTO history (id, type, value, search, note, time) VALUES (?1, 'text', ?2, ?2, ?3, ?4)",
+                params![id, search, note, time],
+            )
+            .unwrap();
+        }
+        conn.execute("UPDATE history SET deleted = 1 WHERE id = '4'", [])
+            .unwrap();
+
+        conn
+    }
+
+    fn ids(conn: &Connection, query: &str) -> Vec<String> {
+        let mut ids: Vec<String> = search_history_with_conn(conn, query, 50, 0)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.item.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn test_phrase_prefix_and_boolean() {
+        let conn = setup();
+
+        assert_eq!(ids(&conn, "hello world"), vec!["1"]);
+        assert_eq!(ids(&conn, "\"world peace\""), vec!["2"]);
+        assert_eq!(ids(&conn, "wor*"), vec!["1", "2"]);
+        assert_eq!(ids(&conn, "world NOT hello"), vec!["2"]);
+        assert_eq!(ids(&conn, "peace OR rust"), vec!["1", "2"]);
+        // 语法错误时按短语匹配
+        assert!(ids(&conn, "\"unbalanced").is_empty());
+    }
+
+    #[test]
+    fn test_cjk_and_short_keywords() {
+        let conn = setup();
+
+        assert_eq!(ids(&conn, "剪贴板"), vec!["3"]);
+        assert_eq!(ids(&conn, "同步"), vec!["3"]);
+    }
+
+    #[test]
+    fn test_index_follows_updates() {
+        let conn = setup();
+
+        conn.execute(
+            "UPDATE history SET search = 'ocr recognized text' WHERE id = '3'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(ids(&conn, "recognized"), vec!["3"]);
+        assert!(ids(&conn, "剪贴板").is_empty());
+
+        conn.execute("DELETE FROM history WHERE id = '1'", [])
+            .unwrap();
+        assert_eq!(ids(&conn, "hello"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_snippet_highlights() {
+        let conn = setup();
+        let results = search_history_with_conn(&conn, "peace", 10, 0).unwrap();
+
+        assert_eq!(results.len(), 1);
+        let (start, end) = results[0].highlights[0];
+        let highlighted: String = results[0]
+            .snippet
+            .chars()
+            .skip(start)
+            .take(end - start)
+            .collect();
+        assert_eq!(highlighted, "peace");
+
+        let short = search_history_with_conn(&conn, "同步", 10, 0).unwrap();
+        let (start, end) = short[0].highlights[0];
+        let highlighted: String = short[0]
+            .snippet
+            .chars()
+            .skip(start)
+            .take(end - start)
+            .collect();
+        assert_eq!(highlighted, "同步");
+    }
+}
@@ -0,0 +1,476 @@
+//! 数据库快照
+//! 使用 SQLite 在线备份接口定期为数据库生成一致的快照（不会复制到写入一半的文件），
+//! 快照按类型轮换保留，可以列出并回滚到任意快照
+//!
+//! 快照保存在数据库所在目录的 `snapshots` 子目录中，文件名为 `<类型>-<创建时间>.db`：
+//! - `daily`：每天一份
+//! - `weekly`：每周一份
+//! - `safety`：重置数据库、导入旧版数据库、回滚等破坏性操作之前自动生成
+
+use crate::database::DatabaseManager;
+use crate::events::{emit_changes, DatabaseChanges};
+use crate::migrations::run_migrations;
+use crate::pool::enable_wal;
+use crate::DatabaseState;
+use chrono::{DateTime, Datelike, Local, TimeZone};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+
+/// 快照目录名
+const SNAPSHOT_DIR: &str = "snapshots";
+/// 定时检查间隔
+const SNAPSHOT_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 数据库被锁定时的重试次数和间隔
+const BACKUP_MAX_RETRIES: usize = 50;
+const BACKUP_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// 快照类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotKind {
+    /// 每日快照
+    Daily,
+    /// 每周快照
+    Weekly,
+    /// 破坏性操作前的安全快照
+    Safety,
+}
+
+impl SnapshotKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Safety => "safety",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "daily" => Some(Self::Daily),
+            "weekly" => Some(Self::Weekly),
+            "safety" => Some(Self::Safety),
+            _ => None,
+        }
+    }
+}
+
+/// 快照信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    /// 快照文件名（回滚时使用）
+    pub name: String,
+    pub kind: SnapshotKind,
+    /// 创建时间
+    pub created_at: i64,
+    /// 文件大小（字节）
+    pub size: u64,
+}
+
+/// 快照保留策略（每种类型保留的数量）
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotPolicy {
+    pub daily: usize,
+    pub weekly: usize,
+    pub safety: usize,
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        Self {
+            daily: 7,
+            weekly: 4,
+            safety: 5,
+        }
+    }
+}
+
+impl SnapshotPolicy {
+    fn keep(&self, kind: SnapshotKind) -> usize {
+        match kind {
+            SnapshotKind::Daily => self.daily,
+            SnapshotKind::Weekly => self.weekly,
+            SnapshotKind::Safety => self.safety,
+        }
+    }
+}
+
+/// 快照目录（数据库所在目录下）
+pub(crate) fn snapshot_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(SNAPSHOT_DIR)
+}
+
+/// 解析快照文件名，不符合格式的文件（包括路径）返回 None
+fn parse_snapshot_name(name: &str) -> Option<(SnapshotKind, i64)> {
+    let (kind, created_at) = name.strip_suffix(".db")?.split_once('-')?;
+    if created_at.is_empty() || !created_at.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((SnapshotKind::parse(kind)?, created_at.parse().ok()?))
+}
+
+/// 使用在线备份接口将数据库完整复制到目标连接
+///
+/// 一次复制所有页面，整个过程处于同一个读事务中；WAL 模式下不会阻塞其他连接写入
+fn copy_database(source: &Connection, target: &mut Connection) -> Result<(), String> {
+    let backup = Backup::new(source, target).map_err(|e| format!("复制数据库失败: {}", e))?;
+
+    for _ in 0..BACKUP_MAX_RETRIES {
+        match backup
+            .step(-1)
+            .map_err(|e| format!("复制数据库失败: {}", e))?
+        {
+            StepResult::Done => return Ok(()),
+            // 数据库被锁定时稍后重试
+            _ => std::thread::sleep(BACKUP_RETRY_DELAY),
+        }
+    }
+
+    Err("复制数据库失败: 数据库繁忙".to_string())
+}
+
+/// 为数据库生成一份快照
+///
+/// # Arguments
+/// * `conn` - 源数据库连接
+/// * `dir` - 快照目录
+/// * `kind` - 快照类型
+pub(crate) fn take_snapshot(
+    conn: &Connection,
+    dir: &Path,
+    kind: SnapshotKind,
+) -> Result<SnapshotInfo, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("创建快照目录失败: {}", e))?;
+
+    let created_at = chrono::Utc::now().timestamp_millis();
+    let name = format!("{}-{}.db", kind.as_str(), created_at);
+    let path = dir.join(&name);
+    // 先写入临时文件，完成后再改名，避免留下不完整的快照
+    let temp = path.with_extension("tmp");
+
+    let result = Connection::open(&temp)
+        .map_err(|e| format!("创建快照文件失败: {}", e))
+        .and_then(|mut target| {
+            copy_database(conn, &mut target)?;
+            // 快照是独立的单文件，不使用 WAL，便于直接打开和复制
+            target
+                .pragma_update(None, "journal_mode", "DELETE")
+                .map_err(|e| format!("设置快照日志模式失败: {}", e))
+        })
+        .and_then(|_| std::fs::rename(&temp, &path).map_err(|e| format!("保存快照失败: {}", e)));
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e);
+    }
+
+    let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    log::info!("[Snapshot] 已生成快照: {} ({} 字节)", name, size);
+
+    Ok(SnapshotInfo {
+        name,
+        kind,
+        created_at,
+        size,
+    })
+}
+
+/// 列出目录中的快照（按创建时间降序）
+pub(crate) fn list_snapshots_in(dir: &Path) -> Result<Vec<SnapshotInfo>, String> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("读取快照目录失败: {}", e))?;
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_string();
+            let (kind, created_at) = parse_snapshot_name(&name)?;
+            Some(SnapshotInfo {
+                name,
+                kind,
+                created_at,
+                size: entry.metadata().map(|m| m.len()).unwrap_or(0),
+            })
+        })
+        .collect();
+
+    snapshots.sort_by_key(|s| Reverse(s.created_at));
+    Ok(snapshots)
+}
+
+fn local_time(timestamp_millis: i64) -> Option<DateTime<Local>> {
+    Local.timestamp_millis_opt(timestamp_millis).single()
+}
+
+/// 当前需要生成的定时快照类型
+/// 当天还没有每日快照、本周还没有每周快照时生成
+fn due_kinds(snapshots: &[SnapshotInfo], now: DateTime<Local>) -> Vec<SnapshotKind> {
+    let has = |kind: SnapshotKind, same_period: &dyn Fn(DateTime<Local>) -> bool| {
+        snapshots
+            .iter()
+            .filter(|s| s.kind == kind)
+            .filter_map(|s| local_time(s.created_at))
+            .any(same_period)
+    };
+
+    let mut due = Vec::new();
+    if !has(SnapshotKind::Daily, &|t| t.date_naive() == now.date_naive()) {
+        due.push(SnapshotKind::Daily);
+    }
+    if !has(SnapshotKind::Weekly, &|t| t.iso_week() == now.iso_week()) {
+        due.push(SnapshotKind::Weekly);
+    }
+    due
+}
+
+/// 超出保留数量的快照（每种类型保留最新的若干份）
+fn expired_snapshots(snapshots: &[SnapshotInfo], policy: SnapshotPolicy) -> Vec<&SnapshotInfo> {
+    let mut sorted: Vec<&SnapshotInfo> = snapshots.iter().collect();
+    sorted.sort_by_key(|s| Reverse(s.created_at));
+
+    [
+        SnapshotKind::Daily,
+        SnapshotKind::Weekly,
+        SnapshotKind::Safety,
+    ]
+    .into_iter()
+    .flat_map(|kind| {
+        sorted
+            .iter()
+            .filter(move |s| s.kind == kind)
+            .skip(policy.keep(kind))
+            .copied()
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+/// 按保留策略清理旧快照
+///
+/// # Returns
+/// 删除的快照数量
+pub(crate) fn rotate_snapshots(dir: &Path, policy: SnapshotPolicy) -> Result<usize, String> {
+    let snapshots = list_snapshots_in(dir)?;
+    let mut removed = 0;
+
+    for snapshot in expired_snapshots(&snapshots, policy) {
+        match std::fs::remove_file(dir.join(&snapshot.name)) {
+            Ok(_) => removed += 1,
+            Err(e) => log::warn!("[Snapshot] 删除旧快照失败: {} ({})", snapshot.name, e),
+        }
+    }
+
+    Ok(removed)
+}
+
+impl DatabaseManager {
+    fn snapshot_dir(&self) -> Result<PathBuf, String> {
+        self.get_db_path()
+            .map(|path| snapshot_dir(path))
+            .ok_or_else(|| "数据库路径未设置".to_string())
+    }
+
+    /// 立即生成一份快照，并按默认策略轮换
+    pub fn create_snapshot(&self, kind: SnapshotKind) -> Result<SnapshotInfo, String> {
+        let dir = self.snapshot_dir()?;
+        let conn = self.get_connection()?;
+        let snapshot = take_snapshot(&conn, &dir, kind)?;
+
+        rotate_snapshots(&dir, SnapshotPolicy::default())?;
+        Ok(snapshot)
+    }
+
+    /// 列出所有快照（按创建时间降序）
+    pub fn list_snapshots(&self) -> Result<Vec<SnapshotInfo>, String> {
+        list_snapshots_in(&self.snapshot_dir()?)
+    }
+
+    /// 生成到期的定时快照并清理旧快照
+    ///
+    /// # Returns
+    /// 新生成的快照
+    pub fn run_scheduled_snapshots(
+        &self,
+        policy: SnapshotPolicy,
+    ) -> Result<Vec<SnapshotInfo>, String> {
+        let dir = self.snapshot_dir()?;
+        let due = due_kinds(&list_snapshots_in(&dir)?, Local::now());
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.get_connection()?;
+        let created = due
+            .into_iter()
+            .map(|kind| take_snapshot(&conn, &dir, kind))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        rotate_snapshots(&dir, policy)?;
+        Ok(created)
+    }
+
+    /// 将数据库回滚到指定快照
+    /// 回滚前会为当前数据生成一份安全快照，回滚后的数据如果来自旧版本会自动迁移
+    ///
+    /// # Arguments
+    /// * `name` - 快照文件名
+    pub fn rollback_to_snapshot(&self, name: &str) -> Result<SnapshotInfo, String> {
+        if parse_snapshot_name(name).is_none() {
+            return Err(format!("无效的快照名称: {}", name));
+        }
+
+        let dir = self.snapshot_dir()?;
+        let snapshot = list_snapshots_in(&dir)?
+            .into_iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| format!("快照不存在: {}", name))?;
+
+        // 全文索引的校验需要写权限，不能以只读方式打开
+        let source =
+            Connection::open(dir.join(name)).map_err(|e| format!("打开快照失败: {}", e))?;
+        let check: String = source
+            .query_row("PRAGMA quick_check", [], |row| row.get(0))
+            .map_err(|e| format!("校验快照失败: {}", e))?;
+        if check != "ok" {
+            return Err(format!("快照已损坏: {}", check));
+        }
+
+        let mut conn = self.get_connection()?;
+        take_snapshot(&conn, &dir, SnapshotKind::Safety)?;
+
+        copy_database(&source, &mut conn)?;
+        enable_wal(&conn)?;
+        run_migrations(&mut conn, None)?;
+
+        rotate_snapshots(&dir, SnapshotPolicy::default())?;
+        log::warn!("[Snapshot] 数据库已回滚到快照: {}", name);
+
+        Ok(snapshot)
+    }
+}
+
+/// 启动定时快照任务（每小时检查一次，启动时立即检查）
+pub fn start_snapshot_scheduler(state: DatabaseState) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let db = state.read().await;
+            if !db.is_initialized() {
+                continue;
+            }
+
+            if let Err(e) = db.run_scheduled_snapshots(SnapshotPolicy::default()) {
+                log::error!("[Snapshot] 定时快照失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 列出数据库快照
+#[tauri::command]
+pub async fn list_snapshots(state: State<'_, DatabaseState>) -> Result<Vec<SnapshotInfo>, String> {
+    let db = state.read().await;
+    db.list_snapshots()
+}
+
+/// 回滚到指定快照
+#[tauri::command]
+pub async fn rollback_snapshot<R: tauri::Runtime>(
+    name: String,
+    state: State<'_, DatabaseState>,
+    app_handle: tauri::AppHandle<R>,
+) -> Result<SnapshotInfo, String> {
+    let db = state.read().await;
+    let snapshot = db.rollback_to_snapshot(&name)?;
+
+    emit_changes(&app_handle, DatabaseChanges::reload());
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(kind: SnapshotKind, created_at: DateTime<Local>) -> SnapshotInfo {
+        SnapshotInfo {
+            name: format!("{}-{}.db", kind.as_str(), created_at.timestamp_millis()),
+            kind,
+            created_at: created_at.timestamp_millis(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn test_schedule_and_rotation() {
+        // 2024-01-10 是周三
+        let now = Local.with_ymd_and_hms(2024, 1, 10, 12, 0, 0).unwrap();
+        let days_ago = |days: i64| now - chrono::Duration::days(days);
+
+        assert_eq!(
+            due_kinds(&[], now),
+            vec![SnapshotKind::Daily, SnapshotKind::Weekly]
+        );
+
+        // 周一已有每周快照，今天只需要每日快照
+        let existing = vec![
+            snapshot(SnapshotKind::Daily, days_ago(1)),
+            snapshot(SnapshotKind::Weekly, days_ago(2)),
+        ];
+        assert_eq!(due_kinds(&existing, now), vec![SnapshotKind::Daily]);
+
+        let mut snapshots: Vec<SnapshotInfo> = (0..10)
+            .map(|day| snapshot(SnapshotKind::Daily, days_ago(day)))
+            .collect();
+        snapshots.push(snapshot(SnapshotKind::Weekly, days_ago(0)));
+
+        let expired = expired_snapshots(&snapshots, SnapshotPolicy::default());
+        assert_eq!(expired.len(), 3);
+        assert!(expired.iter().all(
+            |s| s.kind == SnapshotKind::Daily && s.created_at < days_ago(6).timestamp_millis()
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_is_restorable() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-snapshot-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('before');")
+            .unwrap();
+
+        let info = take_snapshot(&conn, &dir, SnapshotKind::Safety).unwrap();
+        assert_eq!(
+            parse_snapshot_name(&info.name),
+            Some((SnapshotKind::Safety, info.created_at))
+        );
+        assert_eq!(list_snapshots_in(&dir).unwrap().len(), 1);
+
+        conn.execute("UPDATE t SET v = 'after'", []).unwrap();
+        let source = Connection::open(dir.join(&info.name)).unwrap();
+        copy_database(&source, &mut conn).unwrap();
+
+        let value: String = conn.query_row("SELECT v FROM t", [], |r| r.get(0)).unwrap();
+        assert_eq!(value, "before");
+
+        assert!(parse_snapshot_name("../daily-1.db").is_none());
+        assert!(parse_snapshot_name("daily-1.tmp").is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,296 @@
+//! 片段（模板）
+//! 常用的固定文本（签名、评审意见、SQL 骨架等），粘贴时由 paste 插件展开其中的占位符
+//!
+//! 删除片段时只做标记并清空内容，保留的记录用于同步时通知其他设备删除。
+//! 同步以片段为单位，`updated_at` 较新的版本胜出
+
+use crate::database::DatabaseManager;
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tauri_plugin_eco_common::id::generate_id;
+
+/// 片段表结构
+pub(crate) const SNIPPETS_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS snippets (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        content TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        deleted INTEGER DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_snippets_deleted ON snippets(deleted);
+"#;
+
+/// 片段
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    /// 模板内容（可以包含占位符）
+    pub content: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+fn snippet_from_row(row: &rusqlite::Row) -> rusqlite::Result<Snippet> {
+    Ok(Snippet {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        content: row.get("content")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        deleted: row.get::<_, i32>("deleted").unwrap_or(0) != 0,
+    })
+}
+
+/// 规范化片段名称
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("片段名称不能为空".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn query_snippets(conn: &Connection, include_deleted: bool) -> Result<Vec<Snippet>, String> {
+    let sql = if include_deleted {
+        "SELECT * FROM snippets ORDER BY name COLLATE NOCASE"
+    } else {
+        "SELECT * FROM snippets WHERE deleted = 0 ORDER BY name COLLATE NOCASE"
+    };
+
+    conn.prepare_cached(sql)
+        .and_then(|mut stmt| {
+            stmt.query_map([], snippet_from_row)?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("查询片段失败: {}", e))
+}
+
+fn find_snippet(conn: &Connection, id: &str) -> Result<Snippet, String> {
+    conn.prepare_cached("SELECT * FROM snippets WHERE id = ?1 AND deleted = 0")
+        .and_then(|mut stmt| stmt.query_row(params![id], snippet_from_row))
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => format!("片段不存在: {}", id),
+            e => format!("查询片段失败: {}", e),
+        })
+}
+
+/// 写入云端的片段（只保留较新的版本）
+///
+/// # Returns
+/// 本地实际更新的片段数量
+pub(crate) fn upsert_snippets(conn: &Connection, snippets: &[Snippet]) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT INTO snippets (id, name, content, created_at, updated_at, deleted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(id) DO UPDATE SET
+                 name = excluded.name,
+                 content = excluded.content,
+                 updated_at = excluded.updated_at,
+                 deleted = excluded.deleted
+             WHERE excluded.updated_at > snippets.updated_at",
+        )
+        .map_err(|e| format!("准备写入片段失败: {}", e))?;
+
+    let mut updated = 0;
+    for snippet in snippets {
+        updated += stmt
+            .execute(params![
+                snippet.id,
+                snippet.name,
+                snippet.content,
+                snippet.created_at,
+                snippet.updated_at,
+                snippet.deleted as i32
+            ])
+            .map_err(|e| format!("写入片段失败: {}", e))?;
+    }
+
+    Ok(updated)
+}
+
+impl DatabaseManager {
+    /// 获取所有片段（按名称排序）
+    pub fn list_snippets(&self) -> Result<Vec<Snippet>, String> {
+        let conn = self.get_connection()?;
+        query_snippets(&conn, false)
+    }
+
+    /// 获取片段
+    pub fn get_snippet(&self, id: &str) -> Result<Snippet, String> {
+        let conn = self.get_connection()?;
+        find_snippet(&conn, id)
+    }
+
+    /// 创建片段
+    pub fn create_snippet(&self, name: &str, content: &str) -> Result<Snippet, String> {
+        let name = normalize_name(name)?;
+        let conn = self.get_connection()?;
+
+        let id = generate_id();
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "INSERT INTO snippets (id, name, content, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![id, name, content, now],
+        )
+        .map_err(|e| format!("创建片段失败: {}", e))?;
+
+        find_snippet(&conn, &id)
+    }
+
+    /// 修改片段名称或内容（未传入的字段保持不变）
+    pub fn update_snippet(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        content: Option<&str>,
+    ) -> Result<Snippet, String> {
+        let name = name.map(normalize_name).transpose()?;
+        let conn = self.get_connection()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE snippets SET
+                     name = COALESCE(?1, name),
+                     content = COALESCE(?2, content),
+                     updated_at = ?3
+                 WHERE id = ?4 AND deleted = 0",
+                params![name, content, chrono::Utc::now().timestamp_millis(), id],
+            )
+            .map_err(|e| format!("修改片段失败: {}", e))?;
+        if updated == 0 {
+            return Err(format!("片段不存在: {}", id));
+        }
+
+        find_snippet(&conn, id)
+    }
+
+    /// 删除片段（保留删除标记用于同步）
+    pub fn delete_snippet(&self, id: &str) -> Result<(), String> {
+        let conn = self.get_connection()?;
+
+        conn.execute(
+            "UPDATE snippets SET deleted = 1, content = '', updated_at = ?1
+             WHERE id = ?2 AND deleted = 0",
+            params![chrono::Utc::now().timestamp_millis(), id],
+        )
+        .map_err(|e| format!("删除片段失败: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 获取用于同步的片段（包括已删除的）
+    pub fn query_snippets_for_sync(&self) -> Result<Vec<Snippet>, String> {
+        let conn = self.get_connection()?;
+        query_snippets(&conn, true)
+    }
+
+    /// 合并云端的片段，每个片段保留 `updated_at` 较新的版本
+    ///
+    /// # Returns
+    /// 本地实际更新的片段数量
+    pub fn merge_remote_snippets(&self, snippets: &[Snippet]) -> Result<usize, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let updated = upsert_snippets(&conn, snippets)?;
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+        Ok(updated)
+    }
+}
+
+/// 获取所有片段
+#[tauri::command]
+pub async fn list_snippets(state: State<'_, DatabaseState>) -> Result<Vec<Snippet>, String> {
+    let db = state.read().await;
+    db.list_snippets()
+}
+
+/// 创建片段
+#[tauri::command]
+pub async fn create_snippet(
+    name: String,
+    content: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Snippet, String> {
+    let db = state.read().await;
+    db.create_snippet(&name, &content)
+}
+
+/// 修改片段
+#[tauri::command]
+pub async fn update_snippet(
+    id: String,
+    name: Option<String>,
+    content: Option<String>,
+    state: State<'_, DatabaseState>,
+) -> Result<Snippet, String> {
+    let db = state.read().await;
+    db.update_snippet(&id, name.as_deref(), content.as_deref())
+}
+
+/// 删除片段
+#[tauri::command]
+pub async fn delete_snippet(id: String, state: State<'_, DatabaseState>) -> Result<(), String> {
+    let db = state.read().await;
+    db.delete_snippet(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: &str, content: &str, updated_at: i64, deleted: bool) -> Snippet {
+        Snippet {
+            id: id.to_string(),
+            name: id.to_string(),
+            content: content.to_string(),
+            created_at: 1,
+            updated_at,
+            deleted,
+        }
+    }
+
+    #[test]
+    fn test_upsert_keeps_newer_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SNIPPETS_SCHEMA).unwrap();
+
+        let local = [
+            snippet("a", "local a", 10, false),
+            snippet("b", "local b", 10, false),
+        ];
+        assert_eq!(upsert_snippets(&conn, &local).unwrap(), 2);
+
+        // a 云端更旧，b 在云端已删除，c 是新片段
+        let remote = [
+            snippet("a", "remote a", 5, false),
+            snippet("b", "", 20, true),
+            snippet("c", "remote c", 1, false),
+        ];
+        assert_eq!(upsert_snippets(&conn, &remote).unwrap(), 2);
+
+        let all = query_snippets(&conn, true).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].content, "local a");
+        assert!(all[1].deleted);
+
+        let active = query_snippets(&conn, false).unwrap();
+        let ids: Vec<&str> = active.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert!(find_snippet(&conn, "b").is_err());
+    }
+}
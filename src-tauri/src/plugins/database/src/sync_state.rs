@@ -0,0 +1,381 @@
+//! 同步状态存储
+//! 保存每个项目的同步基准版本（三方合并的 base）以及字段级冲突记录
+
+use crate::blobs::offload_content;
+use crate::database::DatabaseManager;
+use crate::models::SyncDataItem;
+use crate::tags::replace_item_tags_by_name;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// 同步冲突记录
+/// 两端自上次同步后都修改了同一字段且结果不同时产生
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    /// 冲突ID（新建时忽略）
+    pub id: i64,
+    /// 项目ID
+    pub item_id: String,
+    /// 冲突字段（favorite / note / value / subtype / expiresAt / expireOnPaste）
+    pub field: String,
+    /// 上次同步时的值
+    pub base_value: Option<String>,
+    /// 本地修改后的值
+    pub local_value: Option<String>,
+    /// 云端修改后的值
+    pub remote_value: Option<String>,
+    /// 产生时间
+    pub created_at: i64,
+    /// 是否已解决
+    pub resolved: bool,
+}
+
+/// 同步状态相关表结构
+pub(crate) const SYNC_STATE_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS sync_base (
+        id TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS sync_conflicts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        item_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        base_value TEXT,
+        local_value TEXT,
+        remote_value TEXT,
+        created_at INTEGER NOT NULL,
+        resolved INTEGER DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_sync_conflicts_item ON sync_conflicts(item_id);
+"#;
+
+/// 字段名对应的数据库列名（白名单，防止拼接任意列名）
+fn conflict_column(field: &str) -> Result<&'static str, String> {
+    match field {
+        "note" => Ok("note"),
+        "value" => Ok("value"),
+        "subtype" => Ok("subtype"),
+        "favorite" => Ok("favorite"),
+        "expiresAt" => Ok("expiresAt"),
+        "expireOnPaste" => Ok("expireOnPaste"),
+        _ => Err(format!("不支持的冲突字段: {}", field)),
+    }
+}
+
+impl DatabaseManager {
+    /// 读取所有项目的同步基准版本
+    pub fn load_sync_bases(&self) -> Result<HashMap<String, SyncDataItem>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare("SELECT id, data FROM sync_base")
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("查询同步基准失败: {}", e))?;
+
+        let mut bases = HashMap::new();
+        for row in rows.flatten() {
+            match serde_json::from_str::<SyncDataItem>(&row.1) {
+                Ok(item) => {
+                    bases.insert(row.0, item);
+                }
+                Err(e) => log::warn!("[Database] 解析同步基准失败: {} ({})", row.0, e),
+            }
+        }
+
+        Ok(bases)
+    }
+
+    /// 保存同步基准版本（覆盖已有记录）
+    pub fn save_sync_bases(&self, items: &[SyncDataItem]) -> Result<(), String> {
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_connection()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {}", e))?;
+
+        for item in items {
+            let data = serde_json::to_string(item)
+                .map_err(|e| format!("序列化同步基准失败: {}", e))?;
+            tx.execute(
+                "INSERT OR REPLACE INTO sync_base (id, data) VALUES (?1, ?2)",
+                params![item.id, data],
+            )
+            .map_err(|e| format!("保存同步基准失败: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))
+    }
+
+    /// 删除同步基准版本（项目从云端移除时调用）
+    pub fn remove_sync_bases(&self, ids: &[String]) -> Result<(), String> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_connection()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {}", e))?;
+
+        for id in ids {
+            tx.execute("DELETE FROM sync_base WHERE id = ?1", params![id])
+                .map_err(|e| format!("删除同步基准失败: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))
+    }
+
+    /// 将合并后的字段写回本地（保持同步状态为已同步）
+    pub fn apply_merged_item(&self, item: &SyncDataItem) -> Result<(), String> {
+        let conn = self.get_connection()?;
+        let stored = offload_content(
+            self.blob_dir().as_deref(),
+            Some(item.item_type.as_str()),
+            item.value.as_deref(),
+            None,
+        )?;
+
+        conn.execute(
+            "UPDATE history SET favorite = ?1, note = ?2, value = ?3, valueBlob = ?4, subtype = ?5, time = ?6,
+                expiresAt = ?7, expireOnPaste = ?8, syncStatus = 'synced'
+            WHERE id = ?9",
+            params![
+                item.favorite,
+                item.note,
+                stored.value,
+                stored.value_blob,
+                item.subtype,
+                item.time,
+                item.expires_at,
+                item.expire_on_paste,
+                item.id,
+            ],
+        )
+        .map_err(|e| format!("写入合并结果失败: {}", e))?;
+
+        replace_item_tags_by_name(&conn, &item.id, &item.tags)?;
+
+        Ok(())
+    }
+
+    /// 记录同步冲突
+    /// 同一项目同一字段只保留最新一条未解决的冲突
+    pub fn insert_sync_conflicts(&self, conflicts: &[SyncConflict]) -> Result<(), String> {
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_connection()?;
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("开启事务失败: {}", e))?;
+
+        for conflict in conflicts {
+            tx.execute(
+                "DELETE FROM sync_conflicts WHERE item_id = ?1 AND field = ?2 AND resolved = 0",
+                params![conflict.item_id, conflict.field],
+            )
+            .map_err(|e| format!("清理旧冲突失败: {}", e))?;
+
+            tx.execute(
+                "INSERT INTO sync_conflicts (item_id, field, base_value, local_value, remote_value, created_at, resolved)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0)",
+                params![
+                    conflict.item_id,
+                    conflict.field,
+                    conflict.base_value,
+                    conflict.local_value,
+                    conflict.remote_value,
+                    conflict.created_at,
+                ],
+            )
+            .map_err(|e| format!("记录同步冲突失败: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("提交事务失败: {}", e))
+    }
+
+    /// 查询同步冲突
+    ///
+    /// # Arguments
+    /// * `include_resolved` - 是否包含已解决的冲突
+    pub fn list_sync_conflicts(&self, include_resolved: bool) -> Result<Vec<SyncConflict>, String> {
+        let conn = self.get_connection()?;
+
+        let sql = if include_resolved {
+            "SELECT id, item_id, field, base_value, local_value, remote_value, created_at, resolved
+            FROM sync_conflicts ORDER BY created_at DESC"
+        } else {
+            "SELECT id, item_id, field, base_value, local_value, remote_value, created_at, resolved
+            FROM sync_conflicts WHERE resolved = 0 ORDER BY created_at DESC"
+        };
+
+        let mut stmt = conn
+            .prepare(sql)
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+
+        let rows = stmt
+            .query_map([], Self::map_conflict_row)
+            .map_err(|e| format!("查询同步冲突失败: {}", e))?;
+
+        Ok(rows.flatten().collect())
+    }
+
+    /// 根据ID查询同步冲突
+    pub fn get_sync_conflict(&self, conflict_id: i64) -> Result<Option<SyncConflict>, String> {
+        let conn = self.get_connection()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, item_id, field, base_value, local_value, remote_value, created_at, resolved
+                FROM sync_conflicts WHERE id = ?1",
+            )
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+
+        let mut rows = stmt
+            .query_map(params![conflict_id], Self::map_conflict_row)
+            .map_err(|e| format!("查询同步冲突失败: {}", e))?;
+
+        Ok(rows.next().and_then(|r| r.ok()))
+    }
+
+    /// 解决同步冲突
+    ///
+    /// # Arguments
+    /// * `conflict_id` - 冲突ID
+    /// * `value` - 需要写回本地的值（None 表示保留当前值）
+    pub fn resolve_sync_conflict(
+        &self,
+        conflict_id: i64,
+        value: Option<Option<String>>,
+    ) -> Result<SyncConflict, String> {
+        let conflict = self
+            .get_sync_conflict(conflict_id)?
+            .ok_or_else(|| format!("冲突记录不存在: {}", conflict_id))?;
+
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        if let Some(value) = value {
+            Self::write_conflict_field(&conn, &conflict.item_id, &conflict.field, value)?;
+            self.get_change_tracker().mark_item_changed(
+                &conn,
+                &conflict.item_id,
+                "conflict_resolved",
+                &[conflict.field.as_str()],
+                false,
+            )?;
+        }
+
+        conn.execute(
+            "UPDATE sync_conflicts SET resolved = 1 WHERE id = ?1",
+            params![conflict_id],
+        )
+        .map_err(|e| format!("更新冲突状态失败: {}", e))?;
+
+        conn.commit()
+            .map_err(|e| format!("提交事务失败: {}", e))?;
+
+        Ok(SyncConflict {
+            resolved: true,
+            ..conflict
+        })
+    }
+
+    /// 写入冲突字段的值
+    fn write_conflict_field(
+        conn: &Connection,
+        item_id: &str,
+        field: &str,
+        value: Option<String>,
+    ) -> Result<(), String> {
+        let column = conflict_column(field)?;
+
+        if column == "favorite" || column == "expireOnPaste" {
+            let flag = matches!(value.as_deref(), Some("1") | Some("true"));
+            let sql = format!("UPDATE history SET {} = ?1 WHERE id = ?2", column);
+            conn.execute(&sql, params![flag, item_id])
+        } else if column == "expiresAt" {
+            let expires_at = value.and_then(|v| v.parse::<i64>().ok());
+            conn.execute(
+                "UPDATE history SET expiresAt = ?1 WHERE id = ?2",
+                params![expires_at, item_id],
+            )
+        } else {
+            let sql = format!("UPDATE history SET {} = ?1 WHERE id = ?2", column);
+            conn.execute(&sql, params![value, item_id])
+        }
+        .map_err(|e| format!("写入冲突字段失败: {}", e))?;
+
+        Ok(())
+    }
+
+    fn map_conflict_row(row: &rusqlite::Row) -> rusqlite::Result<SyncConflict> {
+        Ok(SyncConflict {
+            id: row.get(0)?,
+            item_id: row.get(1)?,
+            field: row.get(2)?,
+            base_value: row.get(3)?,
+            local_value: row.get(4)?,
+            remote_value: row.get(5)?,
+            created_at: row.get(6)?,
+            resolved: row.get::<_, i32>(7)? != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_merged_item_writes_expiry() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-sync-state-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO history (id, type, value, time, syncStatus) VALUES ('1', 'text', 'hello', 1, 'synced')",
+                [],
+            )
+            .unwrap();
+
+        // 只有云端修改了过期时间
+        let merged = SyncDataItem {
+            expires_at: Some(5_000),
+            expire_on_paste: true,
+            ..SyncDataItem::from(db.query_by_id("1").unwrap().unwrap())
+        };
+        db.apply_merged_item(&merged).unwrap();
+
+        let (expires_at, expire_on_paste): (Option<i64>, bool) = db
+            .get_connection()
+            .unwrap()
+            .query_row(
+                "SELECT expiresAt, expireOnPaste FROM history WHERE id = '1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(expires_at, Some(5_000));
+        assert!(expire_on_paste);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
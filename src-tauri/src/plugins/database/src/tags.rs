@@ -0,0 +1,559 @@
+//! 标签
+//! 标签与历史记录为多对多关系（`tags` + `item_tags`），与表示内容类别的 `group` 列相互独立
+//!
+//! 标签按名称（不区分大小写）唯一，同步时以名称标识，各设备上的标签ID可以不同。
+//! 修改记录的标签、重命名或删除标签时，受影响的记录会被标记为待同步
+
+use crate::database::DatabaseManager;
+use crate::query::like_pattern;
+use crate::DatabaseState;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::State;
+use tauri_plugin_eco_common::id::generate_id;
+
+/// 标签相关表结构
+pub(crate) const TAGS_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS tags (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL COLLATE NOCASE UNIQUE,
+        color TEXT,
+        created_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS item_tags (
+        item_id TEXT NOT NULL,
+        tag_id TEXT NOT NULL,
+        PRIMARY KEY (item_id, tag_id)
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_item_tags_tag ON item_tags(tag_id);
+
+    -- 删除记录或标签时清理关联
+    CREATE TRIGGER IF NOT EXISTS history_item_tags_ad AFTER DELETE ON history BEGIN
+        DELETE FROM item_tags WHERE item_id = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS tags_item_tags_ad AFTER DELETE ON tags BEGIN
+        DELETE FROM item_tags WHERE tag_id = old.id;
+    END;
+"#;
+
+/// 标签
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: i64,
+    /// 使用该标签的记录数量（不含已删除）
+    #[serde(default)]
+    pub item_count: i64,
+}
+
+fn tag_from_row(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        color: row.get("color")?,
+        created_at: row.get("created_at")?,
+        item_count: row.get("item_count").unwrap_or(0),
+    })
+}
+
+/// 标签查询（附带记录数量）
+const TAG_SELECT: &str = "SELECT t.id, t.name, t.color, t.created_at,
+        (SELECT COUNT(*) FROM item_tags it JOIN history h ON h.id = it.item_id
+         WHERE it.tag_id = t.id AND COALESCE(h.deleted, 0) = 0) AS item_count
+    FROM tags t";
+
+/// 规范化标签名称
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("标签名称不能为空".to_string());
+    }
+    Ok(name.to_string())
+}
+
+fn query_tags(conn: &Connection, filter: &str, param: &str) -> Result<Vec<Tag>, String> {
+    let sql = format!("{} {} ORDER BY t.name COLLATE NOCASE", TAG_SELECT, filter);
+    let mut stmt = conn
+        .prepare_cached(&sql)
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let rows = if filter.is_empty() {
+        stmt.query_map([], tag_from_row)
+    } else {
+        stmt.query_map(params![param], tag_from_row)
+    }
+    .map_err(|e| format!("查询标签失败: {}", e))?;
+
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("查询标签失败: {}", e))
+}
+
+fn get_tag(conn: &Connection, id: &str) -> Result<Tag, String> {
+    query_tags(conn, "WHERE t.id = ?1", id)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("标签不存在: {}", id))
+}
+
+/// 按名称查找标签，不存在时创建
+fn find_or_create_tag(conn: &Connection, name: &str) -> Result<String, String> {
+    let existing: Option<String> = conn
+        .prepare_cached("SELECT id FROM tags WHERE name = ?1")
+        .and_then(|mut stmt| stmt.query_row(params![name], |row| row.get(0)).optional())
+        .map_err(|e| format!("查询标签失败: {}", e))?;
+
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let id = generate_id();
+    conn.execute(
+        "INSERT INTO tags (id, name, created_at) VALUES (?1, ?2, ?3)",
+        params![id, name, chrono::Utc::now().timestamp_millis()],
+    )
+    .map_err(|e| format!("创建标签失败: {}", e))?;
+    Ok(id)
+}
+
+/// 使用指定标签的记录ID
+fn tagged_item_ids(conn: &Connection, tag_id: &str) -> Result<Vec<String>, String> {
+    conn.prepare_cached("SELECT item_id FROM item_tags WHERE tag_id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map(params![tag_id], |row| row.get(0))?
+                .collect::<Result<_, _>>()
+        })
+        .map_err(|e| format!("查询标签记录失败: {}", e))
+}
+
+/// 所有记录的标签名称（按名称排序），用于同步
+pub(crate) fn item_tag_names(conn: &Connection) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT it.item_id, t.name FROM item_tags it JOIN tags t ON t.id = it.tag_id
+             ORDER BY it.item_id, t.name COLLATE NOCASE",
+        )
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("查询记录标签失败: {}", e))?;
+
+    let mut names: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        let (item_id, name) = row.map_err(|e| format!("查询记录标签失败: {}", e))?;
+        names.entry(item_id).or_default().push(name);
+    }
+    Ok(names)
+}
+
+/// 按名称替换记录的标签（同步写入云端数据时使用，不存在的标签会自动创建）
+pub(crate) fn replace_item_tags_by_name(
+    conn: &Connection,
+    item_id: &str,
+    names: &[String],
+) -> Result<(), String> {
+    conn.execute("DELETE FROM item_tags WHERE item_id = ?1", params![item_id])
+        .map_err(|e| format!("清理记录标签失败: {}", e))?;
+
+    for name in names {
+        let Ok(name) = normalize_name(name) else {
+            continue;
+        };
+        let tag_id = find_or_create_tag(conn, &name)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+            params![item_id, tag_id],
+        )
+        .map_err(|e| format!("写入记录标签失败: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// 书签分组（sync 插件保存的 bookmark-data.json）
+#[derive(Deserialize)]
+struct BookmarkGroup {
+    id: String,
+    name: String,
+    #[serde(default)]
+    color: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BookmarkData {
+    #[serde(default)]
+    groups: Vec<BookmarkGroup>,
+}
+
+/// 将书签分组导入为标签
+/// 书签分组通过搜索名称筛选记录，导入时为当前匹配该名称的记录添加对应标签
+///
+/// # Returns
+/// 导入的标签数量
+pub(crate) fn import_bookmark_groups(conn: &Connection, path: &Path) -> Result<usize, String> {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return Ok(0);
+    };
+    let data: BookmarkData = match serde_json::from_str(&content) {
+        Ok(data) => data,
+        Err(e) => {
+            log::warn!("[Tags] 书签数据无法解析，跳过导入: {}", e);
+            return Ok(0);
+        }
+    };
+
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut imported = 0;
+
+    for group in data.groups {
+        let Ok(name) = normalize_name(&group.name) else {
+            continue;
+        };
+
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![group.id, name, group.color, now],
+            )
+            .map_err(|e| format!("导入书签分组失败: {}", e))?;
+        if inserted == 0 {
+            continue;
+        }
+
+        conn.execute(
+            "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+             SELECT id, ?1 FROM history
+             WHERE COALESCE(deleted, 0) = 0
+               AND (search LIKE ?2 ESCAPE '\\' OR value LIKE ?2 ESCAPE '\\' OR note LIKE ?2 ESCAPE '\\')",
+            params![group.id, like_pattern(&name)],
+        )
+        .map_err(|e| format!("导入书签分组失败: {}", e))?;
+        imported += 1;
+    }
+
+    if imported > 0 {
+        log::info!("[Tags] 已将 {} 个书签分组导入为标签", imported);
+    }
+    Ok(imported)
+}
+
+impl DatabaseManager {
+    /// 标记记录的标签已修改（与标签修改使用同一事务）
+    fn mark_tags_changed(&self, conn: &Connection, item_ids: &[String]) -> Result<(), String> {
+        for item_id in item_ids {
+            self.get_change_tracker()
+                .mark_item_changed(conn, item_id, "tags", &["tags"], false)?;
+        }
+        Ok(())
+    }
+
+    /// 获取所有标签（按名称排序）
+    pub fn list_tags(&self) -> Result<Vec<Tag>, String> {
+        let conn = self.get_connection()?;
+        query_tags(&conn, "", "")
+    }
+
+    /// 创建标签
+    pub fn create_tag(&self, name: &str, color: Option<String>) -> Result<Tag, String> {
+        let name = normalize_name(name)?;
+        let conn = self.get_connection()?;
+
+        let id = generate_id();
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO tags (id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![id, name, color, chrono::Utc::now().timestamp_millis()],
+            )
+            .map_err(|e| format!("创建标签失败: {}", e))?;
+        if inserted == 0 {
+            return Err(format!("标签已存在: {}", name));
+        }
+
+        get_tag(&conn, &id)
+    }
+
+    /// 修改标签名称或颜色
+    /// 重命名时使用该标签的记录会被标记为待同步
+    pub fn update_tag(
+        &self,
+        id: &str,
+        name: Option<&str>,
+        color: Option<String>,
+    ) -> Result<Tag, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+        let current = get_tag(&conn, id)?;
+
+        if let Some(name) = name.map(normalize_name).transpose()? {
+            if name != current.name {
+                conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![name, id])
+                    .map_err(|e| match e {
+                        rusqlite::Error::SqliteFailure(err, _)
+                            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+                        {
+                            format!("标签已存在: {}", name)
+                        }
+                        e => format!("修改标签失败: {}", e),
+                    })?;
+                self.mark_tags_changed(&conn, &tagged_item_ids(&conn, id)?)?;
+            }
+        }
+
+        if let Some(color) = color {
+            conn.execute(
+                "UPDATE tags SET color = ?1 WHERE id = ?2",
+                params![color, id],
+            )
+            .map_err(|e| format!("修改标签失败: {}", e))?;
+        }
+
+        let tag = get_tag(&conn, id)?;
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+        Ok(tag)
+    }
+
+    /// 删除标签（记录本身保留，只移除关联）
+    ///
+    /// # Returns
+    /// 受影响的记录数量
+    pub fn delete_tag(&self, id: &str) -> Result<usize, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let item_ids = tagged_item_ids(&conn, id)?;
+        self.mark_tags_changed(&conn, &item_ids)?;
+
+        conn.execute("DELETE FROM tags WHERE id = ?1", params![id])
+            .map_err(|e| format!("删除标签失败: {}", e))?;
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+        Ok(item_ids.len())
+    }
+
+    /// 获取记录的标签
+    pub fn get_item_tags(&self, item_id: &str) -> Result<Vec<Tag>, String> {
+        let conn = self.get_connection()?;
+        query_tags(
+            &conn,
+            "WHERE t.id IN (SELECT tag_id FROM item_tags WHERE item_id = ?1)",
+            item_id,
+        )
+    }
+
+    /// 设置记录的标签（替换原有标签）
+    pub fn set_item_tags(&self, item_id: &str, tag_ids: &[String]) -> Result<Vec<Tag>, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let mut current: Vec<String> = conn
+            .prepare_cached("SELECT tag_id FROM item_tags WHERE item_id = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_map(params![item_id], |row| row.get(0))?
+                    .collect::<Result<_, _>>()
+            })
+            .map_err(|e| format!("查询记录标签失败: {}", e))?;
+        let mut target = tag_ids.to_vec();
+        current.sort();
+        target.sort();
+        target.dedup();
+
+        if current != target {
+            conn.execute("DELETE FROM item_tags WHERE item_id = ?1", params![item_id])
+                .map_err(|e| format!("清理记录标签失败: {}", e))?;
+
+            for tag_id in &target {
+                get_tag(&conn, tag_id)?;
+                conn.execute(
+                    "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                    params![item_id, tag_id],
+                )
+                .map_err(|e| format!("写入记录标签失败: {}", e))?;
+            }
+
+            self.mark_tags_changed(&conn, &[item_id.to_string()])?;
+        }
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+        drop(pooled);
+
+        self.get_item_tags(item_id)
+    }
+
+    /// 所有记录的标签名称（按记录ID分组），用于备份
+    pub fn all_item_tag_names(&self) -> Result<HashMap<String, Vec<String>>, String> {
+        let conn = self.get_connection()?;
+        item_tag_names(&conn)
+    }
+
+    /// 按名称设置记录的标签（替换原有标签，不存在的标签会自动创建），用于恢复备份
+    pub fn set_item_tag_names(&self, item_id: &str, names: &[String]) -> Result<(), String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        replace_item_tags_by_name(&conn, item_id, names)?;
+        self.mark_tags_changed(&conn, &[item_id.to_string()])?;
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))
+    }
+}
+
+/// 获取所有标签
+#[tauri::command]
+pub async fn list_tags(state: State<'_, DatabaseState>) -> Result<Vec<Tag>, String> {
+    let db = state.read().await;
+    db.list_tags()
+}
+
+/// 创建标签
+#[tauri::command]
+pub async fn create_tag(
+    name: String,
+    color: Option<String>,
+    state: State<'_, DatabaseState>,
+) -> Result<Tag, String> {
+    let db = state.read().await;
+    db.create_tag(&name, color)
+}
+
+/// 修改标签
+#[tauri::command]
+pub async fn update_tag(
+    id: String,
+    name: Option<String>,
+    color: Option<String>,
+    state: State<'_, DatabaseState>,
+) -> Result<Tag, String> {
+    let db = state.read().await;
+    db.update_tag(&id, name.as_deref(), color)
+}
+
+/// 删除标签
+#[tauri::command]
+pub async fn delete_tag(id: String, state: State<'_, DatabaseState>) -> Result<usize, String> {
+    let db = state.read().await;
+    db.delete_tag(&id)
+}
+
+/// 获取记录的标签
+#[tauri::command]
+pub async fn get_item_tags(
+    item_id: String,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<Tag>, String> {
+    let db = state.read().await;
+    db.get_item_tags(&item_id)
+}
+
+/// 设置记录的标签
+#[tauri::command]
+pub async fn set_item_tags(
+    item_id: String,
+    tag_ids: Vec<String>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<Tag>, String> {
+    let db = state.read().await;
+    db.set_item_tags(&item_id, &tag_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::HISTORY_SCHEMA;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(HISTORY_SCHEMA).unwrap();
+        conn.execute_batch(TAGS_SCHEMA).unwrap();
+        for (id, value) in [
+            ("1", "rust notes"),
+            ("2", "shopping list"),
+            ("3", "Rust 100%"),
+        ] {
+            conn.execute(
+                "INSERT INTO history (id, type, value, search) VALUES (?1, 'text', ?2, ?2)",
+                params![id, value],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn test_tags_by_name_and_cleanup() {
+        let conn = setup();
+
+        replace_item_tags_by_name(&conn, "1", &["Work".into(), " work ".into(), "".into()])
+            .unwrap();
+        replace_item_tags_by_name(&conn, "2", &["home".into(), "WORK".into()]).unwrap();
+
+        // 名称不区分大小写，同名标签只创建一次
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let names = item_tag_names(&conn).unwrap();
+        assert_eq!(names["1"], vec!["Work"]);
+        assert_eq!(names["2"], vec!["home", "Work"]);
+
+        conn.execute("DELETE FROM history WHERE id = '2'", [])
+            .unwrap();
+        let work = find_or_create_tag(&conn, "work").unwrap();
+        assert_eq!(tagged_item_ids(&conn, &work).unwrap(), vec!["1"]);
+
+        conn.execute("DELETE FROM tags WHERE id = ?1", params![work])
+            .unwrap();
+        assert!(item_tag_names(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_import_bookmark_groups() {
+        let conn = setup();
+        let path =
+            std::env::temp_dir().join(format!("ecopaste-bookmarks-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r##"{"last_modified":1,"groups":[
+                {"id":"custom_1","name":"rust","color":"#ff0000"},
+                {"id":"custom_2","name":"100%","color":"#00ff00"},
+                {"id":"custom_3","name":"  ","color":"#0000ff"}
+            ]}"##,
+        )
+        .unwrap();
+
+        assert_eq!(import_bookmark_groups(&conn, &path).unwrap(), 2);
+        // 再次导入不会重复创建
+        assert_eq!(import_bookmark_groups(&conn, &path).unwrap(), 0);
+        std::fs::remove_file(&path).unwrap();
+
+        let tags = query_tags(&conn, "", "").unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[1].name, "rust");
+        assert_eq!(tags[1].color.as_deref(), Some("#ff0000"));
+        assert_eq!(tags[1].item_count, 2);
+        assert_eq!(tagged_item_ids(&conn, "custom_2").unwrap(), vec!["3"]);
+
+        assert_eq!(
+            import_bookmark_groups(&conn, Path::new("/nonexistent/bookmark-data.json")).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! 删除记录（墓碑）
+//! 从 history 表中移除记录时（硬删除、同步后清理、重置数据库等），在同一事务中写入墓碑，
+//! 同步时与云端交换，保证删除在所有设备上生效，已删除的项目不会被重新下载或上传
+//!
+//! 墓碑超过保留期后被清理（见 [`DatabaseManager::purge_tombstones`]）
+
+use crate::change_log::local_device_id;
+use crate::database::DatabaseManager;
+use crate::images::image_paths_of;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 墓碑表结构
+pub(crate) const TOMBSTONE_SCHEMA: &str = r#"
+    CREATE TABLE IF NOT EXISTS history_tombstones (
+        id TEXT PRIMARY KEY,
+        deleted_at INTEGER NOT NULL,
+        device_id TEXT NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_history_tombstones_deleted_at ON history_tombstones(deleted_at);
+"#;
+
+/// 默认保留天数
+pub const DEFAULT_TOMBSTONE_RETENTION_DAYS: u32 = 30;
+
+/// 删除记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tombstone {
+    /// 被删除的项目ID
+    pub id: String,
+    /// 删除时间
+    pub deleted_at: i64,
+    /// 执行删除的设备
+    pub device_id: String,
+}
+
+/// 为即将删除的项目写入墓碑（应与删除操作使用同一事务）
+/// 已有墓碑的项目保留最初的删除时间
+pub(crate) fn record_tombstones(conn: &Connection, ids: &[String]) -> Result<usize, String> {
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    let device_id = local_device_id(conn)?;
+    let deleted_at = chrono::Utc::now().timestamp_millis();
+
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT OR IGNORE INTO history_tombstones (id, deleted_at, device_id) VALUES (?1, ?2, ?3)",
+        )
+        .map_err(|e| format!("准备写入墓碑失败: {}", e))?;
+
+    let mut count = 0;
+    for id in ids {
+        count += stmt
+            .execute(params![id, deleted_at, device_id])
+            .map_err(|e| format!("写入墓碑失败: {}", e))?;
+    }
+
+    Ok(count)
+}
+
+/// 应用一条墓碑
+///
+/// # Returns
+/// 本地是否删除了该项目
+fn apply_tombstone(conn: &Connection, tombstone: &Tombstone) -> Result<bool, String> {
+    let local: Option<i64> = conn
+        .prepare_cached("SELECT COALESCE(deleted, 0) FROM history WHERE id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_row(params![tombstone.id], |row| row.get(0))
+                .optional()
+        })
+        .map_err(|e| format!("查询项目失败: {}", e))?;
+
+    // 本地在删除之后又修改过（或重新写入，如恢复备份）且尚未同步的项目以本地为准，墓碑作废
+    if local == Some(0) {
+        let modified_after: bool = conn
+            .prepare_cached(
+                "SELECT EXISTS(SELECT 1 FROM history_changes WHERE item_id = ?1 AND created_at > ?2)",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_row(params![tombstone.id, tombstone.deleted_at], |row| {
+                    row.get(0)
+                })
+            })
+            .map_err(|e| format!("查询变更日志失败: {}", e))?;
+
+        if modified_after {
+            conn.execute(
+                "DELETE FROM history_tombstones WHERE id = ?1",
+                params![tombstone.id],
+            )
+            .map_err(|e| format!("删除墓碑失败: {}", e))?;
+            log::info!(
+                "[Tombstone] 项目在删除后被修改，保留本地版本: {}",
+                tombstone.id
+            );
+            return Ok(false);
+        }
+    }
+
+    conn.prepare_cached(
+        "INSERT OR IGNORE INTO history_tombstones (id, deleted_at, device_id) VALUES (?1, ?2, ?3)",
+    )
+    .and_then(|mut stmt| {
+        stmt.execute(params![
+            tombstone.id,
+            tombstone.deleted_at,
+            tombstone.device_id
+        ])
+    })
+    .map_err(|e| format!("写入墓碑失败: {}", e))?;
+
+    if local.is_none() {
+        return Ok(false);
+    }
+
+    conn.execute("DELETE FROM history WHERE id = ?1", params![tombstone.id])
+        .map_err(|e| format!("删除项目失败: {}", e))?;
+    conn.execute("DELETE FROM sync_base WHERE id = ?1", params![tombstone.id])
+        .map_err(|e| format!("删除同步基准失败: {}", e))?;
+
+    Ok(true)
+}
+
+impl DatabaseManager {
+    /// 获取所有墓碑（按ID排序）
+    pub fn get_tombstones(&self) -> Result<Vec<Tombstone>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id, deleted_at, device_id FROM history_tombstones ORDER BY id")
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Tombstone {
+                    id: row.get(0)?,
+                    deleted_at: row.get(1)?,
+                    device_id: row.get(2)?,
+                })
+            })
+            .map_err(|e| format!("查询墓碑失败: {}", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("查询墓碑失败: {}", e))
+    }
+
+    /// 应用其他设备的墓碑：删除本地对应的项目并保存墓碑
+    ///
+    /// 本地在删除时间之后修改过且变更尚未同步的项目会被保留，对应的墓碑作废
+    ///
+    /// # Returns
+    /// 本地被删除的项目ID
+    pub fn apply_tombstones(&self, tombstones: &[Tombstone]) -> Result<Vec<String>, String> {
+        if tombstones.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let ids: Vec<String> = tombstones.iter().map(|t| t.id.clone()).collect();
+        let images = image_paths_of(&conn, &ids)?;
+
+        let mut deleted = Vec::new();
+        for tombstone in tombstones {
+            if apply_tombstone(&conn, tombstone)? {
+                deleted.push(tombstone.id.clone());
+            }
+        }
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+        self.release_images(&images)?;
+
+        Ok(deleted)
+    }
+
+    /// 移除墓碑（项目被重新写入时，如恢复备份）
+    pub fn remove_tombstones(&self, ids: &[String]) -> Result<usize, String> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare_cached("DELETE FROM history_tombstones WHERE id = ?1")
+            .map_err(|e| format!("准备删除墓碑失败: {}", e))?;
+
+        let mut count = 0;
+        for id in ids {
+            count += stmt
+                .execute(params![id])
+                .map_err(|e| format!("删除墓碑失败: {}", e))?;
+        }
+
+        Ok(count)
+    }
+
+    /// 移除过时的墓碑：云端项目在删除之后又被修改过（如在其他设备上从回收站恢复），以云端为准
+    ///
+    /// # Arguments
+    /// * `items` - 云端项目的ID和时间
+    ///
+    /// # Returns
+    /// 被移除墓碑的项目ID
+    pub fn remove_stale_tombstones(&self, items: &[(&str, i64)]) -> Result<Vec<String>, String> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare_cached("DELETE FROM history_tombstones WHERE id = ?1 AND deleted_at < ?2")
+            .map_err(|e| format!("准备删除墓碑失败: {}", e))?;
+
+        let mut removed = Vec::new();
+        for (id, time) in items {
+            if stmt
+                .execute(params![id, time])
+                .map_err(|e| format!("删除墓碑失败: {}", e))?
+                > 0
+            {
+                removed.push(id.to_string());
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// 清理超过保留期的墓碑
+    ///
+    /// # Arguments
+    /// * `retention_days` - 保留天数
+    ///
+    /// # Returns
+    /// 清理的墓碑数量
+    pub fn purge_tombstones(&self, retention_days: u32) -> Result<usize, String> {
+        let conn = self.get_connection()?;
+        let cutoff = tombstone_cutoff(retention_days);
+
+        conn.execute(
+            "DELETE FROM history_tombstones WHERE deleted_at < ?1",
+            params![cutoff],
+        )
+        .map_err(|e| format!("清理墓碑失败: {}", e))
+    }
+}
+
+/// 保留期的起始时间，早于该时间的墓碑可以清理
+pub fn tombstone_cutoff(retention_days: u32) -> i64 {
+    (chrono::Utc::now() - chrono::Duration::days(retention_days as i64)).timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change_log::{append_change, CHANGE_LOG_SCHEMA};
+    use crate::database::HISTORY_SCHEMA;
+    use crate::sync_state::SYNC_STATE_SCHEMA;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for schema in [
+            HISTORY_SCHEMA,
+            SYNC_STATE_SCHEMA,
+            CHANGE_LOG_SCHEMA,
+            TOMBSTONE_SCHEMA,
+        ] {
+            conn.execute_batch(schema).unwrap();
+        }
+        conn
+    }
+
+    fn insert(conn: &Connection, id: &str, time: i64, status: &str) {
+        conn.execute(
+            "INSERT INTO history (id, type, value, time, syncStatus) VALUES (?1, 'text', ?1, ?2, ?3)",
+            params![id, time, status],
+        )
+        .unwrap();
+    }
+
+    fn exists(conn: &Connection, id: &str) -> bool {
+        conn.query_row("SELECT 1 FROM history WHERE id = ?1", params![id], |_| {
+            Ok(())
+        })
+        .optional()
+        .unwrap()
+        .is_some()
+    }
+
+    fn tombstone(id: &str, deleted_at: i64) -> Tombstone {
+        Tombstone {
+            id: id.to_string(),
+            deleted_at,
+            device_id: "device-remote".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_keeps_first_deletion_time() {
+        let conn = setup();
+        let ids = vec!["a".to_string()];
+
+        assert_eq!(record_tombstones(&conn, &ids).unwrap(), 1);
+        let first: i64 = conn
+            .query_row("SELECT deleted_at FROM history_tombstones", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+
+        assert_eq!(record_tombstones(&conn, &ids).unwrap(), 0);
+        let second: i64 = conn
+            .query_row("SELECT deleted_at FROM history_tombstones", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_remote_tombstone_deletes_unless_modified_later() {
+        let conn = setup();
+        insert(&conn, "synced", 100, "synced");
+        insert(&conn, "edited", 300, "changed");
+        append_change(&conn, "edited", "note", &["note"]).unwrap();
+
+        assert!(apply_tombstone(&conn, &tombstone("synced", 200)).unwrap());
+        assert!(!exists(&conn, "synced"));
+
+        // 删除之后本地又修改过，保留本地版本
+        assert!(!apply_tombstone(&conn, &tombstone("edited", 200)).unwrap());
+        assert!(exists(&conn, "edited"));
+
+        // 本地不存在的项目也保存墓碑，防止之后被重新下载
+        assert!(!apply_tombstone(&conn, &tombstone("missing", 200)).unwrap());
+
+        let ids: Vec<String> = conn
+            .prepare("SELECT id FROM history_tombstones ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, vec!["missing", "synced"]);
+    }
+}
@@ -0,0 +1,374 @@
+//! 回收站
+//! 用户删除的记录先软删除（`deleted = 1`）放入回收站，可以恢复或彻底删除：
+//! 1. 最近一次删除在短时间内可以撤销（见 `undo_delete`）
+//! 2. 同步时回收站中的记录从云端删除并写入墓碑，本地记录保留在回收站中
+//! 3. 恢复时移除墓碑并记录变更：云端删除尚未执行时直接取消，已从云端删除时重新上传
+//! 4. 清空回收站时硬删除记录并释放图片，超过保留天数的记录在自动清理时彻底删除
+
+use crate::database::DatabaseManager;
+use crate::delete::DeleteManager;
+use crate::events::{emit_changes, ChangeOperation, DatabaseChanges};
+use crate::filter::{SortField, SortInfo, SortOrder};
+use crate::models::{HistoryItem, QueryOptions};
+use crate::query::QueryCondition;
+use crate::sensitive::mask_sensitive_items;
+use crate::tombstone::record_tombstones;
+use crate::DatabaseState;
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime, State};
+
+/// 回收站保留天数，超过后在自动清理时彻底删除
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// 撤销最近一次删除的有效时间
+const UNDO_WINDOW: Duration = Duration::from_secs(30);
+
+/// 最近一次删除的记录
+struct DeleteBatch {
+    ids: Vec<String>,
+    deleted_at: Instant,
+}
+
+static LAST_DELETE_BATCH: Mutex<Option<DeleteBatch>> = Mutex::new(None);
+
+/// 记录最近一次删除（放入回收站的记录），供撤销使用
+pub fn record_delete_batch(ids: &[String]) {
+    if ids.is_empty() {
+        return;
+    }
+
+    if let Ok(mut batch) = LAST_DELETE_BATCH.lock() {
+        *batch = Some(DeleteBatch {
+            ids: ids.to_vec(),
+            deleted_at: Instant::now(),
+        });
+    }
+}
+
+/// 取出仍在撤销有效期内的最近一次删除
+fn take_undoable_batch() -> Vec<String> {
+    let Ok(mut batch) = LAST_DELETE_BATCH.lock() else {
+        return Vec::new();
+    };
+
+    match batch.take() {
+        Some(batch) if batch.deleted_at.elapsed() <= UNDO_WINDOW => batch.ids,
+        _ => Vec::new(),
+    }
+}
+
+/// 在给定的记录中筛选出回收站中的记录
+fn trashed_among(conn: &Connection, ids: &[String]) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT 1 FROM history WHERE id = ?1 AND deleted = 1")
+        .map_err(|e| format!("准备查询失败: {}", e))?;
+
+    let mut trashed = Vec::new();
+    for id in ids {
+        if stmt
+            .exists(params![id])
+            .map_err(|e| format!("查询回收站失败: {}", e))?
+        {
+            trashed.push(id.clone());
+        }
+    }
+
+    Ok(trashed)
+}
+
+impl DatabaseManager {
+    /// 分页查询回收站（按删除时间从新到旧）
+    pub fn list_trash(
+        &self,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Vec<HistoryItem>, String> {
+        self.query_history(QueryOptions {
+            conditions: vec![QueryCondition::Deleted(true)],
+            sort: Some(SortInfo {
+                field: SortField::Time,
+                order: SortOrder::Desc,
+            }),
+            limit,
+            offset,
+            ..Default::default()
+        })
+    }
+
+    /// 回收站中所有记录的ID
+    pub fn trashed_ids(&self) -> Result<HashSet<String>, String> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn
+            .prepare_cached("SELECT id FROM history WHERE deleted = 1")
+            .map_err(|e| format!("准备查询失败: {}", e))?;
+
+        let ids = stmt
+            .query_map([], |row| row.get(0))
+            .and_then(|rows| rows.collect::<Result<HashSet<String>, _>>())
+            .map_err(|e| format!("查询回收站失败: {}", e))?;
+
+        Ok(ids)
+    }
+
+    /// 从回收站恢复记录
+    /// 移除墓碑并记录变更，下次同步时取消云端删除或重新上传；
+    /// 同时清除已到期的过期时间和粘贴后过期设置，避免恢复后再次被自动删除
+    ///
+    /// # Returns
+    /// 恢复的记录ID
+    pub fn restore_items(&self, ids: &[String]) -> Result<Vec<String>, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let restored = trashed_among(&conn, ids)?;
+        for id in &restored {
+            conn.execute(
+                "UPDATE history SET deleted = 0,
+                    expiresAt = CASE WHEN expiresAt <= ?1 THEN NULL ELSE expiresAt END,
+                    expireOnPaste = 0
+                 WHERE id = ?2",
+                params![now, id],
+            )
+            .map_err(|e| format!("恢复记录失败: {}", e))?;
+            conn.execute("DELETE FROM history_tombstones WHERE id = ?1", params![id])
+                .map_err(|e| format!("删除墓碑失败: {}", e))?;
+
+            // 更新时间戳晚于墓碑，其他设备据此放弃已应用的删除
+            self.get_change_tracker().mark_item_changed(
+                &conn,
+                id,
+                "restore",
+                &["deleted", "expiresAt", "expireOnPaste"],
+                false,
+            )?;
+        }
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+        Ok(restored)
+    }
+
+    /// 同步已将回收站中的记录从云端删除：写入墓碑并标记为未同步，本地记录保留在回收站中
+    ///
+    /// # Returns
+    /// 回收站中的记录ID（不在回收站中的ID被忽略）
+    pub fn settle_trashed_items(&self, ids: &[String]) -> Result<Vec<String>, String> {
+        let pooled = self.get_connection()?;
+        let conn = pooled
+            .unchecked_transaction()
+            .map_err(|e| format!("开始事务失败: {}", e))?;
+
+        let trashed = trashed_among(&conn, ids)?;
+        record_tombstones(&conn, &trashed)?;
+        for id in &trashed {
+            conn.execute(
+                "UPDATE history SET syncStatus = 'not_synced' WHERE id = ?1",
+                params![id],
+            )
+            .map_err(|e| format!("更新同步状态失败: {}", e))?;
+        }
+
+        conn.commit().map_err(|e| format!("提交事务失败: {}", e))?;
+
+        Ok(trashed)
+    }
+
+    /// 彻底删除回收站中的记录
+    ///
+    /// # Arguments
+    /// * `ids` - 要删除的记录，None 表示清空整个回收站
+    ///
+    /// # Returns
+    /// 删除的记录ID
+    pub fn empty_trash(&self, ids: Option<&[String]>) -> Result<Vec<String>, String> {
+        let trashed = match ids {
+            Some(ids) => {
+                let conn = self.get_connection()?;
+                trashed_among(&conn, ids)?
+            }
+            None => self.trashed_ids()?.into_iter().collect(),
+        };
+
+        DeleteManager::batch_hard_delete(self, &trashed)?;
+        Ok(trashed)
+    }
+
+    /// 彻底删除放入回收站超过保留天数的记录
+    ///
+    /// # Returns
+    /// 删除的记录ID
+    pub fn purge_trash(&self, retention_days: i64) -> Result<Vec<String>, String> {
+        let cutoff =
+            (chrono::Utc::now() - chrono::Duration::days(retention_days)).timestamp_millis();
+        let expired = {
+            let conn = self.get_connection()?;
+            let mut stmt = conn
+                .prepare_cached("SELECT id FROM history WHERE deleted = 1 AND time < ?1")
+                .map_err(|e| format!("准备查询失败: {}", e))?;
+            stmt.query_map(params![cutoff], |row| row.get(0))
+                .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+                .map_err(|e| format!("查询回收站失败: {}", e))?
+        };
+
+        self.empty_trash(Some(&expired))
+    }
+}
+
+/// 分页查询回收站
+#[tauri::command]
+pub async fn list_trash(
+    limit: Option<i32>,
+    offset: Option<i32>,
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<HistoryItem>, String> {
+    let db = state.read().await;
+    let mut items = db.list_trash(limit, offset)?;
+    mask_sensitive_items(&mut items);
+    Ok(items)
+}
+
+/// 从回收站恢复记录
+///
+/// # Returns
+/// 恢复的记录ID
+#[tauri::command]
+pub async fn restore_items<R: Runtime>(
+    ids: Vec<String>,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<Vec<String>, String> {
+    let db = state.read().await;
+    let restored = db.restore_items(&ids)?;
+
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(ChangeOperation::Restored, &restored, &[]),
+    );
+
+    Ok(restored)
+}
+
+/// 彻底删除回收站中的记录
+///
+/// # Arguments
+/// * `ids` - 要删除的记录，为空时清空整个回收站
+///
+/// # Returns
+/// 删除的记录数量
+#[tauri::command]
+pub async fn empty_trash<R: Runtime>(
+    ids: Option<Vec<String>>,
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<usize, String> {
+    let db = state.read().await;
+    let deleted = db.empty_trash(ids.as_deref())?;
+
+    log::info!("[Database] 已彻底删除回收站中的 {} 条记录", deleted.len());
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(ChangeOperation::Deleted, &deleted, &[]),
+    );
+
+    Ok(deleted.len())
+}
+
+/// 撤销最近一次删除（仅在删除后的短时间内有效）
+///
+/// # Returns
+/// 恢复的记录ID，没有可撤销的删除时为空
+#[tauri::command]
+pub async fn undo_delete<R: Runtime>(
+    state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<Vec<String>, String> {
+    let ids = take_undoable_batch();
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let db = state.read().await;
+    let restored = db.restore_items(&ids)?;
+
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(ChangeOperation::Restored, &restored, &[]),
+    );
+
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations::run_migrations;
+
+    #[test]
+    fn test_trashed_among_and_undo_window() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run_migrations(&mut conn, None).unwrap();
+
+        for (id, deleted) in [("trashed", 1), ("active", 0)] {
+            conn.execute(
+                "INSERT INTO history (id, type, value, deleted) VALUES (?1, 'text', 'hello', ?2)",
+                params![id, deleted],
+            )
+            .unwrap();
+        }
+
+        let ids = [
+            "trashed".to_string(),
+            "active".to_string(),
+            "missing".to_string(),
+        ];
+        assert_eq!(
+            trashed_among(&conn, &ids).unwrap(),
+            vec!["trashed".to_string()]
+        );
+
+        record_delete_batch(&["trashed".to_string()]);
+        assert_eq!(take_undoable_batch(), vec!["trashed".to_string()]);
+        // 撤销只能执行一次
+        assert!(take_undoable_batch().is_empty());
+    }
+
+    #[test]
+    fn test_restore_expired_item_stays_alive() {
+        let dir = std::env::temp_dir().join(format!("ecopaste-trash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+        db.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO history (id, type, value, deleted, expiresAt, expireOnPaste)
+                 VALUES ('1', 'text', 'hello', 0, 1000, 1)",
+                [],
+            )
+            .unwrap();
+
+        // 过期任务放入回收站后恢复
+        assert_eq!(db.delete_expired_items().unwrap(), vec!["1".to_string()]);
+        assert_eq!(
+            db.restore_items(&["1".to_string()]).unwrap(),
+            vec!["1".to_string()]
+        );
+        assert!(db.delete_expired_items().unwrap().is_empty());
+
+        let item = db.query_by_id("1").unwrap().unwrap();
+        assert_eq!(item.deleted, Some(0));
+        assert_eq!(item.expires_at, None);
+        assert!(!item.expire_on_paste);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
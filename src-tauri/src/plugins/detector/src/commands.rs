@@ -11,9 +11,12 @@ pub use tauri_plugin_eco_common::types::detection::{
     ColorConvertResult, ColorConvertType, LegacyDetectionOptions,
 };
 
+/// 敏感内容的子类型
+pub const SENSITIVE_SUBTYPE: &str = "sensitive";
+
 /// 检测内容类型
 ///
-/// 检测顺序：URL → 邮箱 → 路径 → 颜色 → 代码 → Markdown
+/// 检测顺序：敏感内容 → URL → 邮箱 → 路径 → 颜色 → 代码 → Markdown
 /// 如果匹配到某个类型，立即返回，不会继续检测其他类型
 #[tauri::command]
 pub async fn detect_content(
@@ -41,9 +44,18 @@ pub async fn detect_content(
 /// 运行类型检测（按优先级）
 pub fn run_detection(content: &str, options: &DetectionOptions) -> DetectionResult {
     use crate::detectors::{
-        detect_code, detect_color, detect_email, detect_markdown, detect_path, detect_url,
+        detect_code, detect_color, detect_email, detect_markdown, detect_path, detect_secret,
+        detect_url,
     };
 
+    // 敏感内容检测（优先于其他类型，包含令牌的 URL 也按敏感内容处理）
+    if options.detect_secret && detect_secret(content).is_some() {
+        return DetectionResult {
+            subtype: Some(SENSITIVE_SUBTYPE.to_string()),
+            ..Default::default()
+        };
+    }
+
     // URL 检测
     if options.detect_url && detect_url(content) {
         return DetectionResult {
@@ -111,6 +123,7 @@ impl From<LegacyDetectionOptions> for DetectionOptions {
             detect_color: opt.detect_color,
             detect_code: opt.detect_code,
             detect_markdown: opt.detect_markdown,
+            detect_secret: true,
             code_min_length: 10,
         }
     }
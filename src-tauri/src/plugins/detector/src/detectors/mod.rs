@@ -9,6 +9,7 @@ mod color;
 mod email;
 mod markdown;
 mod path;
+mod secret;
 mod url;
 
 pub use code::detect_code;
@@ -17,6 +18,7 @@ pub use color::{conversion, detect_color, get_color_format};
 pub use email::detect_email;
 pub use markdown::detect_markdown;
 pub use path::detect_path;
+pub use secret::{detect_secret, SecretKind};
 pub use url::detect_url;
 
 /// 检测结果
@@ -39,9 +41,16 @@ pub struct DetectionOptions {
     pub detect_color: bool,
     pub detect_code: bool,
     pub detect_markdown: bool,
+    /// 敏感内容检测（未传入时开启）
+    #[serde(default = "default_detect_secret")]
+    pub detect_secret: bool,
     pub code_min_length: usize,
 }
 
+fn default_detect_secret() -> bool {
+    true
+}
+
 impl Default for DetectionOptions {
     fn default() -> Self {
         Self {
@@ -51,6 +60,7 @@ impl Default for DetectionOptions {
             detect_color: true,
             detect_code: false,
             detect_markdown: true,
+            detect_secret: true,
             code_min_length: 10,
         }
     }
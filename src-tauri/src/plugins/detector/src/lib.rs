@@ -12,9 +12,11 @@ pub use commands::convert_color;
 pub use commands::detect_content;
 pub use commands::run_detection;
 pub use commands::ColorConvertResult;
+pub use commands::SENSITIVE_SUBTYPE;
 pub use detectors::conversion::find_similar_color;
 pub use detectors::{
-    conversion, detect_color, get_color_format, DetectionOptions, DetectionResult,
+    conversion, detect_color, detect_secret, get_color_format, DetectionOptions, DetectionResult,
+    SecretKind,
 };
 pub use models::TypeDetectionResult;
 
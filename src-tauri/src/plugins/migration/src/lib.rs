@@ -102,7 +102,7 @@ async fn run_migration<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
 
     // 获取 database 插件状态
     let db_state = app.state::<tauri_plugin_eco_database::DatabaseState>();
-    let db_manager = db_state.lock().await;
+    let db_manager = db_state.read().await;
 
     // 获取 detector 插件状态
     let detector_state = app.state::<tauri_plugin_eco_detector::DetectorState>();
@@ -167,6 +167,8 @@ async fn run_migration<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
             source_app_name: item.source_app_name.clone(),
             source_app_icon: item.source_app_icon.clone(),
             position: None,
+            expires_at: None,
+            expire_on_paste: false,
         };
 
         match db_manager.insert_with_deduplication(&insert_item, app) {
@@ -1,4 +1,4 @@
-const COMMANDS: &[&str] = &["paste", "paste_with_focus", "quick_paste", "batch_paste", "single_paste", "paste_color"];
+const COMMANDS: &[&str] = &["paste", "paste_with_focus", "quick_paste", "batch_paste", "single_paste", "paste_color", "paste_snippet", "expand_snippet", "get_snippet_inputs"];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS).build();
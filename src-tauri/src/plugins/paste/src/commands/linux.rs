@@ -179,6 +179,18 @@ pub async fn batch_paste<R: Runtime>(
     Ok(())
 }
 
+// 片段粘贴命令 - Linux 存根实现
+#[command]
+pub async fn paste_snippet<R: Runtime>(
+    _app_handle: AppHandle<R>,
+    _id: String,
+    _inputs: Option<std::collections::HashMap<String, String>>,
+) -> Result<(), String> {
+    // Linux: 剪贴板写入由 clipboard 插件处理
+    // 此处仅返回成功，由前端通过 expand_snippet 展开后写入剪贴板
+    Ok(())
+}
+
 // 颜色粘贴命令 - Linux 存根实现
 #[command]
 pub async fn paste_color<R: Runtime>(
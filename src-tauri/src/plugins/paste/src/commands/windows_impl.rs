@@ -5,9 +5,14 @@ use enigo::{
 };
 use std::sync::Arc;
 use tauri::{command, AppHandle, Manager, Runtime};
-use tauri_plugin_eco_database::{DatabaseState, HistoryItem, QueryOptions};
+use tauri_plugin_eco_database::{
+    handle_items_pasted, DatabaseState, HistoryItem, QueryCondition, QueryOptions, SortField,
+    SortInfo, SortOrder,
+};
+use std::collections::HashMap;
 use tokio::sync::Mutex;
 
+use crate::snippet::expand_snippet_by_id;
 use tauri_plugin_eco_common::active_window::{get_last_valid_window_info, restore_focus_to_window};
 
 use winapi::um::winuser::{GetAsyncKeyState, VK_CONTROL, VK_LSHIFT, VK_LWIN, VK_MENU, VK_RSHIFT, VK_RWIN};
@@ -263,15 +268,17 @@ pub async fn quick_paste<R: Runtime>(app_handle: AppHandle<R>, index: u32) -> Re
     let db_state_arc = Arc::clone(&db_state);
     let query_index = index;
     let items: Vec<HistoryItem> = tokio::task::spawn_blocking(move || {
-        let db = db_state_arc.blocking_lock();
+        let db = db_state_arc.blocking_read();
         let options = QueryOptions {
             only_favorites: false,
             exclude_deleted: true,
             limit: Some(1),
             offset: Some((query_index - 1) as i32),
-            order_by: Some("time DESC".to_string()),
-            where_clause: None,
-            params: None,
+            sort: Some(SortInfo {
+                field: SortField::Time,
+                order: SortOrder::Desc,
+            }),
+            conditions: Vec::new(),
         };
         db.query_history(options)
     })
@@ -289,7 +296,7 @@ pub async fn quick_paste<R: Runtime>(app_handle: AppHandle<R>, index: u32) -> Re
     let value = item.value.clone().unwrap_or_default();
     let search = item.search.clone().unwrap_or_default();
 
-    let _id = item.id.clone();
+    let id = item.id.clone();
     let write_result = write_to_clipboard(&app_handle, item_type, subtype, &value, &search, false);
 
     match write_result {
@@ -298,6 +305,12 @@ pub async fn quick_paste<R: Runtime>(app_handle: AppHandle<R>, index: u32) -> Re
                 tokio::time::sleep(std::time::Duration::from_millis(20)).await;
             }
             paste().await;
+
+            // 粘贴后过期的记录在粘贴完成后删除
+            let db = db_state.read().await;
+            if let Err(e) = handle_items_pasted(&app_handle, &db, &[id]) {
+                log::warn!("[Paste] 处理粘贴后过期失败: {}", e);
+            }
             Ok(())
         }
         Err(e) => Err(e),
@@ -329,7 +342,7 @@ pub async fn batch_paste<R: Runtime>(
 
     // 查询所有指定 ID 的项目
     let items: Vec<HistoryItem> = tokio::task::spawn_blocking(move || {
-        let db = db_state_arc.blocking_lock();
+        let db = db_state_arc.blocking_read();
         let mut all_items = Vec::new();
 
         for id in &ids {
@@ -338,9 +351,8 @@ pub async fn batch_paste<R: Runtime>(
                 exclude_deleted: true,
                 limit: Some(1),
                 offset: None,
-                order_by: None,
-                where_clause: Some("id = ?".to_string()),
-                params: Some(vec![id.clone()]),
+                sort: None,
+                conditions: vec![QueryCondition::Id(id.clone())],
             };
 
             if let Ok(mut items) = db.query_history(options) {
@@ -443,15 +455,14 @@ pub async fn single_paste<R: Runtime>(
 
     // 查询指定 ID 的项目
     let item: Option<HistoryItem> = tokio::task::spawn_blocking(move || {
-        let db = db_state_arc.blocking_lock();
+        let db = db_state_arc.blocking_read();
         let options = QueryOptions {
             only_favorites: false,
             exclude_deleted: true,
             limit: Some(1),
             offset: None,
-            order_by: None,
-            where_clause: Some("id = ?".to_string()),
-            params: Some(vec![id]),
+            sort: None,
+            conditions: vec![QueryCondition::Id(id)],
         };
 
         let mut items = db.query_history(options)?;
@@ -495,6 +506,37 @@ pub async fn single_paste<R: Runtime>(
     }
 }
 
+// ==================== 片段粘贴命令 ====================
+
+#[command]
+pub async fn paste_snippet<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    // 在写入剪贴板之前展开，{clipboard} 才能取到原来的内容
+    let expansion = expand_snippet_by_id(&app_handle, &id, &inputs.unwrap_or_default()).await?;
+
+    write_to_clipboard(&app_handle, "text", None, &expansion.text, &expansion.text, true)?;
+
+    let delay = get_write_delay_ms("text", expansion.text.len());
+    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+    paste_with_focus().await;
+
+    // 将光标移动到 {cursor} 所在位置
+    if let Some(offset) = expansion.cursor_offset.filter(|&offset| offset > 0) {
+        let paste_delay = get_paste_delay_ms("text", expansion.text.len());
+        tokio::time::sleep(std::time::Duration::from_millis(paste_delay)).await;
+
+        let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
+        for _ in 0..offset {
+            enigo.key(Key::LeftArrow, Click).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
 // ==================== 颜色粘贴命令 ====================
 
 #[command]
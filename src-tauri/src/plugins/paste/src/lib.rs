@@ -5,8 +5,12 @@ use tauri::{
 };
 
 mod commands;
+mod placeholder;
+mod snippet;
 
 pub use commands::*;
+pub use placeholder::*;
+pub use snippet::*;
 
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     Builder::new("eco-paste")
@@ -43,7 +47,10 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             commands::quick_paste,
             commands::batch_paste,
             commands::single_paste,
-            commands::paste_color
+            commands::paste_color,
+            commands::paste_snippet,
+            snippet::expand_snippet,
+            snippet::get_snippet_inputs
         ])
         .build()
 }
@@ -0,0 +1,215 @@
+//! 片段占位符展开
+//! 粘贴片段前将模板中的占位符替换为实际内容：
+//! - `{date}` / `{date:%Y-%m-%d}`：当前日期（可指定 strftime 格式）
+//! - `{time}` / `{time:%H:%M}`：当前时间（可指定 strftime 格式）
+//! - `{clipboard}`：粘贴前剪贴板中的文本
+//! - `{uuid}`：随机 UUID
+//! - `{input:名称}`：粘贴时由用户填写的内容
+//! - `{cursor}`：粘贴后光标所在的位置
+//!
+//! `{{` 和 `}}` 表示字面量括号；无法识别的 `{...}` 原样保留，代码片段中的括号不需要转义
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+
+/// 展开时使用的上下文
+pub struct ExpandContext<'a> {
+    /// 当前时间
+    pub now: DateTime<Local>,
+    /// 粘贴前剪贴板中的文本
+    pub clipboard: Option<&'a str>,
+    /// 用户填写的输入（名称 -> 内容）
+    pub inputs: &'a HashMap<String, String>,
+}
+
+/// 展开结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expansion {
+    pub text: String,
+    /// 光标位置距文本末尾的字符数（模板中没有 `{cursor}` 时为 None）
+    pub cursor_offset: Option<usize>,
+}
+
+/// 模板片段
+enum Token<'a> {
+    /// 普通文本
+    Text(&'a str),
+    /// 占位符（名称, 参数）
+    Placeholder(&'a str, Option<&'a str>),
+}
+
+/// 拆分模板
+fn tokenize(template: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+
+    while let Some(pos) = rest.find(['{', '}']) {
+        if pos > 0 {
+            tokens.push(Token::Text(&rest[..pos]));
+        }
+        rest = &rest[pos..];
+
+        // 转义的括号
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            tokens.push(Token::Text(&rest[..1]));
+            rest = &rest[2..];
+            continue;
+        }
+
+        let placeholder = rest
+            .strip_prefix('{')
+            .and_then(|inner| inner.find(['{', '}']).map(|end| (inner, end)))
+            .filter(|(inner, end)| inner[*end..].starts_with('}'));
+
+        match placeholder {
+            Some((inner, end)) => {
+                let body = &inner[..end];
+                let (name, arg) = match body.split_once(':') {
+                    Some((name, arg)) => (name, Some(arg)),
+                    None => (body, None),
+                };
+                tokens.push(Token::Placeholder(name, arg));
+                rest = &inner[end + 1..];
+            }
+            None => {
+                tokens.push(Token::Text(&rest[..1]));
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    tokens
+}
+
+/// 按 strftime 格式输出时间，格式无效时返回错误（而不是在格式化时 panic）
+fn format_time(now: &DateTime<Local>, format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("无效的时间格式: {}", format));
+    }
+    Ok(now.format_with_items(items.into_iter()).to_string())
+}
+
+/// 模板中需要用户填写的输入名称（按出现顺序，去重）
+pub fn input_names(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for token in tokenize(template) {
+        if let Token::Placeholder("input", Some(name)) = token {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// 展开模板中的占位符
+pub fn expand(template: &str, ctx: &ExpandContext) -> Result<Expansion, String> {
+    let mut text = String::with_capacity(template.len());
+    let mut cursor = None;
+
+    for token in tokenize(template) {
+        match token {
+            Token::Text(s) => text.push_str(s),
+            Token::Placeholder("date", arg) => {
+                text.push_str(&format_time(&ctx.now, arg.unwrap_or(DEFAULT_DATE_FORMAT))?)
+            }
+            Token::Placeholder("time", arg) => {
+                text.push_str(&format_time(&ctx.now, arg.unwrap_or(DEFAULT_TIME_FORMAT))?)
+            }
+            Token::Placeholder("clipboard", None) => text.push_str(ctx.clipboard.unwrap_or("")),
+            Token::Placeholder("uuid", None) => text.push_str(&uuid::Uuid::new_v4().to_string()),
+            Token::Placeholder("input", Some(name)) => {
+                let value = ctx
+                    .inputs
+                    .get(name)
+                    .ok_or_else(|| format!("缺少输入: {}", name))?;
+                text.push_str(value);
+            }
+            // 只使用第一个光标标记
+            Token::Placeholder("cursor", None) => {
+                cursor.get_or_insert(text.chars().count());
+            }
+            Token::Placeholder(name, arg) => {
+                text.push('{');
+                text.push_str(name);
+                if let Some(arg) = arg {
+                    text.push(':');
+                    text.push_str(arg);
+                }
+                text.push('}');
+            }
+        }
+    }
+
+    let cursor_offset = cursor.map(|pos| text.chars().count() - pos);
+    Ok(Expansion {
+        text,
+        cursor_offset,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn context<'a>(inputs: &'a HashMap<String, String>) -> ExpandContext<'a> {
+        ExpandContext {
+            now: Local.with_ymd_and_hms(2024, 3, 5, 9, 7, 2).unwrap(),
+            clipboard: Some("copied"),
+            inputs,
+        }
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let inputs = HashMap::from([("Name".to_string(), "Alice".to_string())]);
+        let ctx = context(&inputs);
+
+        let result = expand(
+            "Hi {input:Name}, {date} {time} {date:%d/%m} [{clipboard}] {cursor}!",
+            &ctx,
+        )
+        .unwrap();
+        assert_eq!(
+            result.text,
+            "Hi Alice, 2024-03-05 09:07:02 05/03 [copied] !"
+        );
+        assert_eq!(result.cursor_offset, Some(1));
+
+        let uuid = expand("{uuid}", &ctx).unwrap().text;
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+
+        assert!(expand("{input:Other}", &ctx).is_err());
+        assert!(expand("{date:%Q}", &ctx).is_err());
+    }
+
+    #[test]
+    fn test_literal_braces_are_kept() {
+        let inputs = HashMap::new();
+        let ctx = context(&inputs);
+
+        let template = "fn main() { let x = {unknown}; } {{time}} {\"a\": 1} {";
+        let result = expand(template, &ctx).unwrap();
+        assert_eq!(
+            result.text,
+            "fn main() { let x = {unknown}; } {time} {\"a\": 1} {"
+        );
+        assert_eq!(result.cursor_offset, None);
+
+        assert_eq!(
+            input_names("{input:A} {input:B} {input:A} {{input:C}}"),
+            vec!["A", "B"]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! 片段粘贴
+//! 从数据库读取片段并展开占位符，各平台的 `paste_snippet` 使用展开后的文本写入剪贴板
+
+use crate::placeholder::{expand, input_names, ExpandContext, Expansion};
+use std::collections::HashMap;
+use tauri::{command, AppHandle, Manager, Runtime};
+use tauri_plugin_eco_database::{DatabaseState, Snippet};
+
+/// 读取片段
+async fn load_snippet<R: Runtime>(app_handle: &AppHandle<R>, id: &str) -> Result<Snippet, String> {
+    let db_state = app_handle
+        .try_state::<DatabaseState>()
+        .ok_or_else(|| "数据库插件未初始化".to_string())?;
+
+    let db = db_state.read().await;
+    db.get_snippet(id)
+}
+
+/// 读取片段并展开占位符
+/// `{clipboard}` 使用展开时（写入片段之前）剪贴板中的文本
+pub(crate) async fn expand_snippet_by_id<R: Runtime>(
+    app_handle: &AppHandle<R>,
+    id: &str,
+    inputs: &HashMap<String, String>,
+) -> Result<Expansion, String> {
+    let snippet = load_snippet(app_handle, id).await?;
+
+    let clipboard = tauri_plugin_eco_clipboard::get_clipboard_manager(app_handle)
+        .read_text()
+        .ok();
+    let ctx = ExpandContext {
+        now: chrono::Local::now(),
+        clipboard: clipboard.as_deref(),
+        inputs,
+    };
+
+    expand(&snippet.content, &ctx)
+}
+
+/// 获取片段中需要用户填写的输入名称
+#[command]
+pub async fn get_snippet_inputs<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+) -> Result<Vec<String>, String> {
+    let snippet = load_snippet(&app_handle, &id).await?;
+    Ok(input_names(&snippet.content))
+}
+
+/// 展开片段（不写入剪贴板）
+#[command]
+pub async fn expand_snippet<R: Runtime>(
+    app_handle: AppHandle<R>,
+    id: String,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<Expansion, String> {
+    expand_snippet_by_id(&app_handle, &id, &inputs.unwrap_or_default()).await
+}
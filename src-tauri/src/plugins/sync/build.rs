@@ -11,6 +11,9 @@ const COMMANDS: &[&str] = &[
     "reload_config_from_file",
     "upload_local_config",
     "apply_remote_config",
+    // 同步冲突命令
+    "get_sync_conflicts",
+    "resolve_sync_conflict",
     // 书签本地管理命令
     "load_bookmark_data",
     "save_bookmark_data",
@@ -22,6 +25,9 @@ const COMMANDS: &[&str] = &[
     // 服务器配置本地管理命令
     "save_server_config",
     "load_server_config",
+    // 备份与恢复命令
+    "create_backup",
+    "restore_backup",
 ];
 
 fn main() {
@@ -0,0 +1,888 @@
+//! 备份与恢复
+//! 将历史记录、图片、书签和应用设置导出为单个带版本号的 ZIP 归档，
+//! `manifest.json` 记录每个文件的大小和 SHA-256 校验和，恢复前先完整校验归档
+//!
+//! 设置密码时，除清单外的所有文件使用与云同步相同的方式加密（Argon2id + AES-256-GCM）
+//!
+//! 归档布局：
+//! - `manifest.json`：格式版本、创建时间、是否加密、文件列表
+//! - `history.json`：未删除的历史记录
+//! - `tags.json`：记录ID到标签名称的映射（存在标签时）
+//! - `bookmarks.json`：书签分组（存在时）
+//! - `settings.json`：应用设置 `.store.json`（存在时）
+//! - `images/<文件名>`：历史记录引用的图片
+
+use crate::commands::{load_bookmark_data_local, BookmarkGroupData};
+use crate::crypto::SyncCipher;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use tauri_plugin_eco_common::id::generate_id;
+use tauri_plugin_eco_common::paths::{get_config_path, get_data_path};
+use tauri_plugin_eco_database::{
+    emit_changes, store_image_file, DatabaseChanges, DatabaseManager, DatabaseState, DeleteManager,
+    HistoryItem, InsertItem, QueryOptions,
+};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 归档格式版本
+const BACKUP_FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+const HISTORY_FILE: &str = "history.json";
+const TAGS_FILE: &str = "tags.json";
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+const SETTINGS_FILE: &str = "settings.json";
+const IMAGES_PREFIX: &str = "images/";
+
+/// 归档中的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    /// 归档内路径
+    pub path: String,
+    /// 原始大小（加密前）
+    pub size: u64,
+    /// 原始内容的 SHA-256
+    pub sha256: String,
+}
+
+/// 备份清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// 归档格式版本
+    pub version: u32,
+    /// 创建备份的应用版本
+    pub app_version: String,
+    /// 创建时间
+    pub created_at: i64,
+    /// 文件内容是否加密
+    pub encrypted: bool,
+    /// 历史记录数量
+    pub item_count: usize,
+    /// 文件列表
+    pub entries: Vec<BackupEntry>,
+}
+
+/// 恢复模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreMode {
+    /// 合并：按插入去重规则合并历史记录，书签按分组合并，保留本地设置
+    Merge,
+    /// 替换：本地历史记录、书签和设置替换为备份内容
+    ///
+    /// 只影响本机：删除的本地记录不写入墓碑，不会从云端和其他设备删除，
+    /// 开启云同步时云端仍存在的记录会在下次同步时重新下载
+    Replace,
+}
+
+/// 备份结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupResult {
+    pub path: String,
+    pub item_count: usize,
+    pub image_count: usize,
+    /// 归档文件大小（字节）
+    pub size: u64,
+}
+
+/// 恢复结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    /// 新增的记录
+    pub inserted: usize,
+    /// 更新的记录（ID 或内容已存在）
+    pub updated: usize,
+    /// 与本地完全相同、未改动的记录
+    pub unchanged: usize,
+    /// 替换模式下删除的本地记录
+    pub removed: usize,
+    pub image_count: usize,
+    pub bookmarks_restored: bool,
+    /// 设置已替换（需要重启应用生效）
+    pub settings_restored: bool,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 校验归档内路径，拒绝可能写到图片目录之外的文件名
+fn is_valid_entry_path(path: &str) -> bool {
+    if [HISTORY_FILE, TAGS_FILE, BOOKMARKS_FILE, SETTINGS_FILE].contains(&path) {
+        return true;
+    }
+
+    match path.strip_prefix(IMAGES_PREFIX) {
+        Some(name) => {
+            !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':'])
+        }
+        None => false,
+    }
+}
+
+/// 图片目录（与保存剪贴板图片时使用的目录一致）
+fn images_dir<R: Runtime>(app_handle: &AppHandle<R>) -> PathBuf {
+    app_handle
+        .path()
+        .data_dir()
+        .unwrap_or_else(|_| PathBuf::from("./data"))
+        .join("images")
+}
+
+/// 书签文件路径
+fn bookmark_path() -> Result<PathBuf, String> {
+    get_data_path()
+        .map(|dir| dir.join("bookmark-data.json"))
+        .ok_or_else(|| "无法获取数据目录".to_string())
+}
+
+/// 图片记录引用的文件名
+fn image_file_name(item: &HistoryItem) -> Option<&str> {
+    if item.item_type.as_deref() != Some("image") {
+        return None;
+    }
+    item.value
+        .as_deref()
+        .and_then(|value| Path::new(value).file_name())
+        .and_then(|name| name.to_str())
+}
+
+/// 归档写入器
+struct BackupWriter {
+    zip: ZipWriter<File>,
+    cipher: Option<SyncCipher>,
+    entries: Vec<BackupEntry>,
+}
+
+impl BackupWriter {
+    fn create(path: &Path, passphrase: Option<&str>) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("创建备份文件失败: {}", e))?;
+        let cipher = passphrase.map(SyncCipher::new).transpose()?;
+
+        Ok(Self {
+            zip: ZipWriter::new(file),
+            cipher,
+            entries: Vec::new(),
+        })
+    }
+
+    /// 写入一个文件
+    ///
+    /// # Arguments
+    /// * `compress` - 是否压缩（图片等已压缩的内容传 false）
+    fn add(&mut self, path: &str, data: &[u8], compress: bool) -> Result<(), String> {
+        let stored: Cow<[u8]> = match &self.cipher {
+            Some(cipher) => Cow::Owned(cipher.encrypt(data)?),
+            None => Cow::Borrowed(data),
+        };
+
+        // 密文无法压缩
+        let method = if compress && self.cipher.is_none() {
+            CompressionMethod::Deflated
+        } else {
+            CompressionMethod::Stored
+        };
+
+        self.zip
+            .start_file(
+                path,
+                SimpleFileOptions::default().compression_method(method),
+            )
+            .and_then(|_| self.zip.write_all(&stored).map_err(Into::into))
+            .map_err(|e| format!("写入备份文件 {} 失败: {}", path, e))?;
+
+        self.entries.push(BackupEntry {
+            path: path.to_string(),
+            size: data.len() as u64,
+            sha256: sha256_hex(data),
+        });
+        Ok(())
+    }
+
+    /// 写入清单并结束归档
+    fn finish(mut self, app_version: &str, item_count: usize) -> Result<BackupManifest, String> {
+        let manifest = BackupManifest {
+            version: BACKUP_FORMAT_VERSION,
+            app_version: app_version.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+            encrypted: self.cipher.is_some(),
+            item_count,
+            entries: self.entries,
+        };
+
+        let json = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| format!("序列化备份清单失败: {}", e))?;
+        self.zip
+            .start_file(
+                MANIFEST_FILE,
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+            )
+            .and_then(|_| self.zip.write_all(&json).map_err(Into::into))
+            .and_then(|_| self.zip.finish().map(|_| ()))
+            .map_err(|e| format!("写入备份清单失败: {}", e))?;
+
+        Ok(manifest)
+    }
+}
+
+/// 归档读取器，读取的每个文件都会解密并校验
+struct BackupReader {
+    zip: ZipArchive<File>,
+    cipher: Option<SyncCipher>,
+    manifest: BackupManifest,
+}
+
+impl BackupReader {
+    fn open(path: &Path, passphrase: Option<&str>) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("打开备份文件失败: {}", e))?;
+        let mut zip = ZipArchive::new(file).map_err(|e| format!("不是有效的备份文件: {}", e))?;
+
+        let manifest: BackupManifest = {
+            let mut entry = zip
+                .by_name(MANIFEST_FILE)
+                .map_err(|_| "不是有效的备份文件: 缺少备份清单".to_string())?;
+            let mut json = Vec::new();
+            entry
+                .read_to_end(&mut json)
+                .map_err(|e| format!("读取备份清单失败: {}", e))?;
+            serde_json::from_slice(&json).map_err(|e| format!("解析备份清单失败: {}", e))?
+        };
+
+        if manifest.version > BACKUP_FORMAT_VERSION {
+            return Err(format!(
+                "备份文件版本({})高于当前支持的版本({})，请升级应用",
+                manifest.version, BACKUP_FORMAT_VERSION
+            ));
+        }
+
+        if let Some(entry) = manifest
+            .entries
+            .iter()
+            .find(|entry| !is_valid_entry_path(&entry.path))
+        {
+            return Err(format!("备份文件包含无效的路径: {}", entry.path));
+        }
+
+        let cipher = match (manifest.encrypted, passphrase) {
+            (true, Some(passphrase)) => Some(SyncCipher::new(passphrase)?),
+            (true, None) => return Err("备份文件已加密，请输入密码".to_string()),
+            (false, _) => None,
+        };
+
+        Ok(Self {
+            zip,
+            cipher,
+            manifest,
+        })
+    }
+
+    fn has(&self, path: &str) -> bool {
+        self.manifest.entries.iter().any(|entry| entry.path == path)
+    }
+
+    /// 图片文件名列表
+    fn image_names(&self) -> Vec<String> {
+        self.manifest
+            .entries
+            .iter()
+            .filter_map(|entry| entry.path.strip_prefix(IMAGES_PREFIX))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    /// 读取文件内容（解密并校验）
+    fn read(&mut self, path: &str) -> Result<Vec<u8>, String> {
+        let expected = self
+            .manifest
+            .entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .cloned()
+            .ok_or_else(|| format!("备份文件中缺少 {}", path))?;
+
+        let mut stored = Vec::new();
+        self.zip
+            .by_name(path)
+            .map_err(|e| format!("备份文件中缺少 {}: {}", path, e))?
+            .read_to_end(&mut stored)
+            .map_err(|e| format!("读取 {} 失败: {}", path, e))?;
+
+        let data = match &self.cipher {
+            Some(cipher) => cipher
+                .decrypt(&stored)
+                .map_err(|_| "解密失败: 备份密码错误或备份文件已损坏".to_string())?,
+            None => stored,
+        };
+
+        if data.len() as u64 != expected.size || sha256_hex(&data) != expected.sha256 {
+            return Err(format!("备份文件校验失败: {} 已损坏", path));
+        }
+
+        Ok(data)
+    }
+
+    /// 校验清单中的所有文件
+    fn verify(&mut self) -> Result<(), String> {
+        let paths: Vec<String> = self
+            .manifest
+            .entries
+            .iter()
+            .map(|entry| entry.path.clone())
+            .collect();
+
+        for path in paths {
+            self.read(&path)?;
+        }
+        Ok(())
+    }
+}
+
+/// 写入备份归档
+fn write_backup(
+    path: &Path,
+    passphrase: Option<&str>,
+    app_version: &str,
+    history: &[HistoryItem],
+    tags: &HashMap<String, Vec<String>>,
+    bookmarks: Option<&Path>,
+    settings: Option<&Path>,
+) -> Result<(BackupManifest, usize), String> {
+    let mut writer = BackupWriter::create(path, passphrase)?;
+
+    let json = serde_json::to_vec(history).map_err(|e| format!("序列化历史记录失败: {}", e))?;
+    writer.add(HISTORY_FILE, &json, true)?;
+
+    if !tags.is_empty() {
+        let json = serde_json::to_vec(tags).map_err(|e| format!("序列化标签失败: {}", e))?;
+        writer.add(TAGS_FILE, &json, true)?;
+    }
+
+    let mut images = HashSet::new();
+    for item in history {
+        let (Some(name), Some(value)) = (image_file_name(item), item.value.as_deref()) else {
+            continue;
+        };
+        if images.contains(name) {
+            continue;
+        }
+
+        match std::fs::read(value) {
+            Ok(data) => {
+                writer.add(&format!("{}{}", IMAGES_PREFIX, name), &data, false)?;
+                images.insert(name);
+            }
+            Err(e) => log::warn!("[Backup] 跳过无法读取的图片: {} ({})", value, e),
+        }
+    }
+
+    for (file, source) in [(BOOKMARKS_FILE, bookmarks), (SETTINGS_FILE, settings)] {
+        if let Some(source) = source.filter(|source| source.is_file()) {
+            let data = std::fs::read(source).map_err(|e| format!("读取 {} 失败: {}", file, e))?;
+            writer.add(file, &data, true)?;
+        }
+    }
+
+    let manifest = writer.finish(app_version, history.len())?;
+    Ok((manifest, images.len()))
+}
+
+/// 读取要备份的历史记录及其标签名称
+/// 不使用预览查询，转存到外部存储的大内容会还原为完整内容
+fn backup_history(
+    db: &DatabaseManager,
+) -> Result<(Vec<HistoryItem>, HashMap<String, Vec<String>>), String> {
+    let history = db.query_history(QueryOptions {
+        exclude_deleted: true,
+        ..Default::default()
+    })?;
+
+    let ids: HashSet<&str> = history.iter().map(|item| item.id.as_str()).collect();
+    let mut tags = db.all_item_tag_names()?;
+    tags.retain(|id, _| ids.contains(id.as_str()));
+
+    Ok((history, tags))
+}
+
+/// 保存备份中的图片
+/// 与保存剪贴板图片相同：先写入临时文件，再按内容哈希保存并登记，相同内容的图片只保留一份
+///
+/// # Returns
+/// 图片在本机的路径
+fn store_restored_image(
+    db: &DatabaseManager,
+    images_dir: &Path,
+    name: &str,
+    data: &[u8],
+) -> Result<PathBuf, String> {
+    let extension = Path::new(name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    let temp_path = images_dir.join(format!("{}.tmp.{}", generate_id(), extension));
+    std::fs::write(&temp_path, data).map_err(|e| format!("写入图片失败: {}", e))?;
+
+    let stored = match store_image_file(images_dir, &temp_path) {
+        Ok(stored) => stored,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    };
+    db.register_stored_image(&stored)?;
+    Ok(stored.path)
+}
+
+/// 备份中的记录转换为插入项
+///
+/// # Arguments
+/// * `images` - 备份中的图片文件名到本机路径的映射
+fn to_insert_item(item: HistoryItem, images: &HashMap<String, PathBuf>) -> InsertItem {
+    let value = match image_file_name(&item).and_then(|name| images.get(name)) {
+        Some(path) => Some(path.to_string_lossy().to_string()),
+        None => item.value,
+    };
+
+    InsertItem {
+        id: item.id,
+        item_type: item.item_type,
+        group: item.group,
+        value,
+        search: item.search,
+        count: item.count,
+        width: item.width,
+        height: item.height,
+        favorite: item.favorite,
+        time: item.time,
+        note: item.note,
+        subtype: item.subtype,
+        deleted: Some(0),
+        // 恢复的记录需要重新同步
+        sync_status: None,
+        source_app_name: item.source_app_name,
+        source_app_icon: item.source_app_icon,
+        position: item.position,
+        expires_at: item.expires_at,
+        expire_on_paste: item.expire_on_paste,
+    }
+}
+
+/// 合并书签：保留本地分组，追加备份中本地没有的分组
+fn merge_bookmarks(local: BookmarkGroupData, backup: BookmarkGroupData) -> BookmarkGroupData {
+    let mut merged = local;
+    for group in backup.groups {
+        if !merged.groups.iter().any(|g| g.id == group.id) {
+            merged.groups.push(group);
+        }
+    }
+    merged.last_modified = chrono::Utc::now().timestamp_millis();
+    merged
+}
+
+/// 创建备份
+///
+/// # Arguments
+/// * `path` - 备份文件保存路径
+/// * `password` - 加密密码（为空时不加密）
+#[tauri::command]
+pub async fn create_backup<R: Runtime>(
+    path: String,
+    password: Option<String>,
+    db_state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<BackupResult, String> {
+    let (history, tags) = {
+        let db = db_state.read().await;
+        backup_history(&db)?
+    };
+
+    let target = PathBuf::from(&path);
+    // 先写入临时文件，完成后再替换，避免失败时留下不完整的备份
+    let temp = target.with_extension("tmp");
+    let bookmarks = bookmark_path().ok();
+    let settings = get_config_path(&app_handle);
+
+    let written = write_backup(
+        &temp,
+        password.as_deref().filter(|p| !p.is_empty()),
+        &app_handle.package_info().version.to_string(),
+        &history,
+        &tags,
+        bookmarks.as_deref(),
+        settings.as_deref(),
+    )
+    .and_then(|written| {
+        std::fs::rename(&temp, &target)
+            .map(|_| written)
+            .map_err(|e| format!("保存备份文件失败: {}", e))
+    });
+
+    let (manifest, image_count) = match written {
+        Ok(written) => written,
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            log::error!("[Backup] 创建备份失败: {}", e);
+            return Err(e);
+        }
+    };
+
+    let size = std::fs::metadata(&target).map(|m| m.len()).unwrap_or(0);
+    log::info!(
+        "[Backup] 已创建备份: {} ({} 条记录, {} 张图片, {} 字节)",
+        path,
+        manifest.item_count,
+        image_count,
+        size
+    );
+
+    Ok(BackupResult {
+        path,
+        item_count: manifest.item_count,
+        image_count,
+        size,
+    })
+}
+
+/// 从备份恢复
+///
+/// # Arguments
+/// * `path` - 备份文件路径
+/// * `mode` - 恢复模式（merge / replace）
+/// * `password` - 备份密码（未加密时忽略）
+#[tauri::command]
+pub async fn restore_backup<R: Runtime>(
+    path: String,
+    mode: RestoreMode,
+    password: Option<String>,
+    db_state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<RestoreResult, String> {
+    let mut reader = BackupReader::open(Path::new(&path), password.as_deref())?;
+
+    // 先完整校验，避免恢复到一半才发现归档损坏
+    reader.verify()?;
+
+    let mut history: Vec<HistoryItem> = serde_json::from_slice(&reader.read(HISTORY_FILE)?)
+        .map_err(|e| format!("解析历史记录失败: {}", e))?;
+    let tags: HashMap<String, Vec<String>> = if reader.has(TAGS_FILE) {
+        serde_json::from_slice(&reader.read(TAGS_FILE)?)
+            .map_err(|e| format!("解析标签失败: {}", e))?
+    } else {
+        HashMap::new()
+    };
+    let mut result = RestoreResult::default();
+
+    // 1. 历史记录和图片
+    {
+        let db = db_state.read().await;
+
+        if mode == RestoreMode::Replace {
+            let local_ids: Vec<String> = db
+                .query_history(QueryOptions::default())?
+                .into_iter()
+                .map(|item| item.id)
+                .collect();
+            // 不写入墓碑，避免恢复备份删除其他设备上的记录
+            result.removed = DeleteManager::batch_hard_delete_local(&db, &local_ids)?;
+        }
+
+        // 图片在删除本地记录之后保存，避免与被删除记录共用的图片文件被释放
+        let images_dir = images_dir(&app_handle);
+        std::fs::create_dir_all(&images_dir).map_err(|e| format!("创建图片目录失败: {}", e))?;
+        let mut images = HashMap::new();
+        for name in reader.image_names() {
+            let data = reader.read(&format!("{}{}", IMAGES_PREFIX, name))?;
+            let path = store_restored_image(&db, &images_dir, &name, &data)?;
+            images.insert(name, path);
+            result.image_count += 1;
+        }
+
+        // 重新写入的记录不再视为已删除
+        let ids: Vec<String> = history.iter().map(|item| item.id.clone()).collect();
+        db.remove_tombstones(&ids)?;
+
+        // 按原有顺序从底部开始插入，保持排序
+        history.sort_by_key(|item| item.position.unwrap_or(0));
+        for item in history {
+            let time = item.time;
+            let item_tags = tags.get(&item.id);
+            let insert = to_insert_item(item, &images);
+            let inserted = db.insert_with_deduplication(&insert, &app_handle)?;
+
+            // 内容重复时写入的是已有记录
+            let target_id = inserted
+                .insert_id
+                .clone()
+                .unwrap_or_else(|| insert.id.clone());
+
+            match inserted.insert_id {
+                None => result.unchanged += 1,
+                Some(_) if inserted.is_update => result.updated += 1,
+                Some(id) => {
+                    // 保留原始的复制时间
+                    db.update_field(&id, "time", &time.to_string())?;
+                    result.inserted += 1;
+                }
+            }
+
+            // 标签：替换模式使用备份中的标签，合并模式保留本地已有的标签
+            if let Some(names) = item_tags {
+                let mut names = names.clone();
+                if mode == RestoreMode::Merge {
+                    names.extend(
+                        db.get_item_tags(&target_id)?
+                            .into_iter()
+                            .map(|tag| tag.name),
+                    );
+                }
+                db.set_item_tag_names(&target_id, &names)?;
+            }
+        }
+    }
+
+    // 2. 书签
+    if reader.has(BOOKMARKS_FILE) {
+        let backup: BookmarkGroupData = serde_json::from_slice(&reader.read(BOOKMARKS_FILE)?)
+            .map_err(|e| format!("解析书签数据失败: {}", e))?;
+        let data = match mode {
+            RestoreMode::Replace => backup,
+            RestoreMode::Merge => merge_bookmarks(load_bookmark_data_local().await?, backup),
+        };
+
+        let bookmark_path = bookmark_path()?;
+        if let Some(parent) = bookmark_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建数据目录失败: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&data).map_err(|e| format!("序列化失败: {}", e))?;
+        std::fs::write(&bookmark_path, json).map_err(|e| format!("写入书签失败: {}", e))?;
+
+        let _ = app_handle.emit("bookmark-data-changed", ());
+        result.bookmarks_restored = true;
+    }
+
+    // 3. 设置（仅替换模式）
+    if mode == RestoreMode::Replace && reader.has(SETTINGS_FILE) {
+        let data = reader.read(SETTINGS_FILE)?;
+        serde_json::from_slice::<serde_json::Value>(&data)
+            .map_err(|e| format!("解析设置失败: {}", e))?;
+
+        let config_path = get_config_path(&app_handle).ok_or("无法获取配置路径".to_string())?;
+        std::fs::write(&config_path, data).map_err(|e| format!("写入设置失败: {}", e))?;
+        result.settings_restored = true;
+    }
+
+    emit_changes(&app_handle, DatabaseChanges::reload());
+
+    log::info!(
+        "[Backup] 已从备份恢复 ({:?}): 新增 {}，更新 {}，未变 {}，删除 {}，图片 {}",
+        mode,
+        result.inserted,
+        result.updated,
+        result.unchanged,
+        result.removed,
+        result.image_count
+    );
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri_plugin_eco_database::SyncDataItem;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("ecopaste-backup-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn item(id: &str, item_type: &str, value: &str) -> HistoryItem {
+        HistoryItem {
+            id: id.to_string(),
+            item_type: Some(item_type.to_string()),
+            value: Some(value.to_string()),
+            search: Some(value.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_with_encryption() {
+        let dir = temp_dir("roundtrip");
+        let image = dir.join("abc.png");
+        std::fs::write(&image, b"png-bytes").unwrap();
+        let settings = dir.join(".store.json");
+        std::fs::write(&settings, br#"{"globalStore":{}}"#).unwrap();
+
+        let history = vec![
+            HistoryItem {
+                expires_at: Some(5_000),
+                expire_on_paste: true,
+                ..item("1", "text", "hello")
+            },
+            item("2", "image", image.to_str().unwrap()),
+        ];
+        let archive = dir.join("backup.zip");
+        let (manifest, image_count) = write_backup(
+            &archive,
+            Some("secret"),
+            "1.0.0",
+            &history,
+            &HashMap::new(),
+            Some(&dir.join("missing.json")),
+            Some(&settings),
+        )
+        .unwrap();
+        assert!(manifest.encrypted);
+        assert_eq!(image_count, 1);
+
+        assert!(BackupReader::open(&archive, None).is_err());
+        let mut wrong = BackupReader::open(&archive, Some("wrong")).unwrap();
+        assert!(wrong.verify().is_err());
+
+        let mut reader = BackupReader::open(&archive, Some("secret")).unwrap();
+        reader.verify().unwrap();
+        assert!(!reader.has(BOOKMARKS_FILE));
+        assert!(!reader.has(TAGS_FILE));
+        assert_eq!(reader.image_names(), vec!["abc.png"]);
+        assert_eq!(reader.read("images/abc.png").unwrap(), b"png-bytes");
+
+        let restored: Vec<HistoryItem> =
+            serde_json::from_slice(&reader.read(HISTORY_FILE).unwrap()).unwrap();
+        let target = PathBuf::from("/restore/images/0123.png");
+        let images = HashMap::from([("abc.png".to_string(), target.clone())]);
+        let insert = to_insert_item(restored[1].clone(), &images);
+        assert_eq!(insert.value.as_deref(), target.to_str());
+
+        // 过期设置随记录恢复
+        let insert = to_insert_item(restored[0].clone(), &images);
+        assert_eq!(insert.expires_at, Some(5_000));
+        assert!(insert.expire_on_paste);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_large_item_roundtrip() {
+        let dir = temp_dir("large");
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+
+        // 超过阈值的内容在表中只有摘要
+        let large = "x".repeat(tauri_plugin_eco_database::BLOB_THRESHOLD + 1);
+        db.upsert_from_cloud(&SyncDataItem {
+            tags: vec!["cert".to_string()],
+            ..SyncDataItem::from(item("1", "text", &large))
+        })
+        .unwrap();
+
+        let (history, tags) = backup_history(&db).unwrap();
+        assert_eq!(history[0].value.as_deref(), Some(large.as_str()));
+        assert_eq!(tags["1"], vec!["cert"]);
+
+        let archive = dir.join("backup.zip");
+        write_backup(&archive, None, "1.0.0", &history, &tags, None, None).unwrap();
+
+        let mut reader = BackupReader::open(&archive, None).unwrap();
+        reader.verify().unwrap();
+        let restored: Vec<HistoryItem> =
+            serde_json::from_slice(&reader.read(HISTORY_FILE).unwrap()).unwrap();
+        let insert = to_insert_item(restored[0].clone(), &HashMap::new());
+        assert_eq!(insert.value.as_deref(), Some(large.as_str()));
+        assert!(!restored[0].truncated);
+        let restored_tags: HashMap<String, Vec<String>> =
+            serde_json::from_slice(&reader.read(TAGS_FILE).unwrap()).unwrap();
+        assert_eq!(restored_tags, tags);
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_restored_images_are_content_addressed() {
+        let dir = temp_dir("images");
+        let mut db = DatabaseManager::new();
+        db.init(dir.join("test.db")).unwrap();
+        let images_dir = dir.join("images");
+        std::fs::create_dir_all(&images_dir).unwrap();
+
+        // 文件名不同、内容相同的图片只保留一份
+        let first = store_restored_image(&db, &images_dir, "a.png", b"png-bytes").unwrap();
+        let second = store_restored_image(&db, &images_dir, "b.png", b"png-bytes").unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            first.file_name().unwrap().to_str().unwrap(),
+            format!("{}.png", sha256_hex(b"png-bytes"))
+        );
+        assert_eq!(std::fs::read_dir(&images_dir).unwrap().count(), 1);
+
+        // 已登记到图片表：没有记录引用时可以被释放
+        let path = first.to_string_lossy().to_string();
+        assert_eq!(db.release_images(&[path]).unwrap(), 1);
+        assert!(!first.exists());
+
+        drop(db);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejects_corrupted_and_unsafe_entries() {
+        let dir = temp_dir("corrupt");
+        let archive = dir.join("backup.zip");
+        write_backup(
+            &archive,
+            None,
+            "1.0.0",
+            &[item("1", "text", "hello")],
+            &HashMap::new(),
+            None,
+            None,
+        )
+        .unwrap();
+
+        // 篡改内容但保留清单
+        let tampered = dir.join("tampered.zip");
+        {
+            let mut source = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+            let mut manifest = String::new();
+            source
+                .by_name(MANIFEST_FILE)
+                .unwrap()
+                .read_to_string(&mut manifest)
+                .unwrap();
+
+            let mut zip = ZipWriter::new(File::create(&tampered).unwrap());
+            zip.start_file(HISTORY_FILE, SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"[]").unwrap();
+            zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(manifest.as_bytes()).unwrap();
+            zip.finish().unwrap();
+        }
+        let mut reader = BackupReader::open(&tampered, None).unwrap();
+        assert!(reader.verify().unwrap_err().contains("校验失败"));
+
+        assert!(is_valid_entry_path("images/a.png"));
+        assert!(!is_valid_entry_path("images/../../evil"));
+        assert!(!is_valid_entry_path("images/.."));
+        assert!(!is_valid_entry_path("other.json"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
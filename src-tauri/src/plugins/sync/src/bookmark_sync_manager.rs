@@ -1,7 +1,7 @@
 //! 书签同步管理器
 //! 使用统一时间戳进行书签同步
 
-use crate::webdav::WebDAVClientState;
+use crate::storage::SyncStorageState;
 use serde::{Deserialize, Serialize};
 use tauri_plugin_eco_common::paths::get_data_path;
 
@@ -32,17 +32,17 @@ pub struct BookmarkSyncResult {
 /// 书签同步管理器
 /// 使用统一时间戳进行书签同步
 pub struct BookmarkSyncManager {
-    /// WebDAV客户端
-    webdav_client: WebDAVClientState,
+    /// 同步存储
+    storage: SyncStorageState,
     /// 本地书签数据
     local_data: Option<BookmarkSyncData>,
 }
 
 impl BookmarkSyncManager {
     /// 创建新的书签同步管理器
-    pub fn new(webdav_client: WebDAVClientState, _device_id: String) -> Self {
+    pub fn new(storage: SyncStorageState, _device_id: String) -> Self {
         Self {
-            webdav_client,
+            storage,
             local_data: None,
         }
     }
@@ -87,7 +87,7 @@ impl BookmarkSyncManager {
         );
 
         // 从云端下载书签数据
-        let client = self.webdav_client.lock().await;
+        let client = self.storage.lock().await;
         let download_result = match client.download_sync_data("bookmark-sync.json").await {
             Ok(result) => result,
             Err(e) => {
@@ -252,7 +252,7 @@ impl BookmarkSyncManager {
         &self,
         data: &BookmarkSyncData,
     ) -> Result<BookmarkSyncResult, String> {
-        let client = self.webdav_client.lock().await;
+        let client = self.storage.lock().await;
         let json_data =
             serde_json::to_string(data).map_err(|e| format!("序列化书签数据失败: {}", e))?;
 
@@ -283,7 +283,7 @@ impl BookmarkSyncManager {
 
     /// 下载云端书签数据
     pub async fn download_bookmarks(&self) -> Result<Option<BookmarkSyncData>, String> {
-        let client = self.webdav_client.lock().await;
+        let client = self.storage.lock().await;
         let download_result = client.download_sync_data("bookmark-sync.json").await?;
 
         if !download_result.success || download_result.data.is_none() {
@@ -0,0 +1,797 @@
+//! 云端分片索引
+//! 将同步索引按项目ID分桶存储为多个分片，并用清单文件记录各分片的修订号。
+//! 同步时只传输修订号发生变化的分片，替代原来每次整体上传下载的 sync-data.json。
+//!
+//! 云端布局：
+//! - `index/manifest.json`：清单（索引ID、全局修订号、各分片修订号与校验和、删除记录）
+//! - `index/shard-xx-<checksum>.json`：分片内容（按 ID 排序的 SyncDataItem 列表）；
+//!   启用加密时文件名使用密文的哈希，避免向存储服务暴露明文内容是否相同或变化
+//! - `index/lock.json`：服务器不支持 ETag 时使用的租约锁
+//!
+//! 启用加密后只在清单本身未加密（启用加密前上传的索引）时允许读取未加密的分片，
+//! 下一次提交会加密全部分片和清单，之后不再接受未加密的内容
+//!
+//! 多设备并发写入：分片按内容寻址，写入新分片不会覆盖其他设备正在引用的分片；
+//! 清单是唯一的提交点，使用 `If-Match` 条件上传，云端清单已变化时返回
+//! [`INDEX_CONFLICT_ERROR`]，由调用方重新下载合并后重试。
+
+use crate::sync_core::SyncDataItem;
+use crate::crypto::UNENCRYPTED_DATA_ERROR;
+use crate::storage::{SyncStorage, SyncStorageState, WriteCondition};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tauri_plugin_eco_common::paths::get_data_path;
+use tauri_plugin_eco_database::Tombstone;
+
+/// 清单文件路径
+const MANIFEST_PATH: &str = "index/manifest.json";
+/// 旧版单文件索引路径
+const LEGACY_INDEX_PATH: &str = "sync-data.json";
+/// 分片数量
+const SHARD_COUNT: u8 = 64;
+/// 清单格式版本（3：分片文件名可能使用密文哈希，见 [`ShardInfo::file`]）
+const MANIFEST_VERSION: u32 = 3;
+/// 分片文件名均由明文校验和得出的清单版本
+const PLAIN_NAMES_VERSION: u32 = 2;
+/// 租约锁文件路径
+const LOCK_PATH: &str = "index/lock.json";
+/// 租约有效期（毫秒），持有者异常退出后锁会自动失效
+const LEASE_DURATION_MS: i64 = 60_000;
+/// 写入租约后等待确认的时间，用于发现同时写入的其他设备
+const LEASE_SETTLE_MS: u64 = 500;
+
+/// 云端索引已被其他设备更新（需要重新下载合并后重试）
+pub const INDEX_CONFLICT_ERROR: &str = "云端索引已被其他设备更新";
+
+/// 分片信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardInfo {
+    /// 分片最后一次变更时的全局修订号
+    pub revision: u64,
+    /// 项目数量
+    pub count: usize,
+    /// 分片内容校验和（MD5）
+    pub checksum: String,
+    /// 分片文件名中的哈希（启用加密时为密文的 MD5，未设置时使用校验和）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+}
+
+impl ShardInfo {
+    /// 分片在云端的路径
+    fn path(&self, key: &str) -> String {
+        shard_path(key, self.file.as_deref().unwrap_or(&self.checksum))
+    }
+}
+
+/// 索引清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexManifest {
+    /// 格式版本
+    pub version: u32,
+    /// 索引ID（更换服务器或重建索引时变化，用于使本地缓存失效）
+    pub index_id: String,
+    /// 全局修订号
+    pub revision: u64,
+    /// 各分片信息
+    pub shards: BTreeMap<String, ShardInfo>,
+    /// 删除记录（墓碑），与分片在同一次清单提交中生效
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tombstones: Vec<Tombstone>,
+    /// 更新时间
+    pub updated_at: i64,
+}
+
+/// 租约锁内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexLease {
+    /// 持有者（每个进程随机生成）
+    owner: String,
+    /// 过期时间
+    expires_at: i64,
+}
+
+/// 生成随机标识
+fn random_id() -> String {
+    let seed = format!(
+        "{}-{:?}-{}",
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        std::thread::current().id(),
+        std::process::id()
+    );
+    format!("{:x}", md5::compute(seed))
+}
+
+impl IndexManifest {
+    fn new() -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            index_id: random_id(),
+            revision: 0,
+            shards: BTreeMap::new(),
+            tombstones: Vec::new(),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// 计算项目所属分片
+pub fn shard_key(item_id: &str) -> String {
+    let digest = md5::compute(item_id.as_bytes());
+    format!("{:02x}", digest[0] % SHARD_COUNT)
+}
+
+/// 分片在云端的路径（按内容寻址，内容变化时写入新文件）
+fn shard_path(key: &str, checksum: &str) -> String {
+    format!("index/shard-{}-{}.json", key, checksum)
+}
+
+/// 序列化分片（按 ID 排序，保证相同内容得到相同校验和）
+fn serialize_shard(items: &mut [SyncDataItem]) -> Result<(String, String), String> {
+    items.sort_by(|a, b| a.id.cmp(&b.id));
+    let json = serde_json::to_string(items).map_err(|e| format!("序列化索引分片失败: {}", e))?;
+    let checksum = format!("{:x}", md5::compute(json.as_bytes()));
+    Ok((json, checksum))
+}
+
+/// 云端分片索引
+pub struct CloudIndex {
+    storage: SyncStorageState,
+    /// 当前已知的清单
+    manifest: Option<IndexManifest>,
+    /// 当前清单的 ETag（服务器不支持时为空，改用租约锁）
+    manifest_etag: Option<String>,
+    /// 分片内容缓存
+    shards: HashMap<String, Vec<SyncDataItem>>,
+    /// 租约持有者标识
+    lease_owner: String,
+    /// 待随下一次提交上传的删除记录
+    pending_tombstones: Option<Vec<Tombstone>>,
+}
+
+impl CloudIndex {
+    /// 创建新的分片索引
+    pub fn new(storage: SyncStorageState) -> Self {
+        Self {
+            storage,
+            manifest: None,
+            manifest_etag: None,
+            shards: HashMap::new(),
+            lease_owner: random_id(),
+            pending_tombstones: None,
+        }
+    }
+
+    /// 本地缓存目录
+    fn cache_dir(index_id: &str) -> Option<PathBuf> {
+        get_data_path().map(|dir| dir.join("sync-index").join(index_id))
+    }
+
+    /// 从本地缓存恢复分片（应用重启后避免全量下载）
+    fn restore_from_cache(&mut self, manifest: &IndexManifest) {
+        let Some(dir) = Self::cache_dir(&manifest.index_id) else {
+            return;
+        };
+
+        for (key, info) in &manifest.shards {
+            if self.shards.contains_key(key) {
+                continue;
+            }
+
+            let Ok(content) = std::fs::read_to_string(dir.join(format!("shard-{}.json", key)))
+            else {
+                continue;
+            };
+
+            if format!("{:x}", md5::compute(content.as_bytes())) != info.checksum {
+                continue;
+            }
+
+            if let Ok(items) = serde_json::from_str::<Vec<SyncDataItem>>(&content) {
+                self.shards.insert(key.clone(), items);
+            }
+        }
+    }
+
+    /// 写入本地分片缓存
+    fn write_cache(index_id: &str, key: &str, json: &str) {
+        let Some(dir) = Self::cache_dir(index_id) else {
+            return;
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(dir.join(format!("shard-{}.json", key)), json))
+        {
+            log::warn!("[Index] 写入分片缓存失败: {} ({})", key, e);
+        }
+    }
+
+    /// 删除本地分片缓存
+    fn remove_cache(index_id: &str, key: &str) {
+        if let Some(dir) = Self::cache_dir(index_id) {
+            let _ = std::fs::remove_file(dir.join(format!("shard-{}.json", key)));
+        }
+    }
+
+    /// 下载清单及其 ETag，云端不存在时返回 None
+    async fn fetch_manifest(
+        client: &SyncStorage,
+    ) -> Result<Option<(IndexManifest, Option<String>)>, String> {
+        let result = match client.download_sync_data(MANIFEST_PATH).await {
+            // 清单未加密：索引在启用加密前上传，迁移期间允许读取未加密的分片
+            Err(e) if e == UNENCRYPTED_DATA_ERROR => {
+                let result = client.download_legacy_sync_data(MANIFEST_PATH).await?;
+                client.begin_plaintext_migration();
+                result
+            }
+            result => result?,
+        };
+
+        if !result.success {
+            if result.is_not_found() {
+                return Ok(None);
+            }
+            return Err(format!(
+                "下载索引清单失败: {}",
+                result.error_message.unwrap_or_default()
+            ));
+        }
+
+        let etag = result.etag;
+        let data = result.data.unwrap_or_default();
+        let manifest: IndexManifest =
+            serde_json::from_str(&data).map_err(|e| format!("解析索引清单失败: {}", e))?;
+
+        if manifest.version > MANIFEST_VERSION {
+            return Err(format!(
+                "云端索引版本({})高于当前支持的版本({})，请升级应用",
+                manifest.version, MANIFEST_VERSION
+            ));
+        }
+
+        Ok(Some((manifest, etag)))
+    }
+
+    /// 下载单个分片并校验
+    async fn fetch_shard(
+        &self,
+        key: &str,
+        info: &ShardInfo,
+    ) -> Result<(Vec<SyncDataItem>, String), String> {
+        let client = self.storage.lock().await;
+        let result = client.download_sync_data(&info.path(key)).await?;
+
+        if !result.success {
+            // 分片已被其他设备的新提交回收，说明读取期间清单发生了变化
+            if result.is_not_found() {
+                return Err(format!("{}（分片 {} 已更新）", INDEX_CONFLICT_ERROR, key));
+            }
+            return Err(format!(
+                "下载索引分片 {} 失败: {}",
+                key,
+                result.error_message.unwrap_or_default()
+            ));
+        }
+
+        let data = result.data.unwrap_or_default();
+        if format!("{:x}", md5::compute(data.as_bytes())) != info.checksum {
+            return Err(format!("索引分片 {} 校验失败", key));
+        }
+
+        let items: Vec<SyncDataItem> =
+            serde_json::from_str(&data).map_err(|e| format!("解析索引分片 {} 失败: {}", key, e))?;
+
+        Ok((items, data))
+    }
+
+    /// 加载云端索引
+    /// 只下载修订号变化的分片；云端只有旧版 sync-data.json 时自动迁移
+    pub async fn load(&mut self) -> Result<Vec<SyncDataItem>, String> {
+        let fetched = {
+            let client = self.storage.lock().await;
+            let fetched = Self::fetch_manifest(&client).await?;
+            fetched
+        };
+
+        let (manifest, etag) = match fetched {
+            Some(fetched) => fetched,
+            None => return self.migrate_legacy_index().await,
+        };
+        self.manifest_etag = etag;
+
+        // 索引被重建（或更换了服务器），丢弃所有缓存
+        let previous = match self.manifest.take() {
+            Some(previous) if previous.index_id == manifest.index_id => Some(previous),
+            _ => {
+                self.shards.clear();
+                None
+            }
+        };
+
+        self.restore_from_cache(&manifest);
+
+        // 移除云端已不存在的分片
+        self.shards
+            .retain(|key, _| manifest.shards.contains_key(key));
+
+        let mut downloaded = 0;
+        for (key, info) in &manifest.shards {
+            let unchanged = previous
+                .as_ref()
+                .and_then(|p| p.shards.get(key))
+                .map(|old| old == info)
+                .unwrap_or(false);
+
+            if unchanged && self.shards.contains_key(key) {
+                continue;
+            }
+
+            // 缓存内容校验和一致时同样无需下载
+            if let Some(items) = self.shards.get_mut(key) {
+                if serialize_shard(items)
+                    .map(|(_, c)| c == info.checksum)
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+            }
+
+            let (items, json) = self.fetch_shard(key, info).await?;
+            Self::write_cache(&manifest.index_id, key, &json);
+            self.shards.insert(key.clone(), items);
+            downloaded += 1;
+        }
+
+        log::info!(
+            "[Index] 索引修订号 {}，下载 {}/{} 个分片",
+            manifest.revision,
+            downloaded,
+            manifest.shards.len()
+        );
+
+        self.manifest = Some(manifest);
+        Ok(self.all_items())
+    }
+
+    /// 保存云端索引
+    /// 只上传内容发生变化的分片，最后条件提交清单；
+    /// 云端清单自上次加载后已被修改时返回 [`INDEX_CONFLICT_ERROR`]
+    pub async fn save(&mut self, items: &[SyncDataItem]) -> Result<(), String> {
+        let is_new_index = self.manifest.is_none();
+        let mut manifest = self.manifest.clone().unwrap_or_else(IndexManifest::new);
+        let base_revision = manifest.revision;
+        let new_revision = base_revision + 1;
+
+        let mut grouped: HashMap<String, Vec<SyncDataItem>> = HashMap::new();
+        for item in items {
+            grouped
+                .entry(shard_key(&item.id))
+                .or_default()
+                .push(item.clone());
+        }
+
+        let client = self.storage.lock().await;
+        let encrypted = client.is_encryption_enabled();
+        // 迁移未加密的索引时即使内容未变化也要提交加密后的清单
+        let mut changed = usize::from(client.is_plaintext_migration());
+        // 新清单提交成功后才能回收的旧分片
+        let mut stale_paths = Vec::new();
+        let mut shard_json = HashMap::new();
+
+        for (key, mut shard_items) in grouped.clone() {
+            let (json, checksum) = serialize_shard(&mut shard_items)?;
+
+            if let Some(info) = manifest.shards.get(&key) {
+                // 开启或关闭加密后需要重新上传，使文件内容和文件名与当前设置一致
+                if info.checksum == checksum && info.file.is_some() == encrypted {
+                    continue;
+                }
+                stale_paths.push(info.path(&key));
+            }
+
+            // 启用加密时按密文命名，文件名不再反映明文内容
+            let data = client.encode_sync_data(&json)?;
+            let file = encrypted.then(|| format!("{:x}", md5::compute(&data)));
+            let path = shard_path(&key, file.as_deref().unwrap_or(&checksum));
+
+            let result = client.upload_encoded(&path, &data).await?;
+            if !result.success {
+                return Err(format!(
+                    "上传索引分片 {} 失败: {}",
+                    key,
+                    result.error_message.unwrap_or_default()
+                ));
+            }
+
+            manifest.shards.insert(
+                key.clone(),
+                ShardInfo {
+                    revision: new_revision,
+                    count: shard_items.len(),
+                    checksum,
+                    file,
+                },
+            );
+            shard_json.insert(key, json);
+            changed += 1;
+        }
+
+        // 已清空的分片从清单中移除
+        let removed: Vec<String> = manifest
+            .shards
+            .keys()
+            .filter(|key| !grouped.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in &removed {
+            if let Some(info) = manifest.shards.remove(key) {
+                stale_paths.push(info.path(key));
+            }
+            changed += 1;
+        }
+
+        // 删除记录有变化时同样需要提交清单
+        if let Some(tombstones) = self.pending_tombstones.take() {
+            if manifest.tombstones != tombstones {
+                manifest.tombstones = tombstones;
+                changed += 1;
+            }
+        }
+
+        if changed > 0 || is_new_index {
+            // 旧版本客户端按校验和拼接分片路径，清单中出现密文文件名时要求其升级
+            manifest.version = if manifest.shards.values().any(|info| info.file.is_some()) {
+                MANIFEST_VERSION
+            } else {
+                PLAIN_NAMES_VERSION
+            };
+            manifest.revision = new_revision;
+            manifest.updated_at = chrono::Utc::now().timestamp_millis();
+
+            let manifest_json = serde_json::to_string(&manifest)
+                .map_err(|e| format!("序列化索引清单失败: {}", e))?;
+
+            let condition = if is_new_index {
+                Some(WriteCondition::IfNoneMatch)
+            } else {
+                self.manifest_etag.clone().map(WriteCondition::IfMatch)
+            };
+
+            self.manifest_etag = match condition {
+                Some(condition) => {
+                    Self::commit_manifest(&client, &manifest_json, &condition).await?
+                }
+                None => {
+                    self.commit_manifest_with_lease(
+                        &client,
+                        &manifest.index_id,
+                        base_revision,
+                        &manifest_json,
+                    )
+                    .await?
+                }
+            };
+
+            // 加密的清单已提交，不再接受未加密的内容
+            client.finish_plaintext_migration();
+
+            // 新清单已生效，回收不再引用的旧分片
+            for path in &stale_paths {
+                let _ = client.delete_file(path).await;
+            }
+            for key in &removed {
+                Self::remove_cache(&manifest.index_id, key);
+            }
+            for (key, json) in &shard_json {
+                Self::write_cache(&manifest.index_id, key, json);
+            }
+
+            log::info!(
+                "[Index] 索引已更新到修订号 {}，变更 {} 个分片",
+                manifest.revision,
+                changed
+            );
+        }
+        drop(client);
+
+        self.shards = grouped;
+        self.manifest = Some(manifest);
+        Ok(())
+    }
+
+    /// 条件上传清单，返回新的 ETag
+    async fn commit_manifest(
+        client: &SyncStorage,
+        manifest_json: &str,
+        condition: &WriteCondition,
+    ) -> Result<Option<String>, String> {
+        let result = client
+            .upload_sync_data_conditional(MANIFEST_PATH, manifest_json, condition)
+            .await?;
+
+        if result.is_precondition_failed() {
+            log::warn!("[Index] 清单条件上传被拒绝，云端索引已被其他设备更新");
+            return Err(INDEX_CONFLICT_ERROR.to_string());
+        }
+        if !result.success {
+            return Err(format!(
+                "上传索引清单失败: {}",
+                result.error_message.unwrap_or_default()
+            ));
+        }
+
+        Ok(result.etag)
+    }
+
+    /// 服务器不支持 ETag 时，持有租约锁后确认清单未变化再提交
+    async fn commit_manifest_with_lease(
+        &self,
+        client: &SyncStorage,
+        index_id: &str,
+        base_revision: u64,
+        manifest_json: &str,
+    ) -> Result<Option<String>, String> {
+        self.acquire_lease(client).await?;
+        let result =
+            Self::commit_if_unchanged(client, index_id, base_revision, manifest_json).await;
+        self.release_lease(client).await;
+        result
+    }
+
+    /// 确认云端清单仍是本次修改的基准版本后提交
+    async fn commit_if_unchanged(
+        client: &SyncStorage,
+        index_id: &str,
+        base_revision: u64,
+        manifest_json: &str,
+    ) -> Result<Option<String>, String> {
+        match Self::fetch_manifest(client).await? {
+            Some((remote, _))
+                if remote.index_id == index_id && remote.revision == base_revision =>
+            {
+                Self::commit_manifest(client, manifest_json, &WriteCondition::Always).await
+            }
+            _ => Err(INDEX_CONFLICT_ERROR.to_string()),
+        }
+    }
+
+    /// 获取租约锁
+    async fn acquire_lease(&self, client: &SyncStorage) -> Result<(), String> {
+        if let Some(lease) = Self::fetch_lease(client).await? {
+            if lease.owner != self.lease_owner
+                && lease.expires_at > chrono::Utc::now().timestamp_millis()
+            {
+                log::warn!("[Index] 云端索引正被其他设备写入");
+                return Err(INDEX_CONFLICT_ERROR.to_string());
+            }
+        }
+
+        let lease = IndexLease {
+            owner: self.lease_owner.clone(),
+            expires_at: chrono::Utc::now().timestamp_millis() + LEASE_DURATION_MS,
+        };
+        let json = serde_json::to_string(&lease).map_err(|e| format!("序列化租约失败: {}", e))?;
+        let result = client.upload_sync_data(LOCK_PATH, &json).await?;
+        if !result.success {
+            return Err(format!(
+                "写入索引租约失败: {}",
+                result.error_message.unwrap_or_default()
+            ));
+        }
+
+        // 多台设备同时写入租约时只有最后写入者能读回自己的标识
+        tokio::time::sleep(std::time::Duration::from_millis(LEASE_SETTLE_MS)).await;
+        match Self::fetch_lease(client).await? {
+            Some(lease) if lease.owner == self.lease_owner => Ok(()),
+            _ => Err(INDEX_CONFLICT_ERROR.to_string()),
+        }
+    }
+
+    /// 释放租约锁（仅当仍由自己持有时）
+    async fn release_lease(&self, client: &SyncStorage) {
+        if let Ok(Some(lease)) = Self::fetch_lease(client).await {
+            if lease.owner == self.lease_owner {
+                let _ = client.delete_file(LOCK_PATH).await;
+            }
+        }
+    }
+
+    /// 读取租约锁，不存在或无法解析时返回 None
+    async fn fetch_lease(client: &SyncStorage) -> Result<Option<IndexLease>, String> {
+        let result = client.download_sync_data(LOCK_PATH).await?;
+
+        if !result.success {
+            if result.is_not_found() {
+                return Ok(None);
+            }
+            return Err(format!(
+                "读取索引租约失败: {}",
+                result.error_message.unwrap_or_default()
+            ));
+        }
+
+        Ok(result
+            .data
+            .and_then(|data| serde_json::from_str::<IndexLease>(&data).ok()))
+    }
+
+    /// 一次性迁移旧版 sync-data.json 到分片索引
+    async fn migrate_legacy_index(&mut self) -> Result<Vec<SyncDataItem>, String> {
+        self.manifest = None;
+        self.manifest_etag = None;
+        self.shards.clear();
+
+        let legacy = {
+            let client = self.storage.lock().await;
+            // 旧版索引早于加密功能，允许读取未加密的内容
+            let result = client.download_legacy_sync_data(LEGACY_INDEX_PATH).await?;
+            result
+        };
+
+        if !legacy.success {
+            // 网络异常时不能当作云端为空，否则旧索引中的数据不会再被迁移
+            if legacy.is_not_found() {
+                return Ok(vec![]);
+            }
+            return Err(format!(
+                "下载云端数据失败: {}",
+                legacy.error_message.unwrap_or_default()
+            ));
+        }
+
+        let items: Vec<SyncDataItem> = match legacy.data {
+            Some(data) => {
+                serde_json::from_str(&data).map_err(|e| format!("解析云端数据失败: {}", e))?
+            }
+            None => return Ok(vec![]),
+        };
+
+        log::info!(
+            "[Index] 检测到旧版索引（{} 项），开始迁移到分片索引",
+            items.len()
+        );
+        self.save(&items).await?;
+
+        let client = self.storage.lock().await;
+        if !client.delete_file(LEGACY_INDEX_PATH).await.unwrap_or(false) {
+            log::warn!("[Index] 删除旧版索引失败，将在下次迁移检查时忽略");
+        }
+        drop(client);
+
+        log::info!("[Index] 旧版索引迁移完成");
+        Ok(items)
+    }
+
+    /// 云端的删除记录（需先调用 [`CloudIndex::load`]）
+    pub fn tombstones(&self) -> Vec<Tombstone> {
+        self.manifest
+            .as_ref()
+            .map(|manifest| manifest.tombstones.clone())
+            .unwrap_or_default()
+    }
+
+    /// 设置删除记录，随下一次 [`CloudIndex::save`] 提交
+    pub fn set_tombstones(&mut self, mut tombstones: Vec<Tombstone>) {
+        tombstones.sort_by(|a, b| a.id.cmp(&b.id));
+        self.pending_tombstones = Some(tombstones);
+    }
+
+    /// 提交尚未上传的删除记录（本次同步没有其他索引变更时使用）
+    pub async fn save_tombstones(&mut self) -> Result<(), String> {
+        let migrating = self.storage.lock().await.is_plaintext_migration();
+        let unchanged = match (&self.pending_tombstones, &self.manifest) {
+            _ if migrating && self.manifest.is_some() => false,
+            (None, _) => true,
+            (Some(pending), Some(manifest)) => *pending == manifest.tombstones,
+            (Some(pending), None) => pending.is_empty(),
+        };
+        if unchanged {
+            self.pending_tombstones = None;
+            return Ok(());
+        }
+
+        let items = self.all_items();
+        self.save(&items).await
+    }
+
+    /// 当前缓存的全部项目
+    fn all_items(&self) -> Vec<SyncDataItem> {
+        self.shards.values().flatten().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str) -> SyncDataItem {
+        SyncDataItem {
+            id: id.to_string(),
+            item_type: "text".to_string(),
+            subtype: None,
+            value: Some(id.to_string()),
+            search: Some(id.to_string()),
+            favorite: false,
+            note: None,
+            time: 0,
+            tags: vec![],
+            expires_at: None,
+            expire_on_paste: false,
+        }
+    }
+
+    #[test]
+    fn test_shard_key_is_stable() {
+        assert_eq!(shard_key("abc"), shard_key("abc"));
+        assert!(u8::from_str_radix(&shard_key("abc"), 16).unwrap() < SHARD_COUNT);
+    }
+
+    #[test]
+    fn test_shard_path_is_content_addressed() {
+        let (_, checksum_a) = serialize_shard(&mut [item("1")]).unwrap();
+        let (_, checksum_b) = serialize_shard(&mut [item("2")]).unwrap();
+        assert_ne!(shard_path("00", &checksum_a), shard_path("00", &checksum_b));
+    }
+
+    #[test]
+    fn test_shard_checksum_ignores_order() {
+        let mut a = vec![item("1"), item("2")];
+        let mut b = vec![item("2"), item("1")];
+        assert_eq!(
+            serialize_shard(&mut a).unwrap().1,
+            serialize_shard(&mut b).unwrap().1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_shard_names_hide_content() {
+        let root = std::env::temp_dir().join(format!("ecopaste-index-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let mut client = SyncStorage::new();
+        client
+            .initialize(crate::storage::StorageConfig {
+                backend: crate::storage::StorageBackendType::Local,
+                url: String::new(),
+                username: String::new(),
+                password: String::new(),
+                path: root.to_string_lossy().to_string(),
+                timeout: 0,
+                bucket: None,
+                region: None,
+                encryption_passphrase: Some("secret".to_string()),
+            })
+            .await
+            .unwrap();
+        let storage = std::sync::Arc::new(tokio::sync::Mutex::new(client));
+
+        let items = vec![item("1")];
+        CloudIndex::new(storage.clone()).save(&items).await.unwrap();
+
+        // 文件名不包含明文校验和
+        let (_, checksum) = serialize_shard(&mut items.clone()).unwrap();
+        let files = storage.lock().await.list_files("index").await.unwrap();
+        assert!(files.iter().any(|path| path.starts_with("index/shard-")));
+        assert!(files.iter().all(|path| !path.contains(&checksum)));
+
+        let loaded = CloudIndex::new(storage).load().await.unwrap();
+        assert_eq!(loaded.len(), 1);
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_manifest_without_tombstones_is_compatible() {
+        let json = r#"{"version":2,"indexId":"a","revision":3,"shards":{},"updatedAt":0}"#;
+        let manifest: IndexManifest = serde_json::from_str(json).unwrap();
+        assert!(manifest.tombstones.is_empty());
+
+        // 没有删除记录时不写入该字段，旧版本客户端读取的清单保持不变
+        let serialized = serde_json::to_string(&manifest).unwrap();
+        assert!(!serialized.contains("tombstones"));
+    }
+}
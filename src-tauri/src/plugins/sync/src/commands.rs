@@ -4,10 +4,12 @@
 use crate::bookmark_sync_manager::BookmarkGroup;
 use crate::sync_engine::CloudSyncEngine;
 use crate::types::*;
-use crate::webdav::{ConnectionTestResult, WebDAVClientState};
+use crate::storage::{ConnectionTestResult, StorageBackendType, StorageConfig, SyncStorageState};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Runtime, State};
-use tauri_plugin_eco_database::DatabaseState;
+use tauri_plugin_eco_database::{
+    emit_changes, ChangeOperation, DatabaseChanges, DatabaseState, SyncConflict,
+};
 use tauri_plugin_eco_common::paths::get_data_path;
 use tokio::sync::Mutex;
 
@@ -187,15 +189,15 @@ pub async fn update_auto_sync_interval(
     engine.update_auto_sync_interval(interval_minutes).await
 }
 
-/// 测试 WebDAV 连接
+/// 测试存储连接（WebDAV / 本地文件夹 / S3）
 #[tauri::command]
 pub async fn test_webdav_connection(
-    webdav_client: State<'_, WebDAVClientState>,
+    storage: State<'_, SyncStorageState>,
 ) -> Result<ConnectionTestResult, String> {
-    let mut client = webdav_client.lock().await;
+    let mut client = storage.lock().await;
 
     // 1. 从配置文件加载服务器配置
-    let server_config = match load_server_config().await {
+    let server_config = match get_cached_server_config() {
         Ok(config) => config,
         Err(e) => {
             log::warn!("[Sync] 加载配置文件失败: {}", e);
@@ -203,24 +205,32 @@ pub async fn test_webdav_connection(
         }
     };
 
-    // 转换为 WebDAVConfig
-    let config = crate::webdav::WebDAVConfig {
+    // 转换为存储配置
+    let config = StorageConfig {
+        backend: server_config
+            .backend
+            .as_deref()
+            .and_then(StorageBackendType::parse)
+            .unwrap_or_default(),
         url: server_config.url,
         username: server_config.username,
         password: server_config.password,
         path: server_config.path,
         timeout: server_config.timeout,
+        bucket: server_config.bucket,
+        region: server_config.region,
+        // 服务器配置未设置口令时沿用当前客户端的加密设置
+        encryption_passphrase: server_config.encryption_passphrase.or_else(|| {
+            client
+                .get_config()
+                .and_then(|c| c.encryption_passphrase.clone())
+        }),
     };
 
     // 2. 检查是否需要重新初始化
     let needs_reinit = if client.is_initialized() {
         match client.get_config() {
-            Some(current_config) => {
-                current_config.url != config.url
-                    || current_config.username != config.username
-                    || current_config.password != config.password
-                    || current_config.path != config.path
-            }
+            Some(current_config) => current_config != &config,
             None => true,
         }
     } else {
@@ -306,6 +316,65 @@ pub async fn reload_config_from_file<R: Runtime>(
     }
 }
 
+// ================================
+// 同步冲突命令
+// ================================
+
+/// 获取同步冲突列表
+#[tauri::command]
+pub async fn get_sync_conflicts(
+    include_resolved: Option<bool>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<SyncConflict>, String> {
+    let db = db_state.read().await;
+    db.list_sync_conflicts(include_resolved.unwrap_or(false))
+}
+
+/// 解决同步冲突
+///
+/// # Arguments
+/// * `resolution` - `local` 恢复本地的值并在下次同步时上传；`remote` 保留当前（云端）的值
+#[tauri::command]
+pub async fn resolve_sync_conflict<R: Runtime>(
+    conflict_id: i64,
+    resolution: String,
+    db_state: State<'_, DatabaseState>,
+    app_handle: AppHandle<R>,
+) -> Result<SyncConflict, String> {
+    let db = db_state.read().await;
+
+    let conflict = db
+        .get_sync_conflict(conflict_id)?
+        .ok_or_else(|| format!("冲突记录不存在: {}", conflict_id))?;
+
+    let value = match resolution.as_str() {
+        "local" => Some(conflict.local_value),
+        "remote" => None,
+        _ => return Err(format!("不支持的冲突解决方式: {}", resolution)),
+    };
+
+    let resolved = db.resolve_sync_conflict(conflict_id, value)?;
+    drop(db);
+
+    log::info!(
+        "[Sync] 冲突已解决: item={}, field={}, resolution={}",
+        resolved.item_id,
+        resolved.field,
+        resolution
+    );
+
+    emit_changes(
+        &app_handle,
+        DatabaseChanges::single(
+            ChangeOperation::Updated,
+            [resolved.item_id.as_str()],
+            &[resolved.field.as_str()],
+        ),
+    );
+
+    Ok(resolved)
+}
+
 // ================================
 // 书签本地管理命令
 // ================================
@@ -517,10 +586,10 @@ pub enum SaveServerConfigResult {
     Error(String),
 }
 
-/// 从单独文件加载服务器配置（使用缓存）
+/// 从单独文件加载服务器配置（使用缓存，凭据以占位符返回）
 #[tauri::command]
 pub async fn load_server_config() -> Result<ServerConfigData, String> {
-    get_cached_server_config()
+    get_cached_server_config().map(|config| config.redacted())
 }
 
 /// 保存服务器配置到单独文件
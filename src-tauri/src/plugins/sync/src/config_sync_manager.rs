@@ -1,7 +1,7 @@
 //! 配置同步管理器
 //! 负责应用配置的云端同步
 
-use crate::webdav::WebDAVClientState;
+use crate::storage::SyncStorageState;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
@@ -238,6 +238,9 @@ pub struct SyncModeSettings {
     /// 包含文件
     #[serde(default)]
     pub include_files: Option<bool>,
+    /// 包含敏感内容
+    #[serde(default)]
+    pub include_sensitive: Option<bool>,
     /// 仅收藏
     #[serde(default)]
     pub only_favorites: Option<bool>,
@@ -389,12 +392,12 @@ pub struct ConfigSyncResult {
 
 /// 配置同步管理器
 pub struct ConfigSyncManager {
-    webdav_client: WebDAVClientState,
+    storage: SyncStorageState,
     app_data_dir: PathBuf,
 }
 
 impl ConfigSyncManager {
-    pub fn new<R: tauri::Runtime>(webdav_client: WebDAVClientState, app_handle: &AppHandle<R>) -> Self {
+    pub fn new<R: tauri::Runtime>(storage: SyncStorageState, app_handle: &AppHandle<R>) -> Self {
         let _config_filename = get_config_filename();
         let app_data_dir = app_handle
             .path()
@@ -406,7 +409,7 @@ impl ConfigSyncManager {
                 appdata.join(BUNDLE_ID)
             });
         Self {
-            webdav_client,
+            storage,
             app_data_dir,
         }
     }
@@ -462,7 +465,7 @@ impl ConfigSyncManager {
             filtered_config.clipboard_store.is_some()
         );
 
-        let client = self.webdav_client.lock().await;
+        let client = self.storage.lock().await;
         let remote_path = "store-config.json";
 
         match client.upload_sync_data(remote_path, &filtered_json).await {
@@ -487,7 +490,7 @@ impl ConfigSyncManager {
     pub async fn apply_remote_config(&self) -> Result<ConfigSyncResult, String> {
         log::info!("[Config] 开始应用云端配置...");
 
-        let client = self.webdav_client.lock().await;
+        let client = self.storage.lock().await;
         let remote_path = "store-config.json";
 
         let download_result = match client.download_sync_data(remote_path).await {
@@ -0,0 +1,168 @@
+//! 端到端加密模块
+//! 使用口令派生密钥（Argon2id）+ AES-256-GCM 加密所有上传到云端的数据
+//!
+//! 密文格式：`MAGIC(8) | salt(16) | nonce(12) | ciphertext + tag`
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 加密数据头部标识
+const MAGIC: &[u8; 8] = b"ECOENC01";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// 解密失败（口令错误或数据被篡改）
+pub const WRONG_PASSPHRASE_ERROR: &str = "解密失败: 加密密码错误或云端数据已损坏";
+/// 云端数据已加密但本地未配置口令
+pub const MISSING_PASSPHRASE_ERROR: &str = "云端数据已加密，请先在同步设置中填写加密密码";
+/// 已配置口令但云端数据未加密（可能被篡改）
+pub const UNENCRYPTED_DATA_ERROR: &str = "云端数据未加密，已拒绝读取（数据可能被篡改）";
+
+/// 判断数据是否为加密格式
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// 从口令和盐派生密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; KEY_LEN], String> {
+    let mut key = [0u8; KEY_LEN];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("派生加密密钥失败: {}", e))?;
+    Ok(key)
+}
+
+/// 同步数据加密器
+/// 上传统一使用初始化时生成的盐；下载时按密文中的盐派生密钥并缓存，
+/// 以兼容其他设备用不同盐加密的数据
+pub struct SyncCipher {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+    key_cache: Mutex<HashMap<[u8; SALT_LEN], [u8; KEY_LEN]>>,
+}
+
+impl SyncCipher {
+    /// 根据口令创建加密器
+    pub fn new(passphrase: &str) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("加密密码不能为空".to_string());
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt)?;
+
+        Ok(Self {
+            passphrase: passphrase.to_string(),
+            salt,
+            key,
+            key_cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 判断是否使用相同口令创建
+    pub fn matches_passphrase(&self, passphrase: &str) -> bool {
+        self.passphrase == passphrase
+    }
+
+    /// 加密数据
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|e| format!("加密数据失败: {}", e))?;
+
+        let mut output = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        output.extend_from_slice(MAGIC);
+        output.extend_from_slice(&self.salt);
+        output.extend_from_slice(&nonce);
+        output.extend_from_slice(&ciphertext);
+        Ok(output)
+    }
+
+    /// 解密数据
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if !is_encrypted(data) {
+            return Err("数据不是加密格式".to_string());
+        }
+
+        let salt_start = MAGIC.len();
+        let nonce_start = salt_start + SALT_LEN;
+
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&data[salt_start..nonce_start]);
+        let key = self.key_for_salt(&salt)?;
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+        let nonce = Nonce::from_slice(&data[nonce_start..HEADER_LEN]);
+
+        cipher
+            .decrypt(nonce, &data[HEADER_LEN..])
+            .map_err(|_| WRONG_PASSPHRASE_ERROR.to_string())
+    }
+
+    /// 获取指定盐对应的密钥
+    fn key_for_salt(&self, salt: &[u8; SALT_LEN]) -> Result<[u8; KEY_LEN], String> {
+        if salt == &self.salt {
+            return Ok(self.key);
+        }
+
+        let mut cache = self.key_cache.lock().map_err(|e| e.to_string())?;
+        if let Some(key) = cache.get(salt) {
+            return Ok(*key);
+        }
+
+        let key = derive_key(&self.passphrase, salt)?;
+        cache.insert(*salt, key);
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let cipher = SyncCipher::new("correct horse").unwrap();
+        let encrypted = cipher.encrypt(b"[{\"id\":\"1\"}]").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"[{\"id\":\"1\"}]");
+    }
+
+    #[test]
+    fn test_decrypt_from_other_device() {
+        let device_a = SyncCipher::new("correct horse").unwrap();
+        let device_b = SyncCipher::new("correct horse").unwrap();
+        let encrypted = device_a.encrypt(b"bookmark").unwrap();
+
+        assert_eq!(device_b.decrypt(&encrypted).unwrap(), b"bookmark");
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let cipher = SyncCipher::new("correct horse").unwrap();
+        let other = SyncCipher::new("battery staple").unwrap();
+        let encrypted = cipher.encrypt(b"secret").unwrap();
+
+        assert_eq!(
+            other.decrypt(&encrypted).unwrap_err(),
+            WRONG_PASSPHRASE_ERROR
+        );
+    }
+
+    #[test]
+    fn test_plaintext_not_encrypted() {
+        assert!(!is_encrypted(b"[]"));
+        assert!(!is_encrypted(b"ECOENC01"));
+    }
+}
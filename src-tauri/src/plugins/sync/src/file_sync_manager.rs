@@ -3,15 +3,19 @@
 //! 基于前端经验教训，设计更robust的文件同步策略
 //!
 //! 前端踩坑经验总结及解决方案：
-//! 1. 重复上传 - 使用固定文件路径，基于item_id生成唯一路径避免重复上传
+//! 1. 重复上传 - 使用固定文件路径：图片按内容哈希（`files/<sha256>.<扩展名>`），
+//!    相同内容只上传一次；其他文件基于item_id生成唯一路径
 //! 2. 文件丢失 - 统一使用缓存目录，确保文件可恢复
 //! 3. 路径无效 - 原始路径无效时自动切换到缓存目录
 //! 4. 批量操作混乱 - 统一文件包处理逻辑，避免分散处理
 //! 5. 删除遗漏 - 完整的删除流程，确保云端文件被正确删除
 
-use crate::webdav::WebDAVClientState;
+use crate::storage::SyncStorageState;
+use crate::sync_core::SyncDataItem;
 use md5;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::io::AsyncReadExt;
@@ -69,6 +73,32 @@ pub async fn calculate_file_checksum(file_path: &PathBuf) -> Result<String, Stri
     Ok(format!("{:x}", result))
 }
 
+/// 计算文件内容的 SHA-256 哈希
+/// 与数据库插件保存图片时使用的哈希一致，用于生成图片的云端路径
+pub async fn calculate_content_hash(file_path: &PathBuf) -> Result<String, String> {
+    let mut file = tokio::fs::File::open(file_path)
+        .await
+        .map_err(|e| format!("打开文件失败: {}", e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 8192]; // 8KB buffer
+
+    loop {
+        let bytes_read = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+
+        if bytes_read == 0 {
+            break;
+        }
+
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 /// 从 sync item 的 value 字段解析文件路径
 /// 支持 JSON 数组格式 ["path1", "path2"] 和直接字符串格式 "path"
 pub fn parse_file_paths_from_value(value: &str) -> Vec<std::path::PathBuf> {
@@ -93,19 +123,51 @@ pub fn extract_first_file_path(value: &str) -> Option<std::path::PathBuf> {
         .filter(|p| !p.to_string_lossy().is_empty())
 }
 
+/// 从 sync item 的 value 字段（云端元数据 JSON）读取远程路径
+pub fn remote_path_from_value(value: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(value)
+        .ok()?
+        .get("remotePath")?
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+/// 云端数据中仍被引用的远程文件路径
+/// 按内容哈希保存的图片可能被多个条目共享，只有不再被引用时才能删除
+pub fn referenced_remote_paths(items: &[SyncDataItem]) -> HashSet<String> {
+    items
+        .iter()
+        .filter(|item| item.item_type == "image" || item.item_type == "files")
+        .filter_map(|item| item.value.as_deref().and_then(remote_path_from_value))
+        .collect()
+}
+
 /// 从本地文件路径构建上传任务的元数据
+///
+/// # Arguments
+/// * `content_hash` - 图片的内容哈希，传入时远程路径为 `files/<哈希>.<扩展名>`
 pub fn build_metadata_for_upload(
     item_id: &str,
     time: i64,
     local_path: &std::path::PathBuf,
     file_checksum: Option<String>,
+    content_hash: Option<&str>,
 ) -> FileMetadata {
     let file_name = local_path
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
 
-    let remote_path = format!("files/{}_{}", item_id, file_name);
+    let remote_path = match content_hash {
+        Some(hash) => {
+            let extension = local_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("png");
+            format!("files/{}.{}", hash, extension)
+        }
+        None => format!("files/{}_{}", item_id, file_name),
+    };
 
     FileMetadata {
         id: item_id.to_string(),
@@ -174,8 +236,8 @@ pub struct FileSyncConfig {
 /// 文件同步管理器
 /// 负责文件的上传、下载、删除等操作
 pub struct FileSyncManager {
-    /// WebDAV 客户端
-    webdav_client: WebDAVClientState,
+    /// 同步存储
+    storage: SyncStorageState,
     /// 文件同步配置
     config: FileSyncConfig,
 }
@@ -183,11 +245,11 @@ pub struct FileSyncManager {
 impl FileSyncManager {
     /// 创建新的文件同步管理器实例
     /// # Arguments
-    /// * `webdav_client` - WebDAV 客户端
+    /// * `storage` - 同步存储
     /// * `config` - 文件同步配置
-    pub fn new(webdav_client: WebDAVClientState, config: FileSyncConfig) -> Self {
+    pub fn new(storage: SyncStorageState, config: FileSyncConfig) -> Self {
         Self {
-            webdav_client,
+            storage,
             config,
         }
     }
@@ -239,8 +301,8 @@ impl FileSyncManager {
                     file_data.len()
                 );
 
-                // 3. 上传到WebDAV
-                let client = self.webdav_client.lock().await;
+                // 3. 上传到云端
+                let client = self.storage.lock().await;
                 match client.upload_file(&task.remote_path, &file_data).await {
                     Ok(upload_result) => {
                         let end_time = std::time::SystemTime::now()
@@ -305,8 +367,8 @@ impl FileSyncManager {
             .unwrap()
             .as_millis();
 
-        // 1. 从WebDAV下载文件
-        let client = self.webdav_client.lock().await;
+        // 1. 从云端下载文件
+        let client = self.storage.lock().await;
         log::info!(
             "[File] 下载: id={}, name={}, remote={}, size={}",
             task.metadata.id,
@@ -470,8 +532,8 @@ impl FileSyncManager {
             .unwrap()
             .as_millis();
 
-        // 1. 从WebDAV删除文件
-        let client = self.webdav_client.lock().await;
+        // 1. 从云端删除文件
+        let client = self.storage.lock().await;
         match client.delete_file(&remote_path).await {
             Ok(success) => {
                 let end_time = std::time::SystemTime::now()
@@ -555,7 +617,7 @@ impl FileSyncManager {
         for (file_id, remote_path) in file_ids.iter().zip(remote_paths.iter()) {
             let file_id = file_id.clone();
             let remote_path = remote_path.clone();
-            let client_clone = self.webdav_client.clone();
+            let client_clone = self.storage.clone();
 
             let handle = tokio::spawn(async move {
                 let client = client_clone.lock().await;
@@ -623,14 +685,36 @@ impl FileSyncManager {
 }
 
 /// 创建共享的文件同步管理器实例
-pub fn create_shared_manager(webdav_client: WebDAVClientState) -> Arc<Mutex<FileSyncManager>> {
+pub fn create_shared_manager(storage: SyncStorageState) -> Arc<Mutex<FileSyncManager>> {
     let default_config = FileSyncConfig {
         max_file_size: 100 * 1024 * 1024, // 100MB
         timeout_ms: 60000,
     };
 
     Arc::new(Mutex::new(FileSyncManager::new(
-        webdav_client,
+        storage,
         default_config,
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_remote_path_is_keyed_by_hash() {
+        let path = PathBuf::from("/data/images/abc.png");
+
+        let image = build_metadata_for_upload("item-1", 0, &path, None, Some("deadbeef"));
+        assert_eq!(image.remote_path, "files/deadbeef.png");
+
+        let file = build_metadata_for_upload("item-1", 0, &path, None, None);
+        assert_eq!(file.remote_path, "files/item-1_abc.png");
+
+        assert_eq!(
+            remote_path_from_value("{\"remotePath\":\"files/deadbeef.png\"}").as_deref(),
+            Some("files/deadbeef.png")
+        );
+        assert_eq!(remote_path_from_value("/data/images/abc.png"), None);
+    }
+}
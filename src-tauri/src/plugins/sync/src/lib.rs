@@ -5,17 +5,26 @@ use tauri::{
 };
 
 mod auto_sync_manager;
+mod backup;
 mod bookmark_sync_manager;
+mod cloud_index;
 mod commands;
 mod config_sync_manager;
+mod crypto;
 mod data_manager;
 mod file_sync_manager;
+mod local_folder;
+mod merge;
+mod s3;
+mod snippet_sync;
+mod storage;
 mod sync_core;
 mod sync_engine;
 mod types;
 mod webdav;
 
 pub use auto_sync_manager::{create_shared_manager, AutoSyncManagerState};
+pub use backup::{BackupEntry, BackupManifest, BackupResult, RestoreMode, RestoreResult};
 pub use bookmark_sync_manager::{BookmarkSyncData, BookmarkSyncManager, BookmarkSyncResult};
 pub use config_sync_manager::{AppConfig, ConfigSyncManager, ConfigSyncResult};
 pub use data_manager::{create_shared_manager as create_data_manager, DataManager};
@@ -23,10 +32,14 @@ pub use file_sync_manager::{
     create_shared_manager as create_file_sync_manager, FileDownloadTask, FileMetadata,
     FileOperationResult, FileSyncManager, FileUploadTask,
 };
+pub use snippet_sync::{SnippetSyncData, SnippetSyncResult};
 pub use sync_core::{SyncCore, SyncDataItem, SyncModeConfig, SyncProcessResult};
 pub use sync_engine::{create_shared_engine, CloudSyncEngine};
 pub use types::*;
-pub use webdav::{create_shared_client, WebDAVClientState, WebDAVConfig};
+pub use storage::{
+    create_shared_storage, StorageBackend, StorageBackendType, StorageConfig, SyncStorage,
+    SyncStorageState,
+};
 use tauri_plugin_eco_common::paths::get_config_path;
 
 /// 从本地文件读取同步配置
@@ -56,19 +69,35 @@ pub fn read_sync_config_from_file<R: tauri::Runtime>(
                         .and_then(|v| v.get("cloudSync"))
                         .and_then(|v| v.get("serverConfig"))
                     {
-                        // 检查必要字段是否存在
-                        let has_server_url = server_config
-                            .get("url")
-                            .and_then(|v| v.as_str())
-                            .filter(|s| !s.is_empty())
-                            .is_some();
-                        let has_username = server_config
-                            .get("username")
+                        let has_field = |name: &str| {
+                            server_config
+                                .get(name)
+                                .and_then(|v| v.as_str())
+                                .filter(|s| !s.is_empty())
+                                .is_some()
+                        };
+                        let read_option = |name: &str| {
+                            server_config
+                                .get(name)
+                                .and_then(|v| v.as_str())
+                                .filter(|s| !s.is_empty())
+                                .map(|s| s.to_string())
+                        };
+
+                        let backend = server_config
+                            .get("backend")
                             .and_then(|v| v.as_str())
-                            .filter(|s| !s.is_empty())
-                            .is_some();
+                            .and_then(StorageBackendType::parse)
+                            .unwrap_or_default();
+
+                        // 检查所选存储后端的必要字段是否存在
+                        let is_complete = match backend {
+                            StorageBackendType::Webdav => has_field("url") && has_field("username"),
+                            StorageBackendType::Local => has_field("path"),
+                            StorageBackendType::S3 => has_field("url") && has_field("bucket"),
+                        };
 
-                        if !has_server_url || !has_username {
+                        if !is_complete {
                             return None;
                         }
 
@@ -117,6 +146,15 @@ pub fn read_sync_config_from_file<R: tauri::Runtime>(
                             .and_then(|v| v.get("includeBookmarks"))
                             .and_then(|v| v.as_bool())
                             .unwrap_or(false);
+                        let include_sensitive = sync_mode_settings
+                            .and_then(|v| v.get("includeSensitive"))
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(false);
+                        let tombstone_retention_days = sync_mode_settings
+                            .and_then(|v| v.get("tombstoneRetentionDays"))
+                            .and_then(|v| v.as_u64())
+                            .map(|v| v as u32)
+                            .unwrap_or(tauri_plugin_eco_database::DEFAULT_TOMBSTONE_RETENTION_DAYS);
 
                         Some(types::SyncConfig {
                             server_url: server_config
@@ -143,12 +181,18 @@ pub fn read_sync_config_from_file<R: tauri::Runtime>(
                                 .get("timeout")
                                 .and_then(|v| v.as_u64())
                                 .unwrap_or(60000),
+                            encryption_passphrase: read_option("encryptionPassphrase"),
+                            backend: Some(backend),
+                            bucket: read_option("bucket"),
+                            region: read_option("region"),
                             auto_sync,
                             auto_sync_interval_minutes,
                             only_favorites,
                             include_images,
                             include_files,
                             include_bookmarks,
+                            include_sensitive,
+                            tombstone_retention_days,
                         })
                     } else {
                         None
@@ -176,6 +220,9 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             commands::reload_config_from_file,
             commands::upload_local_config,
             commands::apply_remote_config,
+            // 同步冲突命令
+            commands::get_sync_conflicts,
+            commands::resolve_sync_conflict,
             // 书签本地管理命令
             commands::load_bookmark_data,
             commands::save_bookmark_data,
@@ -187,16 +234,19 @@ pub fn init<R: Runtime>() -> TauriPlugin<R> {
             // 服务器配置本地管理命令（不参与云同步）
             commands::save_server_config,
             commands::load_server_config,
+            // 备份与恢复命令
+            backup::create_backup,
+            backup::restore_backup,
         ])
         .setup(|app_handle, _webview_manager| {
             // 在插件初始化时创建共享实例
-            let webdav_client = create_shared_client();
+            let storage = create_shared_storage();
             let auto_sync_manager = create_shared_manager();
             let sync_engine =
-                create_shared_engine(webdav_client.clone(), auto_sync_manager.clone(), app_handle);
+                create_shared_engine(storage.clone(), auto_sync_manager.clone(), app_handle);
 
             // 注册状态管理器，让命令可以访问这些状态
-            app_handle.manage(webdav_client.clone());
+            app_handle.manage(storage.clone());
             app_handle.manage(auto_sync_manager.clone());
             app_handle.manage(sync_engine.clone());
 
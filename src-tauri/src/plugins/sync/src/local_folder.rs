@@ -0,0 +1,520 @@
+//! 本地文件夹存储后端
+//! 将同步数据写入本地或网络文件夹（Syncthing 同步目录、SMB/NFS 挂载等），
+//! 由外部工具负责在设备间传输文件
+//!
+//! 文件 ETag 取内容的 MD5，条件写入在写入前比较，写入时先写临时文件再重命名，
+//! 避免其他设备读到写了一半的文件
+//!
+//! 条件写入的读取、比较和重命名期间持有独占的锁文件（`create_new` 创建），
+//! 共享同一文件夹的多台设备不会同时通过检查而丢失其中一次写入
+
+use crate::storage::{
+    ConnectionTestResult, FileDownloadResult, FileUploadResult, StorageBackend, StorageConfig,
+    WriteCondition, NOT_FOUND_ERROR, PRECONDITION_FAILED_ERROR,
+};
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri_plugin_eco_common::id::generate_id;
+use tokio::io::AsyncWriteExt;
+
+/// 临时文件后缀
+const TEMP_SUFFIX: &str = ".ecopaste-tmp";
+/// 条件写入锁文件后缀
+const LOCK_SUFFIX: &str = ".ecopaste-lock";
+/// 等待锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+/// 超过该时间未释放的锁视为异常退出遗留，直接删除
+const LOCK_STALE_AFTER: Duration = Duration::from_secs(60);
+
+/// 本地文件夹存储
+pub struct LocalFolderBackend {
+    root: PathBuf,
+}
+
+/// 计算内容的 ETag
+fn content_etag(data: &[u8]) -> String {
+    format!("\"{:x}\"", md5::compute(data))
+}
+
+/// 生成锁持有者标识，加入进程号避免多台设备同时生成相同的值
+fn unique_token() -> String {
+    format!("{}-{}", generate_id(), std::process::id())
+}
+
+/// 条件写入锁，释放时删除锁文件
+/// 锁文件内容为持有者标识，接管遗留的锁和释放锁时据此确认不会删除其他设备的锁
+struct WriteLock {
+    path: PathBuf,
+    owner: String,
+}
+
+impl WriteLock {
+    /// 获取目标文件的锁，被其他设备持有时等待
+    async fn acquire(target: &Path) -> Result<Self, String> {
+        let file_name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let path = target.with_file_name(format!(".{}{}", file_name, LOCK_SUFFIX));
+        let owner = unique_token();
+        let start_time = Instant::now();
+
+        loop {
+            match tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .await
+            {
+                Ok(mut file) => {
+                    let lock = Self { path, owner };
+                    file.write_all(lock.owner.as_bytes())
+                        .await
+                        .map_err(|e| format!("写入文件锁失败: {}", e))?;
+                    return Ok(lock);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if let Some(stale_owner) = Self::stale_owner(&path).await {
+                        Self::take_over(&path, &stale_owner).await;
+                        continue;
+                    }
+                    if start_time.elapsed() >= LOCK_TIMEOUT {
+                        return Err(format!("等待文件锁超时: {}", path.display()));
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Err(e) => return Err(format!("创建文件锁失败: {}", e)),
+            }
+        }
+    }
+
+    /// 锁文件超过有效期未释放时返回其持有者标识
+    async fn stale_owner(path: &Path) -> Option<String> {
+        let stale = tokio::fs::metadata(path)
+            .await
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|elapsed| elapsed > LOCK_STALE_AFTER);
+
+        if stale {
+            tokio::fs::read_to_string(path).await.ok()
+        } else {
+            None
+        }
+    }
+
+    /// 接管遗留的锁
+    /// 先原子地重命名为唯一的文件名（同时等待的设备只有一个能成功），
+    /// 确认拿到的仍是判定为遗留的锁后再删除；拿到的是其他设备刚创建的锁时放回原处
+    async fn take_over(path: &Path, stale_owner: &str) {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let moved = path.with_file_name(format!("{}.{}", file_name, unique_token()));
+
+        if tokio::fs::rename(path, &moved).await.is_err() {
+            // 已被其他设备接管或释放
+            return;
+        }
+
+        let owner = tokio::fs::read_to_string(&moved).await.unwrap_or_default();
+        if owner == stale_owner && Self::stale_owner(&moved).await.is_some() {
+            log::warn!("[LocalFolder] 删除遗留的锁文件: {}", path.display());
+        } else {
+            // 硬链接在目标已存在时失败，不会覆盖之后创建的锁
+            let _ = tokio::fs::hard_link(&moved, path).await;
+        }
+        let _ = tokio::fs::remove_file(&moved).await;
+    }
+}
+
+impl Drop for WriteLock {
+    fn drop(&mut self) {
+        // 锁已被其他设备接管时不删除
+        if std::fs::read_to_string(&self.path).is_ok_and(|owner| owner == self.owner) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+impl LocalFolderBackend {
+    /// 根据配置创建本地文件夹存储（`path` 为同步目录的绝对路径）
+    pub fn new(config: &StorageConfig) -> Result<Self, String> {
+        let root = PathBuf::from(config.path.trim());
+
+        if !root.is_absolute() {
+            return Err(format!("同步文件夹必须是绝对路径: {}", config.path));
+        }
+
+        Ok(Self { root })
+    }
+
+    /// 相对路径转换为本地路径，拒绝跳出同步目录的路径
+    fn resolve(&self, relative_path: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(relative_path.trim_start_matches('/'));
+
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(format!("非法的同步路径: {}", relative_path));
+        }
+
+        Ok(self.root.join(relative))
+    }
+
+    /// 读取文件当前的 ETag，文件不存在时返回 None
+    async fn current_etag(path: &Path) -> Result<Option<String>, String> {
+        match tokio::fs::read(path).await {
+            Ok(data) => Ok(Some(content_etag(&data))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("读取文件失败: {}", e)),
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalFolderBackend {
+    async fn prepare(&self) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.root)
+            .await
+            .map_err(|e| format!("创建同步文件夹失败: {}", e))
+    }
+
+    async fn test_connection(&self) -> Result<ConnectionTestResult, String> {
+        let start_time = Instant::now();
+        let probe = self.root.join(format!(".ecopaste-probe{}", TEMP_SUFFIX));
+
+        // 写入并删除探测文件，确认目录可写
+        let result = async {
+            tokio::fs::create_dir_all(&self.root).await?;
+            tokio::fs::write(&probe, b"ok").await?;
+            tokio::fs::remove_file(&probe).await
+        }
+        .await;
+
+        Ok(ConnectionTestResult {
+            success: result.is_ok(),
+            latency_ms: start_time.elapsed().as_millis() as u64,
+            status_code: None,
+            error_message: result
+                .err()
+                .map(|e| format!("同步文件夹不可写: {}", e)),
+            server_info: Some(self.root.to_string_lossy().to_string()),
+        })
+    }
+
+    async fn upload(
+        &self,
+        file_path: &str,
+        data: &[u8],
+        condition: &WriteCondition,
+    ) -> Result<FileUploadResult, String> {
+        let target = self.resolve(file_path)?;
+
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+
+        // 条件写入在重命名完成前持有锁
+        let _lock = match condition {
+            WriteCondition::Always => None,
+            _ => Some(WriteLock::acquire(&target).await?),
+        };
+
+        let precondition_ok = match condition {
+            WriteCondition::Always => true,
+            WriteCondition::IfMatch(etag) => {
+                Self::current_etag(&target).await?.as_deref() == Some(etag.as_str())
+            }
+            WriteCondition::IfNoneMatch => Self::current_etag(&target).await?.is_none(),
+        };
+
+        if !precondition_ok {
+            return Ok(FileUploadResult {
+                success: false,
+                path: file_path.to_string(),
+                size: 0,
+                duration_ms: 0,
+                error_message: Some(PRECONDITION_FAILED_ERROR.to_string()),
+                etag: None,
+            });
+        }
+
+        let file_name = target
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp = target.with_file_name(format!(
+            ".{}-{}{}",
+            file_name,
+            std::process::id(),
+            TEMP_SUFFIX
+        ));
+
+        tokio::fs::write(&temp, data)
+            .await
+            .map_err(|e| format!("写入文件失败: {}", e))?;
+        if let Err(e) = tokio::fs::rename(&temp, &target).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(format!("写入文件失败: {}", e));
+        }
+
+        Ok(FileUploadResult {
+            success: true,
+            path: file_path.to_string(),
+            size: data.len() as u64,
+            duration_ms: 0,
+            error_message: None,
+            etag: Some(content_etag(data)),
+        })
+    }
+
+    async fn download(&self, file_path: &str) -> Result<FileDownloadResult, String> {
+        let target = self.resolve(file_path)?;
+
+        match tokio::fs::read(&target).await {
+            Ok(data) => Ok(FileDownloadResult {
+                success: true,
+                path: file_path.to_string(),
+                size: data.len() as u64,
+                duration_ms: 0,
+                data: None,
+                etag: Some(content_etag(&data)),
+                binary_data: Some(data),
+                error_message: None,
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(FileDownloadResult {
+                success: false,
+                path: file_path.to_string(),
+                size: 0,
+                duration_ms: 0,
+                data: None,
+                binary_data: None,
+                error_message: Some(NOT_FOUND_ERROR.to_string()),
+                etag: None,
+            }),
+            Err(e) => Err(format!("读取文件失败: {}", e)),
+        }
+    }
+
+    async fn delete(&self, remote_path: &str) -> Result<bool, String> {
+        let target = self.resolve(remote_path)?;
+
+        match tokio::fs::remove_file(&target).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(true),
+            Err(e) => Err(format!("删除文件失败: {}", e)),
+        }
+    }
+
+    async fn list(&self, dir: &str) -> Result<Vec<String>, String> {
+        let dir = dir.trim_matches('/');
+        let target = if dir.is_empty() {
+            self.root.clone()
+        } else {
+            self.resolve(dir)?
+        };
+
+        let mut entries = match tokio::fs::read_dir(&target).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(format!("列出目录失败: {}", e)),
+        };
+
+        let mut files = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("列出目录失败: {}", e))?
+        {
+            let is_file = entry.file_type().await.map(|t| t.is_file()).unwrap_or(false);
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // 跳过临时文件和同步工具的隐藏文件（如 .stfolder）
+            if !is_file || name.starts_with('.') {
+                continue;
+            }
+
+            files.push(if dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", dir, name)
+            });
+        }
+
+        files.sort();
+        Ok(files)
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, String> {
+        let target = self.resolve(path)?;
+        tokio::fs::try_exists(&target)
+            .await
+            .map_err(|e| format!("检查文件失败: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backend(name: &str) -> LocalFolderBackend {
+        let root = std::env::temp_dir().join(format!(
+            "ecopaste-local-backend-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+
+        LocalFolderBackend::new(&StorageConfig {
+            backend: crate::storage::StorageBackendType::Local,
+            url: String::new(),
+            username: String::new(),
+            password: String::new(),
+            path: root.to_string_lossy().to_string(),
+            timeout: 0,
+            bucket: None,
+            region: None,
+            encryption_passphrase: None,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upload_download_list_delete() {
+        let backend = backend("roundtrip");
+        let uploaded = backend
+            .upload("index/a.json", b"[]", &WriteCondition::Always)
+            .await
+            .unwrap();
+        assert!(uploaded.success);
+
+        let downloaded = backend.download("index/a.json").await.unwrap();
+        assert_eq!(downloaded.binary_data.as_deref(), Some(&b"[]"[..]));
+        assert_eq!(downloaded.etag, uploaded.etag);
+
+        assert_eq!(backend.list("index").await.unwrap(), vec!["index/a.json"]);
+        assert!(backend.delete("index/a.json").await.unwrap());
+        assert!(!backend.exists("index/a.json").await.unwrap());
+        assert_eq!(
+            backend
+                .download("index/a.json")
+                .await
+                .unwrap()
+                .error_message
+                .as_deref(),
+            Some(NOT_FOUND_ERROR)
+        );
+
+        let _ = std::fs::remove_dir_all(&backend.root);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_upload() {
+        let backend = backend("conditional");
+        let first = backend
+            .upload("manifest.json", b"1", &WriteCondition::IfNoneMatch)
+            .await
+            .unwrap();
+        assert!(first.success);
+
+        let again = backend
+            .upload("manifest.json", b"2", &WriteCondition::IfNoneMatch)
+            .await
+            .unwrap();
+        assert!(again.is_precondition_failed());
+
+        let matched = backend
+            .upload(
+                "manifest.json",
+                b"2",
+                &WriteCondition::IfMatch(first.etag.clone().unwrap()),
+            )
+            .await
+            .unwrap();
+        assert!(matched.success);
+
+        let stale = backend
+            .upload(
+                "manifest.json",
+                b"3",
+                &WriteCondition::IfMatch(first.etag.unwrap()),
+            )
+            .await
+            .unwrap();
+        assert!(stale.is_precondition_failed());
+
+        let _ = std::fs::remove_dir_all(&backend.root);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_conditional_upload() {
+        let backend = backend("lock");
+        let first = backend
+            .upload("manifest.json", b"1", &WriteCondition::Always)
+            .await
+            .unwrap();
+        let condition = WriteCondition::IfMatch(first.etag.unwrap());
+
+        // 基于同一版本的两次写入只有一次成功
+        let (a, b) = tokio::join!(
+            backend.upload("manifest.json", b"a", &condition),
+            backend.upload("manifest.json", b"b", &condition),
+        );
+        let (a, b) = (a.unwrap(), b.unwrap());
+        assert!(a.success != b.success);
+        assert!(a.is_precondition_failed() || b.is_precondition_failed());
+
+        // 写入完成后锁文件已删除
+        let lock = backend.root.join(format!(".manifest.json{}", LOCK_SUFFIX));
+        assert!(!lock.exists());
+        assert_eq!(backend.list("").await.unwrap(), vec!["manifest.json"]);
+
+        let _ = std::fs::remove_dir_all(&backend.root);
+    }
+
+    #[tokio::test]
+    async fn test_stale_lock_takeover() {
+        let backend = backend("stale");
+        std::fs::create_dir_all(&backend.root).unwrap();
+        let lock = backend.root.join(format!(".manifest.json{}", LOCK_SUFFIX));
+
+        // 超过有效期的锁可以被接管
+        std::fs::write(&lock, "crashed").unwrap();
+        let expired = std::time::SystemTime::now() - LOCK_STALE_AFTER * 2;
+        std::fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(expired)
+            .unwrap();
+        let uploaded = backend
+            .upload("manifest.json", b"1", &WriteCondition::IfNoneMatch)
+            .await
+            .unwrap();
+        assert!(uploaded.success);
+        assert!(!lock.exists());
+
+        // 判定遗留后锁已被其他设备重新获取：放回原处，不删除
+        std::fs::write(&lock, "fresh").unwrap();
+        WriteLock::take_over(&lock, "crashed").await;
+        assert_eq!(std::fs::read_to_string(&lock).unwrap(), "fresh");
+        assert_eq!(std::fs::read_dir(&backend.root).unwrap().count(), 2);
+
+        let _ = std::fs::remove_dir_all(&backend.root);
+    }
+
+    #[test]
+    fn test_rejects_parent_path() {
+        let backend = backend("escape");
+        assert!(backend.resolve("../secret").is_err());
+        assert!(backend.resolve("files/a.png").is_ok());
+    }
+}
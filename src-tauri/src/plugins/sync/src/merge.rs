@@ -0,0 +1,275 @@
+//! 字段级三方合并
+//! 以上次同步时的基准版本为参照，逐字段合并本地与云端的修改，标签逐个合并
+
+use crate::sync_core::SyncDataItem;
+use tauri_plugin_eco_database::SyncConflict;
+
+/// 参与合并的字段
+const MERGE_FIELDS: [&str; 6] = [
+    "favorite",
+    "note",
+    "value",
+    "subtype",
+    "expiresAt",
+    "expireOnPaste",
+];
+
+/// 合并结果
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    /// 合并后的数据
+    pub merged: SyncDataItem,
+    /// 本地需要更新（合并结果与本地不同）
+    pub local_changed: bool,
+    /// 云端需要更新（合并结果与云端不同）
+    pub remote_changed: bool,
+    /// 无法自动合并的字段
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// 读取字段值（统一为字符串表示，便于比较和记录冲突）
+fn field_value(item: &SyncDataItem, field: &str) -> Option<String> {
+    match field {
+        "favorite" => Some(if item.favorite { "1" } else { "0" }.to_string()),
+        "note" => item.note.clone().filter(|s| !s.is_empty()),
+        "value" => item.value.clone(),
+        "subtype" => item.subtype.clone(),
+        "expiresAt" => item.expires_at.map(|at| at.to_string()),
+        "expireOnPaste" => Some(if item.expire_on_paste { "1" } else { "0" }.to_string()),
+        _ => None,
+    }
+}
+
+/// 写入字段值
+fn set_field_value(item: &mut SyncDataItem, field: &str, value: Option<String>) {
+    match field {
+        "favorite" => item.favorite = value.as_deref() == Some("1"),
+        "note" => item.note = value,
+        "value" => item.value = value,
+        "subtype" => item.subtype = value,
+        "expiresAt" => item.expires_at = value.and_then(|v| v.parse().ok()),
+        "expireOnPaste" => item.expire_on_paste = value.as_deref() == Some("1"),
+        _ => {}
+    }
+}
+
+/// 图片和文件的 value 在本地是路径、在云端是元数据，不参与合并
+fn is_mergeable_field(item: &SyncDataItem, field: &str) -> bool {
+    !(field == "value" && (item.item_type == "image" || item.item_type == "files"))
+}
+
+/// 逐个标签三方合并：每个标签以修改过的一端为准（标签只有有/无两种状态，两端都修改时结果一定相同）
+fn merge_tags(base: &[String], local: &[String], remote: &[String]) -> Vec<String> {
+    let mut merged: Vec<String> = base
+        .iter()
+        .chain(local)
+        .chain(remote)
+        .filter(|tag| {
+            let in_base = base.contains(tag);
+            if local.contains(tag) != in_base {
+                !in_base
+            } else {
+                remote.contains(tag)
+            }
+        })
+        .cloned()
+        .collect();
+
+    merged.sort_by_key(|tag| tag.to_lowercase());
+    merged.dedup_by(|a, b| a.to_lowercase() == b.to_lowercase());
+    merged
+}
+
+/// 三方合并
+/// - 只有一端修改的字段直接采用修改后的值
+/// - 两端修改为相同值视为无冲突
+/// - 两端修改为不同值时暂时采用云端的值，并记录冲突供用户决定
+pub fn three_way_merge(
+    base: &SyncDataItem,
+    local: &SyncDataItem,
+    remote: &SyncDataItem,
+) -> MergeOutcome {
+    let mut merged = local.clone();
+    let mut conflicts = Vec::new();
+    let now = chrono::Utc::now().timestamp_millis();
+
+    for field in MERGE_FIELDS {
+        if !is_mergeable_field(local, field) {
+            continue;
+        }
+
+        let base_value = field_value(base, field);
+        let local_value = field_value(local, field);
+        let remote_value = field_value(remote, field);
+
+        let local_modified = local_value != base_value;
+        let remote_modified = remote_value != base_value;
+
+        let value = match (local_modified, remote_modified) {
+            (_, false) => local_value,
+            (false, true) => remote_value,
+            (true, true) if local_value == remote_value => local_value,
+            (true, true) => {
+                conflicts.push(SyncConflict {
+                    id: 0,
+                    item_id: local.id.clone(),
+                    field: field.to_string(),
+                    base_value,
+                    local_value,
+                    remote_value: remote_value.clone(),
+                    created_at: now,
+                    resolved: false,
+                });
+                remote_value
+            }
+        };
+
+        set_field_value(&mut merged, field, value);
+    }
+
+    merged.tags = merge_tags(&base.tags, &local.tags, &remote.tags);
+
+    let differs = |other: &SyncDataItem| {
+        merged.tags != other.tags
+            || MERGE_FIELDS.iter().any(|field| {
+                is_mergeable_field(local, field)
+                    && field_value(&merged, field) != field_value(other, field)
+            })
+    };
+
+    let local_changed = differs(local);
+    let remote_changed = differs(remote);
+
+    // 云端无需更新时沿用云端时间戳，避免仅因时间不同而重复上传
+    merged.time = if remote_changed {
+        local.time.max(remote.time)
+    } else {
+        remote.time
+    };
+
+    MergeOutcome {
+        merged,
+        local_changed,
+        remote_changed,
+        conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(favorite: bool, note: Option<&str>, value: &str) -> SyncDataItem {
+        SyncDataItem {
+            id: "item-1".to_string(),
+            item_type: "text".to_string(),
+            subtype: None,
+            value: Some(value.to_string()),
+            search: Some(value.to_string()),
+            favorite,
+            note: note.map(|s| s.to_string()),
+            time: 1000,
+            tags: vec![],
+            expires_at: None,
+            expire_on_paste: false,
+        }
+    }
+
+    #[test]
+    fn test_merge_disjoint_changes() {
+        let base = item(false, None, "hello");
+        let local = item(false, Some("备注"), "hello");
+        let remote = item(true, None, "hello");
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert!(outcome.conflicts.is_empty());
+        assert!(outcome.merged.favorite);
+        assert_eq!(outcome.merged.note.as_deref(), Some("备注"));
+        assert!(outcome.local_changed);
+        assert!(outcome.remote_changed);
+    }
+
+    #[test]
+    fn test_merge_only_remote_changed() {
+        let base = item(false, None, "hello");
+        let local = base.clone();
+        let mut remote = item(true, None, "hello");
+        remote.time = 2000;
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert!(outcome.local_changed);
+        assert!(!outcome.remote_changed);
+        assert_eq!(outcome.merged.time, 2000);
+    }
+
+    #[test]
+    fn test_merge_conflicting_note() {
+        let base = item(false, Some("a"), "hello");
+        let local = item(false, Some("b"), "hello");
+        let remote = item(false, Some("c"), "hello");
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].field, "note");
+        assert_eq!(outcome.conflicts[0].local_value.as_deref(), Some("b"));
+        assert_eq!(outcome.merged.note.as_deref(), Some("c"));
+        assert!(!outcome.remote_changed);
+    }
+
+    #[test]
+    fn test_merge_expiry() {
+        let base = item(false, None, "hello");
+        let mut local = base.clone();
+        local.expire_on_paste = true;
+        let mut remote = base.clone();
+        remote.expires_at = Some(5000);
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.merged.expires_at, Some(5000));
+        assert!(outcome.merged.expire_on_paste);
+        assert!(outcome.local_changed);
+        assert!(outcome.remote_changed);
+    }
+
+    #[test]
+    fn test_merge_tags_per_tag() {
+        let tags = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let mut base = item(false, None, "hello");
+        base.tags = tags(&["a", "b"]);
+        // 本地移除 a、添加 c；云端添加 d
+        let mut local = base.clone();
+        local.tags = tags(&["b", "c"]);
+        let mut remote = base.clone();
+        remote.tags = tags(&["a", "b", "d"]);
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.merged.tags, tags(&["b", "c", "d"]));
+        assert!(outcome.local_changed);
+        assert!(outcome.remote_changed);
+    }
+
+    #[test]
+    fn test_merge_skips_file_value() {
+        let mut base = item(false, None, "C:/a.png");
+        base.item_type = "image".to_string();
+        let mut local = base.clone();
+        local.value = Some("C:/cache/a.png".to_string());
+        let mut remote = base.clone();
+        remote.value = Some("{\"remotePath\":\"files/item-1_a.png\"}".to_string());
+
+        let outcome = three_way_merge(&base, &local, &remote);
+
+        assert!(outcome.conflicts.is_empty());
+        assert_eq!(outcome.merged.value, local.value);
+        assert!(!outcome.local_changed);
+        assert!(!outcome.remote_changed);
+    }
+}
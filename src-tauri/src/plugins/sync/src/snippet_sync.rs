@@ -0,0 +1,114 @@
+//! 片段同步
+//! 所有片段（包括删除标记）保存在云端的 `snippet-sync.json` 中，
+//! 同步时逐个片段比较 `updated_at`，较新的版本胜出：
+//! 1. 下载云端片段并合并到本地
+//! 2. 本地有云端没有的或更新的片段时，上传合并后的完整列表
+
+use crate::storage::SyncStorageState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri_plugin_eco_database::{DatabaseState, Snippet};
+
+/// 云端片段文件
+const SNIPPET_SYNC_FILE: &str = "snippet-sync.json";
+
+/// 云端片段数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnippetSyncData {
+    pub snippets: Vec<Snippet>,
+}
+
+/// 片段同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnippetSyncResult {
+    /// 本地更新的片段数量
+    pub downloaded: usize,
+    /// 是否上传了本地片段
+    pub uploaded: bool,
+}
+
+/// 本地是否有需要上传的片段（云端没有，或本地版本更新）
+fn needs_upload(local: &[Snippet], cloud: &[Snippet]) -> bool {
+    let cloud_versions: HashMap<&str, i64> = cloud
+        .iter()
+        .map(|s| (s.id.as_str(), s.updated_at))
+        .collect();
+
+    local
+        .iter()
+        .any(|s| match cloud_versions.get(s.id.as_str()) {
+            Some(&updated_at) => s.updated_at > updated_at,
+            None => true,
+        })
+}
+
+/// 执行片段同步
+pub async fn sync_snippets(
+    storage: &SyncStorageState,
+    database_state: &DatabaseState,
+) -> Result<SnippetSyncResult, String> {
+    let client = storage.lock().await;
+
+    let download = client.download_sync_data(SNIPPET_SYNC_FILE).await?;
+    let cloud: SnippetSyncData = match download.data {
+        Some(data) if download.success => {
+            serde_json::from_str(&data).map_err(|e| format!("解析云端片段失败: {}", e))?
+        }
+        _ => SnippetSyncData::default(),
+    };
+
+    let (downloaded, local) = {
+        let db = database_state.read().await;
+        let downloaded = db.merge_remote_snippets(&cloud.snippets)?;
+        (downloaded, db.query_snippets_for_sync()?)
+    };
+
+    let uploaded = needs_upload(&local, &cloud.snippets);
+    if uploaded {
+        let json = serde_json::to_string(&SnippetSyncData { snippets: local })
+            .map_err(|e| format!("序列化片段失败: {}", e))?;
+        let result = client.upload_sync_data(SNIPPET_SYNC_FILE, &json).await?;
+        if !result.success {
+            return Err(format!(
+                "上传片段失败: {}",
+                result.error_message.unwrap_or_default()
+            ));
+        }
+    }
+
+    log::info!(
+        "[Snippet] 同步完成: 下载 {} 个, 上传={}",
+        downloaded,
+        uploaded
+    );
+    Ok(SnippetSyncResult {
+        downloaded,
+        uploaded,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snippet(id: &str, updated_at: i64) -> Snippet {
+        Snippet {
+            id: id.to_string(),
+            name: id.to_string(),
+            content: String::new(),
+            created_at: 0,
+            updated_at,
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn test_needs_upload() {
+        let cloud = vec![snippet("a", 10), snippet("b", 10)];
+
+        assert!(!needs_upload(&[snippet("a", 10)], &cloud));
+        assert!(!needs_upload(&[snippet("a", 5)], &cloud));
+        assert!(needs_upload(&[snippet("a", 11)], &cloud));
+        assert!(needs_upload(&[snippet("c", 1)], &cloud));
+    }
+}
@@ -0,0 +1,803 @@
+//! 同步存储模块
+//! 定义云端存储后端接口（WebDAV / 本地文件夹 / S3 兼容存储），
+//! 并在其上统一处理端到端加密与失败重试
+
+use crate::crypto::{self, SyncCipher};
+use crate::local_folder::LocalFolderBackend;
+use crate::s3::S3Backend;
+use crate::webdav::WebDAVClient;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// 同步存储状态
+pub type SyncStorageState = Arc<Mutex<SyncStorage>>;
+
+/// 文件不存在
+pub const NOT_FOUND_ERROR: &str = "HTTP 404";
+/// 条件请求不满足
+pub const PRECONDITION_FAILED_ERROR: &str = "HTTP 412";
+
+/// 存储后端类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackendType {
+    /// WebDAV 服务器
+    #[default]
+    Webdav,
+    /// 本地或网络文件夹（Syncthing 同步目录、SMB 挂载等）
+    Local,
+    /// S3 兼容对象存储（AWS S3、MinIO 等）
+    S3,
+}
+
+impl StorageBackendType {
+    /// 从配置字符串解析，未知值返回 None
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "webdav" => Some(Self::Webdav),
+            "local" => Some(Self::Local),
+            "s3" => Some(Self::S3),
+            _ => None,
+        }
+    }
+}
+
+/// 存储配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// 后端类型
+    #[serde(default)]
+    pub backend: StorageBackendType,
+    /// 服务器地址（WebDAV 地址 / S3 Endpoint，本地文件夹不使用）
+    pub url: String,
+    /// 用户名（S3 为 Access Key）
+    pub username: String,
+    /// 密码（S3 为 Secret Key）
+    pub password: String,
+    /// 同步路径（本地文件夹为目录的绝对路径，S3 为对象键前缀）
+    pub path: String,
+    /// 超时（毫秒）
+    pub timeout: u64,
+    /// S3 存储桶
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 区域
+    #[serde(default)]
+    pub region: Option<String>,
+    /// 端到端加密口令（为空时不加密）
+    #[serde(default)]
+    pub encryption_passphrase: Option<String>,
+}
+
+/// 文件上传结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileUploadResult {
+    pub success: bool,
+    pub path: String,
+    pub size: u64,
+    pub duration_ms: u64,
+    pub error_message: Option<String>,
+    /// 上传后服务器返回的 ETag（服务器不支持时为空）
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+impl FileUploadResult {
+    /// 是否因条件请求不满足而失败（云端文件已被其他设备修改）
+    pub fn is_precondition_failed(&self) -> bool {
+        !self.success && self.error_message.as_deref() == Some(PRECONDITION_FAILED_ERROR)
+    }
+}
+
+/// 文件下载结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileDownloadResult {
+    pub success: bool,
+    pub path: String,
+    pub size: u64,
+    pub duration_ms: u64,
+    pub data: Option<String>,
+    pub binary_data: Option<Vec<u8>>,
+    pub error_message: Option<String>,
+    /// 服务器返回的 ETag（服务器不支持时为空）
+    #[serde(default)]
+    pub etag: Option<String>,
+}
+
+impl FileDownloadResult {
+    /// 是否因文件不存在而失败
+    pub fn is_not_found(&self) -> bool {
+        !self.success && self.error_message.as_deref() == Some(NOT_FOUND_ERROR)
+    }
+}
+
+/// 上传条件
+/// 用于乐观并发控制，避免多台设备同时写入时互相覆盖
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteCondition {
+    /// 无条件覆盖
+    Always,
+    /// 仅当云端文件的 ETag 与给定值一致时写入
+    IfMatch(String),
+    /// 仅当云端文件不存在时写入
+    IfNoneMatch,
+}
+
+/// 连接测试结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTestResult {
+    pub success: bool,
+    pub latency_ms: u64,
+    pub status_code: Option<u16>,
+    pub error_message: Option<String>,
+    pub server_info: Option<String>,
+}
+
+/// 存储后端接口
+///
+/// 路径均为相对同步目录的路径（如 `index/manifest.json`）。
+/// 文件不存在、条件不满足等业务失败通过 `success: false` 和
+/// [`NOT_FOUND_ERROR`] / [`PRECONDITION_FAILED_ERROR`] 返回，
+/// 网络等异常通过 `Err` 返回
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// 初始化时准备存储（如创建同步目录）
+    async fn prepare(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// 测试连接
+    async fn test_connection(&self) -> Result<ConnectionTestResult, String>;
+
+    /// 上传文件
+    async fn upload(
+        &self,
+        path: &str,
+        data: &[u8],
+        condition: &WriteCondition,
+    ) -> Result<FileUploadResult, String>;
+
+    /// 下载文件（结果放在 `binary_data` 中）
+    async fn download(&self, path: &str) -> Result<FileDownloadResult, String>;
+
+    /// 删除文件，文件不存在也视为成功
+    async fn delete(&self, path: &str) -> Result<bool, String>;
+
+    /// 列出目录下的文件（不递归），返回相对同步目录的路径
+    async fn list(&self, dir: &str) -> Result<Vec<String>, String>;
+
+    /// 检查文件是否存在
+    async fn exists(&self, path: &str) -> Result<bool, String>;
+}
+
+/// 根据路径和内容推断上传的 Content-Type
+pub(crate) fn content_type_for(path: &str, data: &[u8]) -> &'static str {
+    if path.ends_with(".json") && !crypto::is_encrypted(data) {
+        "application/json; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// 提取 XML 中指定名称元素的文本（忽略命名空间前缀）
+/// 只用于解析 PROPFIND / ListObjects 这类结构简单的响应
+pub(crate) fn xml_element_texts(body: &str, name: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut rest = body;
+
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>') else {
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+
+        if tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+
+        let tag_name = tag.split_whitespace().next().unwrap_or_default();
+        let local_name = tag_name.rsplit(':').next().unwrap_or(tag_name);
+        if local_name == name {
+            if let Some(end) = rest.find('<') {
+                texts.push(xml_unescape(rest[..end].trim()));
+            }
+        }
+    }
+
+    texts
+}
+
+/// 还原 XML 转义字符
+fn xml_unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 重试配置
+struct RetryConfig {
+    max_retries: u32,
+    base_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 1000,
+        }
+    }
+}
+
+/// 同步存储
+/// 持有当前配置的存储后端，对上层提供带加密和重试的统一读写接口
+pub struct SyncStorage {
+    config: Option<StorageConfig>,
+    backend: Option<Box<dyn StorageBackend>>,
+    cipher: Option<Arc<SyncCipher>>,
+    /// 正在迁移启用加密前上传的数据，期间允许读取未加密的内容
+    plaintext_migration: AtomicBool,
+}
+
+impl Default for SyncStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncStorage {
+    /// 创建未初始化的同步存储
+    pub fn new() -> Self {
+        Self {
+            config: None,
+            backend: None,
+            cipher: None,
+            plaintext_migration: AtomicBool::new(false),
+        }
+    }
+
+    /// 按配置初始化存储后端
+    pub async fn initialize(&mut self, config: StorageConfig) -> Result<(), String> {
+        let backend: Box<dyn StorageBackend> = match config.backend {
+            StorageBackendType::Webdav => Box::new(WebDAVClient::new(&config)?),
+            StorageBackendType::Local => Box::new(LocalFolderBackend::new(&config)?),
+            StorageBackendType::S3 => Box::new(S3Backend::new(&config)?),
+        };
+
+        self.cipher = match config
+            .encryption_passphrase
+            .as_deref()
+            .filter(|p| !p.is_empty())
+        {
+            // 口令未变化时复用已派生的密钥
+            Some(passphrase) => match self.cipher.take() {
+                Some(cipher) if cipher.matches_passphrase(passphrase) => Some(cipher),
+                _ => {
+                    let passphrase = passphrase.to_string();
+                    let cipher = tokio::task::spawn_blocking(move || SyncCipher::new(&passphrase))
+                        .await
+                        .map_err(|e| format!("初始化加密失败: {}", e))??;
+                    Some(Arc::new(cipher))
+                }
+            },
+            None => None,
+        };
+
+        // 降级处理：准备失败只记录警告，不阻塞初始化
+        // 网络不稳定时应用仍可启动，后续同步时会自动创建目录
+        if let Err(e) = backend.prepare().await {
+            log::warn!("[Sync] 初始化时检查同步目录失败: {}，应用将继续运行", e);
+        }
+
+        self.config = Some(config);
+        self.backend = Some(backend);
+        self.plaintext_migration.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// 检查是否已初始化
+    pub fn is_initialized(&self) -> bool {
+        self.config.is_some() && self.backend.is_some()
+    }
+
+    /// 获取当前配置（用于比较配置是否更改）
+    pub fn get_config(&self) -> Option<&StorageConfig> {
+        self.config.as_ref()
+    }
+
+    /// 重置存储状态（测试失败时调用）
+    pub fn reset(&mut self) {
+        self.config = None;
+        self.backend = None;
+        self.cipher = None;
+        self.plaintext_migration.store(false, Ordering::SeqCst);
+    }
+
+    /// 是否启用了端到端加密
+    pub fn is_encryption_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// 获取存储后端
+    fn backend(&self) -> Result<&dyn StorageBackend, String> {
+        self.backend
+            .as_deref()
+            .ok_or_else(|| "同步存储未初始化".to_string())
+    }
+
+    /// 上传前加密数据（未启用加密时原样返回）
+    fn encrypt_payload(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        match &self.cipher {
+            Some(cipher) => cipher.encrypt(data),
+            None => Ok(data.to_vec()),
+        }
+    }
+
+    /// 开始迁移启用加密前上传的数据（云端索引清单未加密时由索引调用）
+    /// 迁移期间允许读取未加密的内容，新清单加密提交后调用 [`Self::finish_plaintext_migration`]
+    pub fn begin_plaintext_migration(&self) {
+        if self.cipher.is_some() && !self.plaintext_migration.swap(true, Ordering::SeqCst) {
+            log::warn!("[Sync] 云端存在启用加密前上传的数据，本次同步后将全部加密");
+        }
+    }
+
+    /// 结束未加密数据的迁移，之后拒绝读取未加密的内容
+    pub fn finish_plaintext_migration(&self) {
+        self.plaintext_migration.store(false, Ordering::SeqCst);
+    }
+
+    /// 是否正在迁移未加密的数据
+    pub fn is_plaintext_migration(&self) -> bool {
+        self.plaintext_migration.load(Ordering::SeqCst)
+    }
+
+    /// 下载后解密数据
+    /// 启用加密时拒绝未加密的内容，避免有存储写权限的人注入明文数据；
+    /// 只有迁移启用加密前的数据时（`allow_plaintext`）允许读取
+    fn decrypt_payload(&self, data: Vec<u8>, allow_plaintext: bool) -> Result<Vec<u8>, String> {
+        match &self.cipher {
+            Some(cipher) if crypto::is_encrypted(&data) => cipher.decrypt(&data),
+            Some(_) if allow_plaintext => Ok(data),
+            Some(_) => Err(crypto::UNENCRYPTED_DATA_ERROR.to_string()),
+            None if crypto::is_encrypted(&data) => {
+                Err(crypto::MISSING_PASSPHRASE_ERROR.to_string())
+            }
+            None => Ok(data),
+        }
+    }
+
+    /// 解密下载结果中的二进制数据
+    fn decrypt_download_result(
+        &self,
+        result: FileDownloadResult,
+        allow_plaintext: bool,
+    ) -> Result<FileDownloadResult, String> {
+        if !result.success {
+            return Ok(result);
+        }
+
+        match result.binary_data {
+            Some(binary) => {
+                let plain = self.decrypt_payload(binary, allow_plaintext)?;
+                Ok(FileDownloadResult {
+                    size: plain.len() as u64,
+                    binary_data: Some(plain),
+                    ..result
+                })
+            }
+            None => Ok(result),
+        }
+    }
+
+    /// 测试连接（带重试机制）
+    pub async fn test_connection(&self) -> Result<ConnectionTestResult, String> {
+        let backend = self.backend()?;
+        let retry_config = RetryConfig::default();
+        let mut last_result = None;
+
+        for attempt in 1..=retry_config.max_retries {
+            match backend.test_connection().await {
+                Ok(result) => {
+                    if result.success {
+                        return Ok(result);
+                    }
+                    // 失败且不可重试，直接返回
+                    if !Self::is_retryable_error(result.error_message.as_deref()) {
+                        return Ok(result);
+                    }
+                    last_result = Some(result);
+                }
+                Err(e) => {
+                    last_result = Some(ConnectionTestResult {
+                        success: false,
+                        latency_ms: 0,
+                        status_code: None,
+                        error_message: Some(format!("连接失败: {}", e)),
+                        server_info: None,
+                    });
+                }
+            }
+
+            // 如果不是最后一次尝试，等待后重试
+            if attempt < retry_config.max_retries {
+                let delay = retry_config.base_delay_ms * 2_u64.pow(attempt - 1);
+                log::debug!(
+                    "[Sync] 连接测试第 {} 次失败，{}ms 后重试...",
+                    attempt,
+                    delay
+                );
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+        }
+
+        // 所有重试都失败，返回最后一次的结果
+        Ok(last_result.unwrap_or_else(|| ConnectionTestResult {
+            success: false,
+            latency_ms: 0,
+            status_code: None,
+            error_message: Some("所有重试均失败".to_string()),
+            server_info: None,
+        }))
+    }
+
+    /// 上传同步数据 (JSON 文本，启用加密时上传密文)
+    pub async fn upload_sync_data(
+        &self,
+        file_path: &str,
+        content: &str,
+    ) -> Result<FileUploadResult, String> {
+        self.upload_sync_data_conditional(file_path, content, &WriteCondition::Always)
+            .await
+    }
+
+    /// 按条件上传同步数据
+    /// 条件不满足时返回 `success: false` 且 `is_precondition_failed()` 为 true
+    pub async fn upload_sync_data_conditional(
+        &self,
+        file_path: &str,
+        content: &str,
+        condition: &WriteCondition,
+    ) -> Result<FileUploadResult, String> {
+        let data = self.encrypt_payload(content.as_bytes())?;
+        self.upload_with_retry(file_path, &data, condition).await
+    }
+
+    /// 按上传格式编码同步数据（启用加密时为密文）
+    /// 需要根据实际上传的内容命名文件时使用，之后通过 [`Self::upload_encoded`] 上传
+    pub fn encode_sync_data(&self, content: &str) -> Result<Vec<u8>, String> {
+        self.encrypt_payload(content.as_bytes())
+    }
+
+    /// 上传 [`Self::encode_sync_data`] 编码后的同步数据
+    pub async fn upload_encoded(
+        &self,
+        file_path: &str,
+        data: &[u8],
+    ) -> Result<FileUploadResult, String> {
+        self.upload_with_retry(file_path, data, &WriteCondition::Always)
+            .await
+    }
+
+    /// 下载同步数据 (JSON 文本)
+    /// 口令错误时返回明确的解密错误，而不是交给调用方解析失败
+    pub async fn download_sync_data(&self, file_path: &str) -> Result<FileDownloadResult, String> {
+        let result = self.download_with_retry(file_path).await?;
+        let result = self.decrypt_download_result(result, self.is_plaintext_migration())?;
+        Ok(Self::into_text_result(result))
+    }
+
+    /// 下载启用加密前上传的旧数据（未加密的索引清单、旧版单文件索引），允许读取未加密的内容
+    pub async fn download_legacy_sync_data(
+        &self,
+        file_path: &str,
+    ) -> Result<FileDownloadResult, String> {
+        let result = self.download_with_retry(file_path).await?;
+        let result = self.decrypt_download_result(result, true)?;
+        Ok(Self::into_text_result(result))
+    }
+
+    /// 将二进制数据转换为文本
+    fn into_text_result(result: FileDownloadResult) -> FileDownloadResult {
+        if result.success {
+            if let Some(binary) = &result.binary_data {
+                let text = String::from_utf8_lossy(binary).to_string();
+                return FileDownloadResult {
+                    data: Some(text),
+                    binary_data: None,
+                    ..result
+                };
+            }
+        }
+
+        result
+    }
+
+    /// 上传二进制文件
+    pub async fn upload_file(
+        &self,
+        remote_path: &str,
+        file_data: &[u8],
+    ) -> Result<FileUploadResult, String> {
+        let data = self.encrypt_payload(file_data)?;
+        self.upload_with_retry(remote_path, &data, &WriteCondition::Always)
+            .await
+    }
+
+    /// 下载二进制文件
+    pub async fn download_file(&self, remote_path: &str) -> Result<FileDownloadResult, String> {
+        let result = self.download_with_retry(remote_path).await?;
+        self.decrypt_download_result(result, self.is_plaintext_migration())
+    }
+
+    /// 删除文件
+    pub async fn delete_file(&self, remote_path: &str) -> Result<bool, String> {
+        self.backend()?.delete(remote_path).await
+    }
+
+    /// 批量删除文件
+    pub async fn delete_files(&self, file_paths: &[String]) -> Result<Vec<String>, String> {
+        let mut failed_paths = Vec::new();
+
+        for path in file_paths {
+            if !self.delete_file(path).await.unwrap_or(false) {
+                failed_paths.push(path.clone());
+            }
+        }
+
+        Ok(failed_paths)
+    }
+
+    /// 列出目录下的文件
+    pub async fn list_files(&self, dir: &str) -> Result<Vec<String>, String> {
+        self.backend()?.list(dir).await
+    }
+
+    /// 检查文件是否存在
+    pub async fn file_exists(&self, remote_path: &str) -> Result<bool, String> {
+        self.backend()?.exists(remote_path).await
+    }
+
+    /// 带重试的上传
+    async fn upload_with_retry(
+        &self,
+        file_path: &str,
+        data: &[u8],
+        condition: &WriteCondition,
+    ) -> Result<FileUploadResult, String> {
+        let backend = self.backend()?;
+        let retry_config = RetryConfig::default();
+        let start_time = Instant::now();
+        let mut last_error = None;
+
+        for attempt in 1..=retry_config.max_retries {
+            match backend.upload(file_path, data, condition).await {
+                Ok(result) if result.success => {
+                    return Ok(FileUploadResult {
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                        ..result
+                    });
+                }
+                Ok(result) => {
+                    if Self::is_retryable_error(result.error_message.as_deref())
+                        && attempt < retry_config.max_retries
+                    {
+                        last_error = result.error_message;
+                        let delay = retry_config.base_delay_ms * 2_u64.pow(attempt - 1);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        continue;
+                    }
+                    return Ok(FileUploadResult {
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                        ..result
+                    });
+                }
+                Err(e) => {
+                    if attempt < retry_config.max_retries {
+                        last_error = Some(e);
+                        let delay = retry_config.base_delay_ms * 2_u64.pow(attempt - 1);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                    } else {
+                        return Ok(FileUploadResult {
+                            success: false,
+                            path: file_path.to_string(),
+                            size: 0,
+                            duration_ms: start_time.elapsed().as_millis() as u64,
+                            error_message: Some(format!("所有重试均失败: {}", e)),
+                            etag: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(FileUploadResult {
+            success: false,
+            path: file_path.to_string(),
+            size: 0,
+            duration_ms: start_time.elapsed().as_millis() as u64,
+            error_message: last_error.or(Some("未知错误".to_string())),
+            etag: None,
+        })
+    }
+
+    /// 带重试的下载
+    async fn download_with_retry(&self, file_path: &str) -> Result<FileDownloadResult, String> {
+        let backend = self.backend()?;
+        let retry_config = RetryConfig::default();
+        let start_time = Instant::now();
+        let mut last_error = None;
+
+        for attempt in 1..=retry_config.max_retries {
+            match backend.download(file_path).await {
+                Ok(result) if result.success => {
+                    return Ok(FileDownloadResult {
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                        ..result
+                    });
+                }
+                Ok(result) => {
+                    if Self::is_retryable_error(result.error_message.as_deref())
+                        && attempt < retry_config.max_retries
+                    {
+                        last_error = result.error_message;
+                        let delay = retry_config.base_delay_ms * 2_u64.pow(attempt - 1);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                        continue;
+                    }
+                    return Ok(FileDownloadResult {
+                        duration_ms: start_time.elapsed().as_millis() as u64,
+                        ..result
+                    });
+                }
+                Err(e) => {
+                    if attempt < retry_config.max_retries {
+                        last_error = Some(e);
+                        let delay = retry_config.base_delay_ms * 2_u64.pow(attempt - 1);
+                        tokio::time::sleep(Duration::from_millis(delay)).await;
+                    } else {
+                        return Ok(FileDownloadResult {
+                            success: false,
+                            path: file_path.to_string(),
+                            size: 0,
+                            duration_ms: start_time.elapsed().as_millis() as u64,
+                            data: None,
+                            binary_data: None,
+                            error_message: Some(format!("所有重试均失败: {}", e)),
+                            etag: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(FileDownloadResult {
+            success: false,
+            path: file_path.to_string(),
+            size: 0,
+            duration_ms: start_time.elapsed().as_millis() as u64,
+            data: None,
+            binary_data: None,
+            error_message: last_error.or(Some("未知错误".to_string())),
+            etag: None,
+        })
+    }
+
+    /// 判断是否是可重试的错误
+    fn is_retryable_error(error_msg: Option<&str>) -> bool {
+        if let Some(msg) = error_msg {
+            msg.contains("timeout")
+                || msg.contains("超时")
+                || msg.contains("connection")
+                || msg.contains("连接")
+                || msg.contains("502")
+                || msg.contains("503")
+                || msg.contains("504")
+                || msg.contains("handshake")
+                || msg.contains("unexpected EOF")
+        } else {
+            false
+        }
+    }
+}
+
+/// 创建共享的同步存储实例
+pub fn create_shared_storage() -> SyncStorageState {
+    Arc::new(Mutex::new(SyncStorage::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend_type() {
+        assert_eq!(StorageBackendType::parse("S3"), Some(StorageBackendType::S3));
+        assert_eq!(
+            StorageBackendType::parse("local"),
+            Some(StorageBackendType::Local)
+        );
+        assert_eq!(StorageBackendType::parse("ftp"), None);
+    }
+
+    #[test]
+    fn test_xml_element_texts() {
+        let body = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+    <d:response><d:href>/dav/EcoPaste-Sync/</d:href></d:response>
+    <d:response><d:href>/dav/EcoPaste-Sync/a%20b.json</d:href><d:propstat/></d:response>
+    <D:href xmlns:D="DAV:">/x&amp;y</D:href>
+</d:multistatus>"#;
+
+        assert_eq!(
+            xml_element_texts(body, "href"),
+            vec!["/dav/EcoPaste-Sync/", "/dav/EcoPaste-Sync/a%20b.json", "/x&y"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cipher_rejects_plaintext_payload() {
+        let root = std::env::temp_dir().join(format!("ecopaste-storage-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+
+        let mut storage = SyncStorage::new();
+        storage
+            .initialize(StorageConfig {
+                backend: StorageBackendType::Local,
+                url: String::new(),
+                username: String::new(),
+                password: String::new(),
+                path: root.to_string_lossy().to_string(),
+                timeout: 0,
+                bucket: None,
+                region: None,
+                encryption_passphrase: Some("secret".to_string()),
+            })
+            .await
+            .unwrap();
+
+        // 有存储写权限的人直接写入的明文分片
+        let shard = "index/shard-00-0123.json";
+        std::fs::create_dir_all(root.join("index")).unwrap();
+        std::fs::write(root.join(shard), b"[]").unwrap();
+
+        assert_eq!(
+            storage.download_sync_data(shard).await.unwrap_err(),
+            crypto::UNENCRYPTED_DATA_ERROR
+        );
+        assert!(storage.download_file(shard).await.is_err());
+
+        // 只有迁移启用加密前的数据时允许读取
+        storage.begin_plaintext_migration();
+        let migrated = storage.download_sync_data(shard).await.unwrap();
+        assert_eq!(migrated.data.as_deref(), Some("[]"));
+        storage.finish_plaintext_migration();
+        assert!(storage.download_sync_data(shard).await.is_err());
+
+        // 加密上传的内容正常读取
+        storage.upload_sync_data(shard, "[1]").await.unwrap();
+        let encrypted = storage.download_sync_data(shard).await.unwrap();
+        assert_eq!(encrypted.data.as_deref(), Some("[1]"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_content_type_for() {
+        assert_eq!(
+            content_type_for("index/manifest.json", b"{}"),
+            "application/json; charset=utf-8"
+        );
+        assert_eq!(
+            content_type_for("files/a.png", b"\x89PNG"),
+            "application/octet-stream"
+        );
+    }
+}
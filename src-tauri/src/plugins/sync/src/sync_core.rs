@@ -1,16 +1,25 @@
 //! 同步核心模块
 //! 基于前端云同步引擎的经验教训，设计更robust的同步架构
 
+use crate::cloud_index::{CloudIndex, INDEX_CONFLICT_ERROR};
+use crate::crypto;
 use crate::data_manager::DataManager;
 use crate::file_sync_manager::FileSyncManager;
+use crate::merge;
 use crate::types::*;
-use crate::webdav::WebDAVClientState;
+use crate::storage::SyncStorageState;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Runtime};
-use tauri_plugin_eco_database::{DatabaseState, DeleteManager};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_eco_database::{
+    emit_changes, ChangeOperation, DatabaseChanges, DatabaseState,
+    DEFAULT_TOMBSTONE_RETENTION_DAYS, SENSITIVE_SUBTYPE,
+};
 use tokio::sync::Mutex;
 
+/// 云端索引并发冲突时的最大同步次数
+const MAX_CONFLICT_ATTEMPTS: u32 = 3;
+
 /// 类型别名：本地数据使用数据库模型
 pub type LocalSyncDataItem = tauri_plugin_eco_database::SyncDataItem;
 
@@ -30,6 +39,8 @@ pub struct SyncModeConfig {
     pub include_images: bool,
     /// 是否包含文件
     pub include_files: bool,
+    /// 是否包含敏感内容
+    pub include_sensitive: bool,
     /// 是否包含书签
     pub include_bookmarks: bool,
 }
@@ -56,12 +67,12 @@ pub struct SyncProcessResult {
 /// 同步核心引擎
 /// 专注于核心同步逻辑
 pub struct SyncCore {
-    /// WebDAV 客户端
-    webdav_client: WebDAVClientState,
     /// 数据管理器
     pub data_manager: Arc<Mutex<DataManager>>,
     /// 文件同步管理器
     file_sync_manager: Arc<Mutex<FileSyncManager>>,
+    /// 云端分片索引
+    cloud_index: Arc<Mutex<CloudIndex>>,
     /// 同步配置（统一配置入口）
     pub config: Arc<Mutex<Option<SyncConfig>>>,
     /// 是否正在同步
@@ -71,12 +82,12 @@ pub struct SyncCore {
 impl SyncCore {
     /// 创建新的同步核心实例
     pub fn new(
-        webdav_client: WebDAVClientState,
+        storage: SyncStorageState,
         data_manager: Arc<Mutex<DataManager>>,
         file_sync_manager: Arc<Mutex<FileSyncManager>>,
     ) -> Self {
         Self {
-            webdav_client,
+            cloud_index: Arc::new(Mutex::new(CloudIndex::new(storage))),
             data_manager,
             file_sync_manager,
             config: Arc::new(Mutex::new(None)),
@@ -95,8 +106,8 @@ impl SyncCore {
         self.config.lock().await.clone()
     }
 
-    /// 执行同步操作（优化后流程）
-    /// 流程：获取云端索引 -> 处理索引删除 -> 获取本地数据 -> 双向同步 -> 处理文件
+    /// 执行同步操作
+    /// 其他设备同时提交了云端索引时，重新下载合并后重试
     pub async fn perform_sync<R: Runtime>(
         &mut self,
         mode_config: SyncModeConfig,
@@ -108,6 +119,52 @@ impl SyncCore {
         }
 
         self.sync_in_progress = true;
+
+        let mut attempt = 1;
+        let outcome = loop {
+            let outcome = self
+                .perform_sync_once(mode_config.clone(), database_state, app_handle)
+                .await;
+
+            let conflicted = match &outcome {
+                Ok(result) => result
+                    .errors
+                    .iter()
+                    .any(|e| e.contains(INDEX_CONFLICT_ERROR)),
+                Err(e) => e.contains(INDEX_CONFLICT_ERROR),
+            };
+
+            if !conflicted || attempt >= MAX_CONFLICT_ATTEMPTS {
+                break outcome;
+            }
+
+            log::warn!(
+                "[Sync] 云端索引已被其他设备更新，重新合并后重试 ({}/{})",
+                attempt,
+                MAX_CONFLICT_ATTEMPTS - 1
+            );
+
+            // 随机退避，避免多台设备再次同时提交
+            let jitter = chrono::Utc::now().timestamp_subsec_millis() as u64 % 500;
+            tokio::time::sleep(std::time::Duration::from_millis(
+                500 * attempt as u64 + jitter,
+            ))
+            .await;
+            attempt += 1;
+        };
+
+        self.sync_in_progress = false;
+        outcome
+    }
+
+    /// 执行一次同步（优化后流程）
+    /// 流程：获取云端索引 -> 交换删除记录 -> 处理索引删除 -> 获取本地数据 -> 双向同步 -> 处理文件
+    async fn perform_sync_once<R: Runtime>(
+        &mut self,
+        mode_config: SyncModeConfig,
+        database_state: &DatabaseState,
+        app_handle: &AppHandle<R>,
+    ) -> Result<SyncProcessResult, String> {
         let start_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -131,8 +188,26 @@ impl SyncCore {
             e
         })?;
 
+        // 交换删除记录：应用其他设备的墓碑，本地墓碑随下一次索引提交上传
+        let tombstone_ids = match self.sync_tombstones(&cloud_data, database_state).await {
+            Ok((deleted_by_remote, tombstone_ids)) => {
+                if !deleted_by_remote.is_empty() {
+                    log::info!("[Sync] 其他设备已删除 {} 项", deleted_by_remote.len());
+                }
+                result.deleted_items.extend(deleted_by_remote);
+                tombstone_ids
+            }
+            Err(e) => {
+                result.errors.push(format!("同步删除记录失败: {}", e));
+                log::error!("[Sync] 同步删除记录失败: {}", e);
+                Default::default()
+            }
+        };
+
         // 处理索引删除
-        let items_to_delete = self.calculate_items_to_delete(database_state).await;
+        let items_to_delete = self
+            .calculate_items_to_delete(database_state, &cloud_data, &tombstone_ids)
+            .await;
         let mut files_to_delete = Vec::new();
 
         if !items_to_delete.is_empty() {
@@ -162,6 +237,26 @@ impl SyncCore {
                 e
             })?;
 
+        // 读取上次确认之后的本地变更日志
+        let pending_changes = {
+            let db = database_state.read().await;
+            db.read_pending_changes(None).unwrap_or_else(|e| {
+                log::error!("[Sync] 读取变更日志失败: {}", e);
+                vec![]
+            })
+        };
+        let changed_ids: std::collections::HashSet<String> = pending_changes
+            .iter()
+            .map(|change| change.item_id.clone())
+            .collect();
+        let change_cursor = pending_changes.last().map(|change| change.seq);
+
+        // 与云端做字段级三方合并，云端修改先写回本地
+        let (local_data, merged_locally) = self
+            .merge_with_cloud(local_data, &cloud_data, database_state)
+            .await;
+        result.downloaded_items.extend(merged_locally);
+
         // 数据比对
         let filtered_cloud = self.filter_cloud_data(&cloud_data, &mode_config);
         let local_ids: std::collections::HashSet<&str> =
@@ -169,7 +264,7 @@ impl SyncCore {
 
         // 收藏模式下，获取已取消收藏的条目ID（本地 favorite=0 但云端有数据）
         let canceled_favorites: Vec<String> = if mode_config.only_favorites {
-            let db = database_state.lock().await;
+            let db = database_state.read().await;
             db.query_canceled_favorites(&cloud_data.iter().map(|i| i.id.as_str()).collect::<Vec<&str>>())
                 .unwrap_or_default()
         } else {
@@ -189,8 +284,9 @@ impl SyncCore {
                     log::info!("[Sync] 从云端删除 {} 项（已取消收藏）", deleted_count);
 
                     // 直接更新本地同步状态为 not_synced（因为云端已删除，本地不再同步）
-                    let db = database_state.lock().await;
+                    let db = database_state.read().await;
                     let _ = db.batch_update_sync_status(&canceled_favorites, "not_synced");
+                    let _ = db.remove_sync_bases(&canceled_favorites);
                     log::info!("[Sync] 更新 {} 项本地同步状态为 not_synced", canceled_favorites.len());
                 }
                 Err(e) => {
@@ -199,10 +295,18 @@ impl SyncCore {
             }
         }
 
+        // 回收站中的项目不从云端重新下载（云端删除失败时仍在云端）
+        let trashed_ids = {
+            let db = database_state.read().await;
+            db.trashed_ids().unwrap_or_default()
+        };
+
         let items_to_download: Vec<String> = filtered_cloud
             .iter()
             .filter(|item| {
                 !local_ids.contains(item.id.as_str())
+                    && !trashed_ids.contains(&item.id)
+                    && !tombstone_ids.contains(&item.id)
                     && !deleted_from_cloud.contains(&item.id)
                     && !canceled_favorites.contains(&item.id)
             })
@@ -215,6 +319,7 @@ impl SyncCore {
                 .upload_local_changes(
                     &local_data.iter().map(|i| i.id.clone()).collect::<Vec<_>>(),
                     &cloud_data,
+                    &changed_ids,
                     database_state,
                 )
                 .await
@@ -249,7 +354,7 @@ impl SyncCore {
 
         // 更新本地同步状态
         {
-            let db = database_state.lock().await;
+            let db = database_state.read().await;
             let tracker = db.get_change_tracker();
             let all_synced_items: Vec<String> = result
                 .uploaded_items
@@ -261,6 +366,15 @@ impl SyncCore {
             let _ = tracker.mark_items_synced(&conn, &all_synced_items);
         }
 
+        // 本次同步没有其他索引变更时单独提交删除记录
+        {
+            let mut cloud_index = self.cloud_index.lock().await;
+            if let Err(e) = cloud_index.save_tombstones().await {
+                result.errors.push(format!("上传删除记录失败: {}", e));
+                log::error!("[Sync] 上传删除记录失败: {}", e);
+            }
+        }
+
         // 处理文件同步
         self.process_file_sync(&local_data, database_state).await?;
 
@@ -276,6 +390,15 @@ impl SyncCore {
             .as_millis() as i64;
 
         result.success = result.errors.is_empty();
+
+        // 同步成功后确认本次读取的变更日志，同步期间产生的新记录留到下次
+        if let Some(cursor) = change_cursor.filter(|_| result.success) {
+            let db = database_state.read().await;
+            if let Err(e) = db.acknowledge_changes(cursor) {
+                log::error!("[Sync] 确认变更日志失败: {}", e);
+            }
+        }
+
         result.duration_ms = (end_time - start_time) as u64;
 
         if result.success {
@@ -297,16 +420,14 @@ impl SyncCore {
             log::error!("[Sync] 完成，有 {} 个错误", result.errors.len());
         }
 
-        // 通知前端刷新列表（如果有数据变更）
-        if !result.uploaded_items.is_empty()
-            || !result.downloaded_items.is_empty()
-            || !result.deleted_items.is_empty()
-        {
-            let payload = serde_json::json!({ "duplicate_id": null });
-            let _ = app_handle.emit("plugin:eco-clipboard://database_updated", payload);
-        }
+        // 本次同步的变更合并为一次事件（没有变更时不发送）
+        let mut changes = DatabaseChanges::new();
+        changes
+            .record(ChangeOperation::Synced, &result.uploaded_items, &["syncStatus"])
+            .record(ChangeOperation::Synced, &result.downloaded_items, &[])
+            .record(ChangeOperation::Deleted, &result.deleted_items, &[]);
+        emit_changes(app_handle, changes);
 
-        self.sync_in_progress = false;
         Ok(result)
     }
 
@@ -328,6 +449,9 @@ impl SyncCore {
         if local_item.item_type != cloud_item.item_type
             || local_item.favorite != cloud_item.favorite
             || local_item.note != cloud_item.note
+            || local_item.tags != cloud_item.tags
+            || local_item.expires_at != cloud_item.expires_at
+            || local_item.expire_on_paste != cloud_item.expire_on_paste
         {
             return false;
         }
@@ -342,6 +466,88 @@ impl SyncCore {
         true
     }
 
+    /// 基于同步基准对本地与云端都存在的项目做三方合并
+    /// 返回合并后的本地数据以及被云端修改更新过的项目ID
+    async fn merge_with_cloud(
+        &self,
+        local_data: Vec<SyncDataItem>,
+        cloud_data: &[SyncDataItem],
+        database_state: &DatabaseState,
+    ) -> (Vec<SyncDataItem>, Vec<String>) {
+        let db = database_state.read().await;
+
+        let bases = match db.load_sync_bases() {
+            Ok(bases) => bases,
+            Err(e) => {
+                log::error!("[Sync] 读取同步基准失败: {}", e);
+                return (local_data, vec![]);
+            }
+        };
+
+        let cloud_map: std::collections::HashMap<&str, &SyncDataItem> =
+            cloud_data.iter().map(|item| (item.id.as_str(), item)).collect();
+
+        let mut merged_data = Vec::with_capacity(local_data.len());
+        let mut merged_locally = Vec::new();
+        let mut new_bases = Vec::new();
+        let mut conflicts = Vec::new();
+
+        for local_item in local_data {
+            let Some(cloud_item) = cloud_map.get(local_item.id.as_str()) else {
+                merged_data.push(local_item);
+                continue;
+            };
+
+            let Some(base) = bases.get(&local_item.id) else {
+                // 尚无基准：两端一致时以云端版本作为基准，否则沿用原有的覆盖逻辑
+                if self.is_item_actually_synced(&local_item, cloud_item) {
+                    new_bases.push((*cloud_item).clone());
+                }
+                merged_data.push(local_item);
+                continue;
+            };
+
+            let outcome = merge::three_way_merge(base, &local_item, cloud_item);
+            conflicts.extend(outcome.conflicts);
+
+            if outcome.local_changed {
+                if let Err(e) = db.apply_merged_item(&outcome.merged) {
+                    log::error!("[Sync] 写入合并结果失败: {} ({})", local_item.id, e);
+                    merged_data.push(local_item);
+                    continue;
+                }
+                merged_locally.push(local_item.id.clone());
+
+                if !outcome.remote_changed {
+                    new_bases.push((*cloud_item).clone());
+                }
+            }
+
+            merged_data.push(outcome.merged);
+        }
+
+        if let Err(e) = db.save_sync_bases(&new_bases) {
+            log::error!("[Sync] 保存同步基准失败: {}", e);
+        }
+
+        if !conflicts.is_empty() {
+            log::warn!("[Sync] 检测到 {} 个字段冲突，已暂时采用云端的值", conflicts.len());
+            if let Err(e) = db.insert_sync_conflicts(&conflicts) {
+                log::error!("[Sync] 记录同步冲突失败: {}", e);
+            }
+        }
+        drop(db);
+
+        if !merged_locally.is_empty() {
+            log::info!("[Sync] 合并云端修改 {} 项", merged_locally.len());
+        }
+
+        let mut manager = self.data_manager.lock().await;
+        manager.load_local_data(merged_data.clone()).await;
+
+        (merged_data, merged_locally)
+    }
+
     /// 获取同步状态
     pub fn get_sync_status(&self) -> SyncStatus {
         if self.sync_in_progress {
@@ -363,7 +569,7 @@ impl SyncCore {
         database_state: &DatabaseState,
         mode_config: &SyncModeConfig,
     ) -> Result<Vec<SyncDataItem>, String> {
-        let db = database_state.lock().await;
+        let db = database_state.read().await;
 
         let content_types = tauri_plugin_eco_database::ContentTypeFilter {
             include_text: true,
@@ -378,6 +584,7 @@ impl SyncCore {
             mode_config.only_favorites,
             mode_config.include_images,
             mode_config.include_files,
+            mode_config.include_sensitive,
             content_types,
             sync_status_filter,
         ) {
@@ -398,29 +605,24 @@ impl SyncCore {
     }
 
     /// 加载云端数据
+    /// 只下载修订号发生变化的索引分片
     async fn load_cloud_data(&self) -> Result<Vec<SyncDataItem>, String> {
-        let webdav_client = self.webdav_client.clone();
         let data_manager = self.data_manager.clone();
 
-        let client = webdav_client.lock().await;
-        match client.download_sync_data("sync-data.json").await {
-            Ok(result) => {
-                let cloud_data = if let Some(data) = result.data {
-                    let cloud_items: Vec<SyncDataItem> = serde_json::from_str(&data)
-                        .map_err(|e| format!("解析云端数据失败: {}", e))?;
+        let mut cloud_index = self.cloud_index.lock().await;
+        match cloud_index.load().await {
+            Ok(cloud_items) => {
+                let mut manager = data_manager.lock().await;
+                manager.load_cloud_data(cloud_items.clone()).await;
 
-                    let mut manager = data_manager.lock().await;
-                    manager.load_cloud_data(cloud_items.clone()).await;
-
-                    log::info!("[Sync] 云端: {} 项", cloud_items.len());
-                    cloud_items
-                } else {
-                    let mut manager = data_manager.lock().await;
-                    manager.load_cloud_data(vec![]).await;
-                    vec![]
-                };
-
-                Ok(cloud_data)
+                log::info!("[Sync] 云端: {} 项", cloud_items.len());
+                Ok(cloud_items)
+            }
+            Err(e)
+                if e == crypto::WRONG_PASSPHRASE_ERROR || e == crypto::MISSING_PASSPHRASE_ERROR =>
+            {
+                log::error!("[Sync] {}", e);
+                Err(e)
             }
             Err(e) => {
                 log::error!("[Sync] 下载云端数据失败: {}", e);
@@ -443,6 +645,13 @@ impl SyncCore {
                     return false;
                 }
 
+                // 敏感内容检查
+                if !mode_config.include_sensitive
+                    && item.subtype.as_deref() == Some(SENSITIVE_SUBTYPE)
+                {
+                    return false;
+                }
+
                 // 内容类型检查（默认包含所有文本类型）
                 match item.item_type.as_str() {
                     "text" => true,
@@ -457,32 +666,86 @@ impl SyncCore {
             .collect()
     }
 
-    /// 计算需要删除的项目（简化版）
-    /// 根据优化方案：本地标记删除的项目直接在云端索引中删除
-    async fn calculate_items_to_delete(&self, _database_state: &DatabaseState) -> Vec<String> {
-        let db = _database_state.lock().await;
-
-        // 查询本地软删除的项目
-        let options = tauri_plugin_eco_database::QueryOptions {
-            where_clause: Some("deleted = 1".to_string()),
-            order_by: None,
-            limit: None,
-            offset: None,
-            only_favorites: false,
-            exclude_deleted: false,
-            params: None,
+    /// 交换删除记录
+    /// 应用云端墓碑（删除本地对应项目），移除删除后又在云端被修改过（如从回收站恢复）的项目的墓碑，
+    /// 清理超过保留期的墓碑，再将本地墓碑暂存到云端索引
+    ///
+    /// # Returns
+    /// 本地被删除的项目ID，以及当前所有墓碑的项目ID
+    async fn sync_tombstones(
+        &self,
+        cloud_data: &[SyncDataItem],
+        database_state: &DatabaseState,
+    ) -> Result<(Vec<String>, std::collections::HashSet<String>), String> {
+        let retention_days = self
+            .config
+            .lock()
+            .await
+            .as_ref()
+            .map(|config| config.tombstone_retention_days)
+            .unwrap_or(DEFAULT_TOMBSTONE_RETENTION_DAYS);
+        let cutoff = tauri_plugin_eco_database::tombstone_cutoff(retention_days);
+
+        let cloud_tombstones: Vec<_> = {
+            let cloud_index = self.cloud_index.lock().await;
+            cloud_index
+                .tombstones()
+                .into_iter()
+                .filter(|tombstone| tombstone.deleted_at >= cutoff)
+                .collect()
         };
 
-        match db.query_history(options) {
-            Ok(items) => {
-                log::info!("[Sync] 本地软删除: {} 项", items.len());
-                items.into_iter().map(|item| item.id).collect()
-            }
-            Err(e) => {
-                log::error!("[Sync] 查询软删除项目失败: {}", e);
-                vec![]
-            }
+        let db = database_state.read().await;
+        let deleted_by_remote = db.apply_tombstones(&cloud_tombstones)?;
+        let cloud_times: Vec<(&str, i64)> = cloud_data
+            .iter()
+            .map(|item| (item.id.as_str(), item.time))
+            .collect();
+        let restored = db.remove_stale_tombstones(&cloud_times)?;
+        if !restored.is_empty() {
+            log::info!("[Sync] {} 项在删除后被恢复，移除墓碑", restored.len());
+        }
+        let purged = db.purge_tombstones(retention_days)?;
+        if purged > 0 {
+            log::info!("[Sync] 清理过期墓碑 {} 条", purged);
         }
+        let tombstones = db.get_tombstones()?;
+        drop(db);
+
+        let tombstone_ids = tombstones.iter().map(|t| t.id.clone()).collect();
+        self.cloud_index.lock().await.set_tombstones(tombstones);
+
+        Ok((deleted_by_remote, tombstone_ids))
+    }
+
+    /// 计算需要删除的项目（简化版）
+    /// 根据优化方案：回收站中仍在云端的项目直接在云端索引中删除；
+    /// 已有墓碑但仍在云端的项目（如未同步前被硬删除）同样从云端删除
+    async fn calculate_items_to_delete(
+        &self,
+        database_state: &DatabaseState,
+        cloud_data: &[SyncDataItem],
+        tombstone_ids: &std::collections::HashSet<String>,
+    ) -> Vec<String> {
+        let db = database_state.read().await;
+
+        // 查询回收站中的项目（已从云端删除的项目保留在回收站中，不再处理）
+        let trashed_ids = db.trashed_ids().unwrap_or_else(|e| {
+            log::error!("[Sync] 查询回收站失败: {}", e);
+            Default::default()
+        });
+
+        let items_to_delete: Vec<String> = cloud_data
+            .iter()
+            .filter(|item| trashed_ids.contains(&item.id) || tombstone_ids.contains(&item.id))
+            .map(|item| item.id.clone())
+            .collect();
+
+        if !items_to_delete.is_empty() {
+            log::info!("[Sync] 待从云端删除: {} 项", items_to_delete.len());
+        }
+
+        items_to_delete
     }
 
     /// 处理文件同步
@@ -519,6 +782,7 @@ impl SyncCore {
             .map_err(|e| format!("创建文件缓存目录失败: {}", e))?;
 
         let mut upload_tasks = Vec::new();
+        let mut upload_paths = std::collections::HashSet::new();
         let mut download_tasks: Vec<(
             String,
             crate::file_sync_manager::FileDownloadTask,
@@ -604,14 +868,28 @@ impl SyncCore {
                                     }
                                 };
 
-                            let remote_path = format!("files/{}_{}", item.id, file_name);
+                            let content_hash = if item.item_type == "image" {
+                                crate::file_sync_manager::calculate_content_hash(&file_path)
+                                    .await
+                                    .ok()
+                            } else {
+                                None
+                            };
+
                             let metadata = crate::file_sync_manager::build_metadata_for_upload(
                                 &item.id,
                                 item.time,
                                 &file_path,
                                 file_checksum.clone(),
+                                content_hash.as_deref(),
                             );
 
+                            // 相同内容的图片只上传一次
+                            if !upload_paths.insert(metadata.remote_path.clone()) {
+                                continue;
+                            }
+
+                            let remote_path = metadata.remote_path.clone();
                             upload_tasks.push(crate::file_sync_manager::FileUploadTask {
                                 metadata,
                                 local_path: file_path.clone(),
@@ -632,23 +910,42 @@ impl SyncCore {
 
         // 执行下载任务
         for (item_id, task, local_path) in download_tasks {
-            match file_manager.download_file(task).await {
-                Ok(result) => {
-                    if result.success {
-                        let db = database_state.lock().await;
-                        if let Err(e) = db
-                            .update_item_value(&item_id, &local_path.to_string_lossy().to_string())
-                        {
-                            log::error!("[File] 更新路径失败: {}", e);
-                        }
-                    } else {
+            // 按内容哈希保存的图片可能被多个条目共享，本地已有相同文件时不再下载
+            let cached = match &task.metadata.checksum {
+                Some(expected) if local_path.exists() => {
+                    crate::file_sync_manager::calculate_file_checksum(&local_path)
+                        .await
+                        .map(|actual| actual == *expected)
+                        .unwrap_or(false)
+                }
+                _ => false,
+            };
+
+            if !cached {
+                match file_manager.download_file(task).await {
+                    Ok(result) if result.success => {}
+                    Ok(result) => {
                         log::error!("[File] 下载失败: {:?}", result.errors);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::error!("[File] 下载异常: {}", e);
+                        continue;
                     }
                 }
-                Err(e) => {
-                    log::error!("[File] 下载异常: {}", e);
+            }
+
+            let db = database_state.read().await;
+            if local_path.starts_with(&images_cache_dir) {
+                if let Err(e) = db.register_image(&local_path) {
+                    log::warn!("[File] 登记图片失败: {}", e);
                 }
             }
+            if let Err(e) =
+                db.update_item_value(&item_id, &local_path.to_string_lossy().to_string())
+            {
+                log::error!("[File] 更新路径失败: {}", e);
+            }
         }
 
         Ok(())
@@ -675,13 +972,13 @@ impl SyncCore {
         &self,
         items: &[String],
         cloud_data: &[SyncDataItem],
+        changed_ids: &std::collections::HashSet<String>,
         database_state: &DatabaseState,
     ) -> Result<Vec<String>, String> {
         if items.is_empty() {
             return Ok(vec![]);
         }
 
-        let webdav_client = self.webdav_client.clone();
         let data_manager = self.data_manager.clone();
         let file_sync_manager = self.file_sync_manager.clone();
 
@@ -698,8 +995,9 @@ impl SyncCore {
             if let Some(local_item) = local_data.iter().find(|i| i.id == *item_id) {
                 let cloud_item = cloud_data.iter().find(|i| i.id == *item_id);
 
+                // 变更日志中有记录的项目一定上传，其余按字段比对
                 let needs_upload = if let Some(cloud) = cloud_item {
-                    !self.is_item_actually_synced(local_item, cloud)
+                    changed_ids.contains(item_id) || !self.is_item_actually_synced(local_item, cloud)
                 } else {
                     true
                 };
@@ -727,6 +1025,8 @@ impl SyncCore {
         if !file_items_to_upload.is_empty() {
             let file_sync_manager_locked = file_sync_manager.lock().await;
             let mut uploaded_file_metadata: Vec<(String, serde_json::Value)> = Vec::new();
+            // 云端已有的文件（相同内容的图片共享同一个远程路径，不重复上传）
+            let mut uploaded_paths = crate::file_sync_manager::referenced_remote_paths(cloud_data);
 
             for file_item in &file_items_to_upload {
                 if let Some(value) = &file_item.value {
@@ -756,30 +1056,52 @@ impl SyncCore {
                             }
                         };
 
-                    let _remote_path = format!("files/{}_{}", file_item.id, file_name);
+                    let content_hash = if file_item.item_type == "image" {
+                        crate::file_sync_manager::calculate_content_hash(&file_path_buf)
+                            .await
+                            .ok()
+                    } else {
+                        None
+                    };
+
                     let metadata = crate::file_sync_manager::build_metadata_for_upload(
                         &file_item.id,
                         file_item.time,
                         &file_path_buf,
                         file_checksum.clone(),
+                        content_hash.as_deref(),
                     );
-
-                    let upload_task = crate::file_sync_manager::FileUploadTask {
-                        metadata,
-                        local_path: file_path_buf.clone(),
-                        remote_path: format!("files/{}_{}", file_item.id, file_name),
+                    let remote_path = metadata.remote_path.clone();
+
+                    let upload_result = if uploaded_paths.contains(&remote_path) {
+                        log::info!("[File] 云端已有相同文件，跳过上传: {}", remote_path);
+                        Ok(crate::file_sync_manager::FileOperationResult {
+                            success: true,
+                            file_ids: vec![file_item.id.clone()],
+                            success_count: 1,
+                            failed_count: 0,
+                            total_bytes: 0,
+                            duration_ms: 0,
+                            errors: vec![],
+                        })
+                    } else {
+                        let upload_task = crate::file_sync_manager::FileUploadTask {
+                            metadata,
+                            local_path: file_path_buf.clone(),
+                            remote_path: remote_path.clone(),
+                        };
+                        file_sync_manager_locked.upload_file(upload_task).await
                     };
 
-                    match file_sync_manager_locked.upload_file(upload_task).await {
+                    match upload_result {
                         Ok(result) => {
                             if result.success {
+                                uploaded_paths.insert(remote_path.clone());
+
                                 let mut metadata_map = serde_json::Map::new();
                                 metadata_map.insert(
                                     "remotePath".to_string(),
-                                    serde_json::Value::String(format!(
-                                        "files/{}_{}",
-                                        file_item.id, file_name
-                                    )),
+                                    serde_json::Value::String(remote_path),
                                 );
 
                                 if let Some(ref checksum) = &file_checksum {
@@ -799,7 +1121,7 @@ impl SyncCore {
                                 }
 
                                 if file_item.item_type == "image" {
-                                    let db = database_state.lock().await;
+                                    let db = database_state.read().await;
                                     let conn = db.get_connection().ok();
                                     if let Some(ref conn) = conn {
                                         let query = format!(
@@ -854,27 +1176,36 @@ impl SyncCore {
             }
         }
 
-        let sync_json = serde_json::to_string(&merged_items)
-            .map_err(|e| format!("序列化同步数据失败: {}", e))?;
-
-        let client = webdav_client.lock().await;
-        match client.upload_sync_data("sync-data.json", &sync_json).await {
+        let mut cloud_index = self.cloud_index.lock().await;
+        match cloud_index.save(&merged_items).await {
             Ok(_) => {
-                let db = database_state.lock().await;
+                let db = database_state.read().await;
                 let tracker = db.get_change_tracker();
                 let conn = db.get_connection()?;
                 if let Err(e) = tracker.mark_items_synced(&conn, &actually_uploaded) {
                     log::error!("[Sync] 标记同步状态失败: {}", e);
                 }
+
+                // 已上传的版本成为下次合并的基准
+                let uploaded_bases: Vec<SyncDataItem> = merged_items
+                    .iter()
+                    .filter(|item| actually_uploaded.contains(&item.id))
+                    .cloned()
+                    .collect();
+                if let Err(e) = db.save_sync_bases(&uploaded_bases) {
+                    log::error!("[Sync] 保存同步基准失败: {}", e);
+                }
                 Ok(actually_uploaded)
             }
             Err(e) => {
                 log::error!("[Sync] 上传同步数据失败: {}", e);
-                let db = database_state.lock().await;
+                let db = database_state.read().await;
                 let tracker = db.get_change_tracker();
                 let conn = db.get_connection()?;
                 for item_id in items {
-                    if let Err(err) = tracker.mark_item_changed(&conn, item_id, "upload_failed", false) {
+                    if let Err(err) =
+                        tracker.mark_item_changed(&conn, item_id, "upload_failed", &[], false)
+                    {
                         log::error!("[Sync] 标记变更失败: {}", err);
                     }
                 }
@@ -902,7 +1233,7 @@ impl SyncCore {
         for item_id in items {
             if let Some(cloud_item) = cloud_data.iter().find(|i| i.id == *item_id) {
                 // 保护措施：检查本地是否已存在且 favorite=0（收藏模式下已取消收藏）
-                let db = database_state.lock().await;
+                let db = database_state.read().await;
                 if let Ok(Some(local_item)) = db.query_by_id(item_id) {
                     if local_item.favorite == 0 {
                         // 已取消收藏，跳过下载
@@ -915,9 +1246,11 @@ impl SyncCore {
                 let mut db_item = cloud_item.clone();
                 db_item.time = chrono::Utc::now().timestamp_millis();
 
-                let db = database_state.lock().await;
+                let db = database_state.read().await;
                 if let Err(e) = db.upsert_from_cloud(&db_item) {
                     log::error!("[Sync] 保存云端数据失败: {}", e);
+                } else if let Err(e) = db.save_sync_bases(std::slice::from_ref(cloud_item)) {
+                    log::error!("[Sync] 保存同步基准失败: {}", e);
                 }
                 drop(db);
 
@@ -957,6 +1290,22 @@ impl SyncCore {
 
         let synced_deleted_items = items.to_vec();
 
+        // 先在本地写入墓碑（回收站中的项目保留，以便恢复），即使云端索引更新失败，下次同步也会根据墓碑重试
+        let db = database_state.read().await;
+        match db.settle_trashed_items(&synced_deleted_items) {
+            Ok(_) => {
+                deleted_ids = synced_deleted_items.clone();
+            }
+            Err(e) => {
+                log::error!("写入墓碑失败: {}", e);
+            }
+        }
+        if let Err(e) = db.remove_sync_bases(&synced_deleted_items) {
+            log::error!("[Sync] 删除同步基准失败: {}", e);
+        }
+        let tombstones = db.get_tombstones();
+        drop(db);
+
         if !synced_deleted_items.is_empty() {
             for item in cloud_data.iter() {
                 if synced_deleted_items.contains(&item.id) {
@@ -974,37 +1323,28 @@ impl SyncCore {
                 }
             }
 
-            let webdav_client = self.webdav_client.clone();
-            let client = webdav_client.lock().await;
-
             let original_count = updated_cloud_data.len();
             updated_cloud_data.retain(|item| !synced_deleted_items.contains(&item.id));
 
-            if updated_cloud_data.len() < original_count {
-                let updated_json = serde_json::to_string(&updated_cloud_data)
-                    .map_err(|e| format!("序列化删除数据失败: {}", e))?;
+            // 仍被其他条目引用的文件（相同内容的图片）保留在云端
+            let referenced =
+                crate::file_sync_manager::referenced_remote_paths(&updated_cloud_data);
+            files_to_delete.retain(|path| !referenced.contains(path));
+            files_to_delete.sort();
+            files_to_delete.dedup();
 
-                if let Err(e) = client
-                    .upload_sync_data("sync-data.json", &updated_json)
-                    .await
-                {
+            if updated_cloud_data.len() < original_count {
+                let mut cloud_index = self.cloud_index.lock().await;
+                match tombstones {
+                    Ok(tombstones) => cloud_index.set_tombstones(tombstones),
+                    Err(e) => log::error!("[Sync] 读取墓碑失败: {}", e),
+                }
+                if let Err(e) = cloud_index.save(&updated_cloud_data).await {
                     return Err(format!("更新云端索引失败: {}", e));
                 }
             }
-            drop(client);
         }
 
-        let mut db = database_state.lock().await;
-        match DeleteManager::batch_hard_delete(&mut *db, &synced_deleted_items) {
-            Ok(_) => {
-                deleted_ids = synced_deleted_items.clone();
-            }
-            Err(e) => {
-                log::error!("本地硬删除失败: {}", e);
-            }
-        }
-        drop(db);
-
         {
             let mut data_manager = self.data_manager.lock().await;
             data_manager.remove_deleted_items(&synced_deleted_items);
@@ -1024,21 +1364,13 @@ impl SyncCore {
             return Ok(vec![]);
         }
 
-        let webdav_client = self.webdav_client.clone();
-        let client = webdav_client.lock().await;
-
         let mut updated_cloud_data = cloud_data.to_vec();
         let original_count = updated_cloud_data.len();
         updated_cloud_data.retain(|item| !items.contains(&item.id));
 
         if updated_cloud_data.len() < original_count {
-            let updated_json = serde_json::to_string(&updated_cloud_data)
-                .map_err(|e| format!("序列化删除数据失败: {}", e))?;
-
-            if let Err(e) = client
-                .upload_sync_data("sync-data.json", &updated_json)
-                .await
-            {
+            let mut cloud_index = self.cloud_index.lock().await;
+            if let Err(e) = cloud_index.save(&updated_cloud_data).await {
                 return Err(format!("更新云端索引失败: {}", e));
             }
 
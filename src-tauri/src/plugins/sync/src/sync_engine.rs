@@ -9,12 +9,16 @@ use crate::data_manager::{create_shared_manager as create_data_manager, DataMana
 use crate::file_sync_manager::{
     create_shared_manager as create_file_sync_manager, FileSyncManager,
 };
+use crate::snippet_sync::sync_snippets;
 use crate::sync_core::{SyncCore, SyncModeConfig, SyncProcessResult};
 use crate::types::*;
-use crate::webdav::WebDAVClientState;
+use crate::storage::{StorageBackendType, SyncStorageState};
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Runtime};
-use tauri_plugin_eco_common::server_config::{get_last_sync_time, update_last_sync_time};
+use tauri_plugin_eco_common::server_config::{
+    get_cached_server_config, get_last_sync_time, is_redacted_secret, update_last_sync_time,
+    REDACTED_SECRET,
+};
 use tauri_plugin_eco_database::DatabaseState;
 use tokio::sync::Mutex;
 
@@ -27,8 +31,8 @@ pub struct CloudSyncEngine {
     pub status: SyncStatus,
     /// 进度
     pub progress: Option<SyncProgress>,
-    /// WebDAV 客户端
-    pub webdav_client: WebDAVClientState,
+    /// 同步存储
+    pub storage: SyncStorageState,
     /// 自动同步管理器
     pub auto_sync_manager: AutoSyncManagerState,
     /// 同步核心引擎
@@ -46,31 +50,31 @@ pub struct CloudSyncEngine {
 impl CloudSyncEngine {
     /// 创建新实例
     pub fn new<R: Runtime>(
-        webdav_client: WebDAVClientState,
+        storage: SyncStorageState,
         auto_sync_manager: AutoSyncManagerState,
         app_handle: &AppHandle<R>,
     ) -> Self {
         let data_manager = create_data_manager();
-        let file_sync_manager = create_file_sync_manager(webdav_client.clone());
+        let file_sync_manager = create_file_sync_manager(storage.clone());
         let sync_core = Arc::new(Mutex::new(SyncCore::new(
-            webdav_client.clone(),
+            storage.clone(),
             data_manager.clone(),
             file_sync_manager.clone(),
         )));
         let config_sync_manager = Arc::new(Mutex::new(ConfigSyncManager::new(
-            webdav_client.clone(),
+            storage.clone(),
             app_handle,
         )));
         let device_id = "device-".to_string() + &chrono::Utc::now().timestamp_millis().to_string();
         let bookmark_sync_manager = Arc::new(Mutex::new(BookmarkSyncManager::new(
-            webdav_client.clone(),
+            storage.clone(),
             device_id,
         )));
 
         Self {
             status: SyncStatus::Idle,
             progress: None,
-            webdav_client,
+            storage,
             auto_sync_manager,
             sync_core,
             data_manager,
@@ -91,20 +95,41 @@ impl CloudSyncEngine {
         let auto_sync = config.auto_sync;
         let auto_sync_interval = config.auto_sync_interval_minutes;
 
-        // 初始化 WebDAV 客户端
-        let webdav_config = crate::webdav::WebDAVConfig {
-            url: config.server_url.clone(),
-            username: config.username.clone(),
-            password: config.password.clone(),
-            path: config.path.clone(),
-            timeout: config.timeout,
-        };
+        // 前端未携带的存储设置和凭据沿用服务器配置文件（凭据保存在系统密钥环中，
+        // 前端只持有占位符）
+        let mut config = config;
+        if let Ok(server_config) = get_cached_server_config() {
+            if config.password.is_empty() || is_redacted_secret(&config.password) {
+                config.password = server_config.password;
+            }
+            if config.backend.is_none() {
+                config.backend = server_config
+                    .backend
+                    .as_deref()
+                    .and_then(StorageBackendType::parse);
+            }
+            if config.bucket.is_none() {
+                config.bucket = server_config.bucket;
+            }
+            if config.region.is_none() {
+                config.region = server_config.region;
+            }
+            if matches!(
+                config.encryption_passphrase.as_deref(),
+                None | Some(REDACTED_SECRET)
+            ) {
+                config.encryption_passphrase = server_config
+                    .encryption_passphrase
+                    .filter(|p| !p.is_empty());
+            }
+        }
 
-        let mut client = self.webdav_client.lock().await;
-        if let Err(e) = client.initialize(webdav_config).await {
-            return Err(format!("WebDAV 客户端初始化失败: {}", e));
+        // 初始化同步存储
+        let mut storage = self.storage.lock().await;
+        if let Err(e) = storage.initialize(config.storage_config()).await {
+            return Err(format!("同步存储初始化失败: {}", e));
         }
-        drop(client);
+        drop(storage);
 
         self.status = SyncStatus::Idle;
 
@@ -278,6 +303,7 @@ impl CloudSyncEngine {
             only_favorites,
             include_images: include_files, // 图片使用 include_files 配置
             include_files,
+            include_sensitive: config.include_sensitive,
             include_bookmarks: config.include_bookmarks,
         };
 
@@ -339,6 +365,21 @@ impl CloudSyncEngine {
             log::info!("[Bookmark] 书签同步已关闭，跳过同步");
         }
 
+        // 执行片段同步（只在主同步成功时）
+        if result.is_ok() {
+            match sync_snippets(&self.storage, database_state).await {
+                Ok(snippet_result) => {
+                    if snippet_result.downloaded > 0 {
+                        // 通知前端片段已更改
+                        let _ = app_handle.emit("snippet-data-changed", ());
+                    }
+                }
+                Err(e) => {
+                    log::warn!("[Snippet] 同步失败: {}", e);
+                }
+            }
+        }
+
         self.status = SyncStatus::Idle;
 
         // 同步完成后更新最后同步时间
@@ -431,12 +472,12 @@ impl CloudSyncEngine {
 
 /// 创建共享的同步引擎实例
 pub fn create_shared_engine<R: tauri::Runtime>(
-    webdav_client: WebDAVClientState,
+    storage: SyncStorageState,
     auto_sync_manager: AutoSyncManagerState,
     app_handle: &AppHandle<R>,
 ) -> Arc<Mutex<CloudSyncEngine>> {
     Arc::new(Mutex::new(CloudSyncEngine::new(
-        webdav_client,
+        storage,
         auto_sync_manager,
         app_handle,
     )))
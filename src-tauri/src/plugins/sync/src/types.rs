@@ -1,5 +1,6 @@
 //! 基础类型定义
 
+use crate::storage::{StorageBackendType, StorageConfig};
 use serde::{Deserialize, Serialize};
 
 /// 同步状态
@@ -27,12 +28,19 @@ mod tests {
             include_images: false,
             include_files: false,
             include_bookmarks: false,
+            include_sensitive: false,
             timeout: 30000,
+            encryption_passphrase: None,
+            backend: None,
+            bucket: None,
+            region: None,
+            tombstone_retention_days: 30,
         };
         assert!(!config.auto_sync);
         assert!(!config.only_favorites);
         assert!(!config.include_files);
         assert!(!config.include_bookmarks);
+        assert!(!config.include_sensitive);
         assert_eq!(config.auto_sync_interval_minutes, 5);
         assert_eq!(config.timeout, 30000);
     }
@@ -132,15 +140,54 @@ pub struct SyncConfig {
     pub include_files: bool,
     /// 是否包含书签同步
     pub include_bookmarks: bool,
+    /// 是否同步敏感内容（默认不同步）
+    #[serde(default)]
+    pub include_sensitive: bool,
     /// 连接超时（毫秒）
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    /// 端到端加密口令（为空时不加密）
+    #[serde(default)]
+    pub encryption_passphrase: Option<String>,
+    /// 存储后端类型（未指定时沿用服务器配置文件中的设置，默认 WebDAV）
+    #[serde(default)]
+    pub backend: Option<StorageBackendType>,
+    /// S3 存储桶
+    #[serde(default)]
+    pub bucket: Option<String>,
+    /// S3 区域
+    #[serde(default)]
+    pub region: Option<String>,
+    /// 删除记录（墓碑）保留天数，超过后清理
+    #[serde(default = "default_tombstone_retention_days")]
+    pub tombstone_retention_days: u32,
+}
+
+impl SyncConfig {
+    /// 转换为存储配置
+    pub fn storage_config(&self) -> StorageConfig {
+        StorageConfig {
+            backend: self.backend.unwrap_or_default(),
+            url: self.server_url.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+            path: self.path.clone(),
+            timeout: self.timeout,
+            bucket: self.bucket.clone(),
+            region: self.region.clone(),
+            encryption_passphrase: self.encryption_passphrase.clone(),
+        }
+    }
 }
 
 fn default_timeout() -> u64 {
     30000
 }
 
+fn default_tombstone_retention_days() -> u32 {
+    tauri_plugin_eco_database::DEFAULT_TOMBSTONE_RETENTION_DAYS
+}
+
 /// 同步进度
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncProgress {
@@ -41,6 +41,7 @@ export const LISTEN_KEY = {
 	TRIGGER_MANUAL_SYNC: "trigger-manual-sync",
 	CREATE_CUSTOM_GROUP: "create-custom-group",
 	BOOKMARK_DATA_CHANGED: "bookmark-data-changed",
+	DATABASE_UPDATED: "plugin:eco-clipboard://database_updated",
 };
 
 export const PRESET_SHORTCUT = {
@@ -1,5 +1,9 @@
 import { LISTEN_KEY } from "@/constants";
-import { backendQueryHistoryWithFilter } from "@/plugins/database";
+import {
+	type DatabaseChanges,
+	type QueryCondition,
+	backendQueryHistoryWithFilter,
+} from "@/plugins/database";
 import { COMMAND, toggleWindow } from "@/plugins/window";
 import type { HistoryTablePayload, TablePayload } from "@/types/database";
 import type { Store } from "@/types/store";
@@ -59,7 +63,21 @@ const Main = () => {
 
 	// 监听后端数据库更新事件
 	const handleDatabaseUpdated = useCallback(
-		async (event: { payload: { duplicate_id: string | null } }) => {
+		async (event: { payload: DatabaseChanges }) => {
+			const { changes = [], reload } = event.payload ?? {};
+
+			// 只有删除时直接从列表中移除，无需重新查询
+			if (
+				!reload &&
+				changes.length > 0 &&
+				changes.every(({ operation }) => operation === "deleted")
+			) {
+				const deletedIds = new Set(changes.flatMap(({ ids }) => ids));
+				state.list = state.list.filter(({ id }) => !deletedIds.has(id));
+				getListCache.current.clear();
+				return;
+			}
+
 			// 清除缓存并刷新列表
 			getListCache.current.clear();
 			lastQueryParams = "";
@@ -84,8 +102,8 @@ const Main = () => {
 
 		const initListen = async () => {
 			// 监听剪贴板插件的数据库更新事件（包括同步成功后的刷新）
-			unlisten = await listen<{ duplicate_id: string | null }>(
-				"plugin:eco-clipboard://database_updated",
+			unlisten = await listen<DatabaseChanges>(
+				LISTEN_KEY.DATABASE_UPDATED,
 				handleDatabaseUpdated,
 			);
 		};
@@ -307,116 +325,59 @@ const Main = () => {
 			return;
 		}
 
-		let rawData: HistoryTablePayload[];
+		// 搜索和收藏条件（所有分组通用）
+		const commonConditions: QueryCondition[] = [];
 
-		// 如果是链接分组，查询所有链接类型、路径和邮箱类型的数据，同时考虑书签筛选
-		if (linkTab) {
-			let whereClause =
-				"(subtype = 'url' OR subtype = 'path' OR subtype = 'email') AND (deleted IS NULL OR deleted = 0)";
-			const params: string[] = [];
-
-			// 如果有搜索条件（书签分组筛选），添加到查询中
-			if (search) {
-				whereClause += " AND (search LIKE ? OR note LIKE ?)";
-				params.push(`%${search}%`, `%${search}%`);
-			}
+		if (search) {
+			commonConditions.push({
+				type: "search",
+				value: { keyword: search, fields: ["SearchText", "Note"] },
+			});
+		}
 
-			// 添加收藏条件
-			if (favorite !== undefined) {
-				whereClause += " AND favorite = ?";
-				params.push(favorite ? "1" : "0");
-			}
+		if (favorite !== undefined) {
+			commonConditions.push({ type: "favorite", value: favorite });
+		}
 
-			const list = await backendQueryHistoryWithFilter({
-				where_clause: whereClause,
-				params,
-			});
+		const conditions: QueryCondition[] = [];
 
-			// 转换数据类型，与 selectSQL 保持一致
-			rawData = (Array.isArray(list) ? list : []).map((item: any) => ({
-				...item,
-				favorite: Boolean(item.favorite),
-				deleted: Boolean(item.deleted),
-				position: Number(item.position || 0),
-				syncStatus: item.syncStatus || "none",
-			})) as HistoryTablePayload[];
+		// 如果是链接分组，查询所有链接类型、路径和邮箱类型的数据，同时考虑书签筛选
+		if (linkTab) {
+			conditions.push({ type: "subtypeIn", value: ["url", "path", "email"] });
 		} else if (colorTab) {
 			// 颜色分组查询：查询 type 为 'text' 且 subtype 为 'color' 的数据
-			let whereClause =
-				"type = 'text' AND subtype = 'color' AND (deleted IS NULL OR deleted = 0)";
-			const params: string[] = [];
-
-			// 如果有搜索条件，添加到查询中
-			if (search) {
-				whereClause += " AND (search LIKE ? OR note LIKE ?)";
-				params.push(`%${search}%`, `%${search}%`);
-			}
-
-			// 添加收藏条件
-			if (favorite !== undefined) {
-				whereClause += " AND favorite = ?";
-				params.push(favorite ? "1" : "0");
-			}
-
-			const list = await backendQueryHistoryWithFilter({
-				where_clause: whereClause,
-				params,
-			});
-
-			// 转换数据类型，与 selectSQL 保持一致
-			rawData = (Array.isArray(list) ? list : []).map((item: any) => ({
-				...item,
-				favorite: Boolean(item.favorite),
-				deleted: Boolean(item.deleted),
-				position: Number(item.position || 0),
-				syncStatus: item.syncStatus || "none",
-			})) as HistoryTablePayload[];
+			conditions.push(
+				{ type: "type", value: "text" },
+				{ type: "subtypeIn", value: ["color"] },
+			);
 		} else {
 			// 特殊处理纯文本和代码分组的查询
-			let whereClause = "(deleted IS NULL OR deleted = 0)";
-			const params: string[] = [];
-
-			// 添加基本条件
 			if (group) {
-				whereClause += " AND [group] = ?";
-				params.push(group);
-			}
-
-			if (search) {
-				whereClause += " AND (search LIKE ? OR note LIKE ?)";
-				// LIKE 需要 % 包裹
-				params.push(`%${search}%`, `%${search}%`);
-			}
-
-			if (favorite !== undefined) {
-				whereClause += " AND favorite = ?";
-				params.push(favorite ? "1" : "0");
+				conditions.push({ type: "group", value: group });
 			}
 
 			// 如果是代码分组，添加 type = 'code' 条件
 			if (isCode) {
-				whereClause += " AND type = 'code'";
+				conditions.push({ type: "type", value: "code" });
 			}
 			// 如果是纯文本分组，只显示纯文本（无子类型）和格式文本
 			else if (group === "text") {
-				whereClause +=
-					" AND ((type = 'text' AND (subtype IS NULL OR subtype = '')) OR type = 'formatted')";
+				conditions.push({ type: "plainText" });
 			}
+		}
 
-			const list = await backendQueryHistoryWithFilter({
-				where_clause: whereClause,
-				params,
-			});
+		const list = await backendQueryHistoryWithFilter({
+			conditions: [...conditions, ...commonConditions],
+		});
 
-			// 转换数据类型，与 selectSQL 保持一致
-			rawData = (Array.isArray(list) ? list : []).map((item: any) => ({
-				...item,
-				favorite: Boolean(item.favorite),
-				deleted: Boolean(item.deleted),
-				position: Number(item.position || 0),
-				syncStatus: item.syncStatus || "none",
-			})) as HistoryTablePayload[];
-		}
+		// 转换数据类型，与 selectSQL 保持一致
+		const rawData = (Array.isArray(list) ? list : []).map((item: any) => ({
+			...item,
+			favorite: Boolean(item.favorite),
+			deleted: Boolean(item.deleted),
+			position: Number(item.position || 0),
+			syncStatus: item.syncStatus || "none",
+		})) as HistoryTablePayload[];
 
 		// 数据库层面已经进行了去重处理，这里直接使用原始数据
 		// 更新缓存
@@ -195,11 +195,11 @@ const CloudSync = () => {
 				setWebdavConfig(webdavConfig);
 				form.setFieldsValue(webdavConfig);
 
-				// 更新全局状态
+				// 更新全局状态（密码保存在系统密钥环中，不写入主配置文件）
 				globalStore.cloudSync.serverConfig = {
 					url: serverData.url,
 					username: serverData.username,
-					password: serverData.password,
+					password: "",
 					path: serverData.path,
 					timeout: serverData.timeout,
 				};
@@ -569,11 +569,11 @@ const CloudSync = () => {
 	// 保存服务器配置到单独文件（不参与云同步）
 	const saveServerConfig = async (config: WebDAVConfig) => {
 		try {
-			// 保存到全局状态
+			// 保存到全局状态（密码保存在系统密钥环中，不写入主配置文件）
 			globalStore.cloudSync.serverConfig = {
 				url: config.url,
 				username: config.username,
-				password: config.password,
+				password: "",
 				path: config.path,
 				timeout: config.timeout,
 			};
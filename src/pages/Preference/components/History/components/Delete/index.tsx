@@ -1,4 +1,4 @@
-import { backendCleanupHistory } from "@/plugins/database";
+import { backendCleanupHistory, createCleanupRule } from "@/plugins/database";
 import { clipboardStore } from "@/stores/clipboard";
 import { DeleteOutlined } from "@ant-design/icons";
 import { Button, message } from "antd";
@@ -13,19 +13,12 @@ const Cleanup = () => {
 		setLoading(true);
 
 		try {
-			// 读取配置项
-			const { duration, maxCount } = clipboardStore.history;
-
 			// duration 是天数（根据前端配置，unit=1 表示天）
-			const retainDays = duration;
-			const retainCount = maxCount;
-
-			await backendCleanupHistory({
-				retain_days: retainDays,
-				retain_count: retainCount,
-			});
+			const report = await backendCleanupHistory(
+				createCleanupRule(clipboardStore.history),
+			);
 
-			message.success("历史记录清理完成");
+			message.success(`历史记录清理完成，删除 ${report.removed.length} 条记录`);
 		} catch (error) {
 			message.error(
 				`清理失败: ${error instanceof Error ? error.message : "未知错误"}`,
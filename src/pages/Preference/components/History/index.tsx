@@ -1,5 +1,5 @@
 import ProList from "@/components/ProList";
-import { backendCleanupHistory } from "@/plugins/database";
+import { backendCleanupHistory, createCleanupRule } from "@/plugins/database";
 import type { Interval } from "@/types/shared";
 import Cleanup from "./components/Delete";
 import Duration from "./components/Duration";
@@ -23,10 +23,7 @@ const History = () => {
 
 	const runCleanup = async () => {
 		try {
-			await backendCleanupHistory({
-				retain_days: clipboardStore.history.duration,
-				retain_count: clipboardStore.history.maxCount,
-			});
+			await backendCleanupHistory(createCleanupRule(clipboardStore.history));
 		} catch (error) {
 			console.error("自动清理失败:", error);
 		}
@@ -45,11 +42,19 @@ const History = () => {
 	};
 
 	useImmediate(clipboardStore.history, async () => {
-		const { duration, maxCount } = clipboardStore.history;
+		const { duration, maxCount, retentionPolicies, maxMediaSize } =
+			clipboardStore.history;
 
 		clearTimeout(timerRef.current);
 
-		if (duration === 0 && maxCount === 0) return;
+		if (
+			duration === 0 &&
+			maxCount === 0 &&
+			!retentionPolicies?.length &&
+			!maxMediaSize
+		) {
+			return;
+		}
 
 		const nextTime = getNextCleanupTime();
 		const now = Date.now();
@@ -408,6 +408,13 @@ export const writeClipboard = async (
 	plain = false,
 ) => {
 	const { type, value, subtype, search } = data;
+
+	// 敏感内容在查询结果中被遮盖，写入前读取原始内容
+	if (subtype === "sensitive") {
+		const { backendRevealSensitive } = await import("./database");
+		return writeText(await backendRevealSensitive(data.id));
+	}
+
 	const textValue = plain ? search : value;
 
 	switch (type) {
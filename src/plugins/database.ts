@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import type { ClipboardStore } from "@/types/store";
 import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 
 // 数据库配置接口
@@ -28,6 +29,12 @@ export interface HistoryItem {
 	sourceAppName?: string;
 	sourceAppIcon?: string;
 	position?: number;
+	// 过期时间（毫秒时间戳）
+	expiresAt?: number;
+	// 是否在粘贴一次后过期
+	expireOnPaste?: boolean;
+	// 预览查询中 value 是否被截断
+	truncated?: boolean;
 }
 
 // 同步数据项
@@ -79,36 +86,246 @@ export interface CleanupRule {
 	retain_days: number;
 	// 保留条数，0 表示不限制
 	retain_count: number;
+	// 按类型、子类型和来源应用匹配的保留策略
+	policies?: RetentionPolicy[];
+	// 图片和文件缓存的磁盘配额（字节），0 表示不限制
+	maxMediaBytes?: number;
+}
+
+// 保留策略（匹配条件为空时匹配所有记录）
+export interface RetentionPolicy {
+	type?: string;
+	subtype?: string;
+	sourceApp?: string;
+	// 保留天数，0 表示不限制
+	retainDays?: number;
+	// 保留条数，0 表示不限制
+	retainCount?: number;
+}
+
+// 清理原因
+export type CleanupReason =
+	| "retainDays"
+	| "retainCount"
+	| "policyDays"
+	| "policyCount"
+	| "diskQuota";
+
+// 清理报告
+export interface CleanupReport {
+	removed: {
+		id: string;
+		itemType?: string;
+		reason: CleanupReason;
+		// 触发清理的保留策略序号
+		policy?: number;
+		// 释放的磁盘空间（字节）
+		reclaimedBytes?: number;
+	}[];
+	// 按配额清理释放的磁盘空间（字节）
+	reclaimedBytes: number;
+	// 清理后媒体文件占用的空间（字节）
+	mediaBytes: number;
+	// 删除的孤儿媒体文件数量
+	cacheFilesRemoved: number;
+}
+
+// 媒体文件垃圾回收报告
+export interface MediaGcReport {
+	// 是否为试运行（只统计，不删除）
+	dryRun: boolean;
+	// 扫描的文件数量
+	scanned: number;
+	// 因处于宽限期而跳过的孤儿文件数量
+	skippedRecent: number;
+	// 删除（试运行时为将要删除）的文件
+	removed: string[];
+	// 释放（试运行时为可释放）的磁盘空间（字节）
+	reclaimedBytes: number;
+	errors?: string[];
+}
+
+// 数据库变更操作类型
+export type ChangeOperation =
+	| "inserted"
+	| "updated"
+	| "deleted"
+	| "restored"
+	| "synced";
+
+// 数据库变更事件（plugin:eco-clipboard://database_updated 的载荷）
+export interface DatabaseChanges {
+	// 复制了已存在的内容时，被更新的记录 ID
+	duplicate_id: string | null;
+	// 同一操作、同一组字段的记录合并为一条
+	changes: {
+		operation: ChangeOperation;
+		ids: string[];
+		// 变更的字段，为空表示整条记录
+		fields: string[];
+	}[];
+	// 变更范围未知，需要重新加载整个列表
+	reload: boolean;
+}
+
+// 全文搜索结果
+export interface SearchResult {
+	item: HistoryItem;
+	// 相关度（越大越相关）
+	score: number;
+	// 匹配内容摘要
+	snippet: string;
+	// 摘要中需要高亮的区间 [开始, 结束)，按字符计算
+	highlights: [number, number][];
+}
+
+// 数据库快照
+export interface SnapshotInfo {
+	// 快照文件名（回滚时使用）
+	name: string;
+	kind: "daily" | "weekly" | "safety";
+	createdAt: number;
+	size: number;
+}
+
+// 标签
+export interface Tag {
+	id: string;
+	name: string;
+	color?: string;
+	createdAt: number;
+	// 使用该标签的记录数量（不含已删除）
+	itemCount: number;
+}
+
+// 片段
+export interface Snippet {
+	id: string;
+	name: string;
+	// 模板内容（可以包含占位符）
+	content: string;
+	createdAt: number;
+	updatedAt: number;
+	deleted: boolean;
 }
 
 const COMMAND = {
 	QUERY_HISTORY_WITH_FILTER: "plugin:eco-database|query_history_with_filter",
+	GET_ITEM_CONTENT: "plugin:eco-database|get_item_content",
+	SEARCH_HISTORY: "plugin:eco-database|search_history",
 	DELETE_ITEMS: "plugin:eco-database|delete_items",
 	UPDATE_FIELD: "plugin:eco-database|update_field",
 	CLEANUP_HISTORY: "plugin:eco-database|cleanup_history",
+	COLLECT_MEDIA_GARBAGE: "plugin:eco-database|collect_media_garbage",
 	RESET_DATABASE: "plugin:eco-database|reset_database",
 	QUERY_HISTORY: "plugin:eco-database|query_history",
 	INSERT_WITH_DEDUPLICATION: "plugin:eco-database|insert_with_deduplication",
 	GET_DATABASE_INFO: "plugin:eco-database|get_database_info",
+	LIST_SNAPSHOTS: "plugin:eco-database|list_snapshots",
+	ROLLBACK_SNAPSHOT: "plugin:eco-database|rollback_snapshot",
+	LIST_TAGS: "plugin:eco-database|list_tags",
+	CREATE_TAG: "plugin:eco-database|create_tag",
+	UPDATE_TAG: "plugin:eco-database|update_tag",
+	DELETE_TAG: "plugin:eco-database|delete_tag",
+	GET_ITEM_TAGS: "plugin:eco-database|get_item_tags",
+	SET_ITEM_TAGS: "plugin:eco-database|set_item_tags",
+	LIST_SNIPPETS: "plugin:eco-database|list_snippets",
+	CREATE_SNIPPET: "plugin:eco-database|create_snippet",
+	UPDATE_SNIPPET: "plugin:eco-database|update_snippet",
+	DELETE_SNIPPET: "plugin:eco-database|delete_snippet",
+	REVEAL_SENSITIVE: "plugin:eco-database|reveal_sensitive",
+	SET_ITEM_EXPIRY: "plugin:eco-database|set_item_expiry",
+	MARK_ITEMS_PASTED: "plugin:eco-database|mark_items_pasted",
+	LIST_TRASH: "plugin:eco-database|list_trash",
+	RESTORE_ITEMS: "plugin:eco-database|restore_items",
+	EMPTY_TRASH: "plugin:eco-database|empty_trash",
+	UNDO_DELETE: "plugin:eco-database|undo_delete",
 } as const;
 
+/**
+ * 查询条件（多个条件之间为 AND 关系，由后端生成参数化 SQL）
+ */
+export type QueryCondition =
+	| {
+			type: "id" | "group" | "type" | "excludeType" | "excludeSubtype";
+			value: string;
+	  }
+	| { type: "sourceApp"; value: string }
+	| { type: "subtypeIn"; value: string[] }
+	// 带有任意一个 / 全部标签（标签ID）
+	| { type: "tagsAny" | "tagsAll"; value: string[] }
+	| { type: "plainText" }
+	| { type: "favorite" | "deleted"; value: boolean }
+	| {
+			type: "syncStatusIn" | "syncStatusNotIn";
+			value: ("notSynced" | "synced" | "changed")[];
+	  }
+	| {
+			type: "search";
+			value: {
+				keyword: string;
+				fields: ("Value" | "SearchText" | "Note" | "Group" | "All")[];
+			};
+	  };
+
+/**
+ * 排序信息
+ */
+export interface QuerySort {
+	field: "Time" | "Type" | "Group" | "Favorite";
+	order: "Asc" | "Desc";
+}
+
+/**
+ * 分页游标：上一页最后一条记录的位置和ID
+ */
+export interface QueryCursor {
+	position: number;
+	id: string;
+}
+
 /**
  * 查询历史记录（带自定义筛选条件）
+ * 设置 cursor 后按 (position, id) 降序分页，不能与 sort 和 offset 同时使用
+ * 设置 previewLength 后文本内容被截断（truncated 为 true），完整内容通过 backendGetItemContent 读取
  */
 export const backendQueryHistoryWithFilter = (options: {
-	where_clause?: string;
-	params?: string[];
+	conditions?: QueryCondition[];
+	sort?: QuerySort;
+	limit?: number;
+	offset?: number;
+	cursor?: QueryCursor;
+	previewLength?: number;
 }) => {
 	return invoke<HistoryItem[]>(COMMAND.QUERY_HISTORY_WITH_FILTER, {
 		args: options,
 	});
 };
 
+/**
+ * 读取记录的完整内容（敏感内容仍被遮盖）
+ */
+export const backendGetItemContent = (id: string) => {
+	return invoke<HistoryItem>(COMMAND.GET_ITEM_CONTENT, { id });
+};
+
+/**
+ * 全文搜索历史记录，按相关度排序
+ * 支持短语（"foo bar"）、前缀（foo*）和布尔运算（AND / OR / NOT）
+ */
+export const backendSearchHistory = (
+	query: string,
+	options: { limit?: number; offset?: number } = {},
+) => {
+	return invoke<SearchResult[]>(COMMAND.SEARCH_HISTORY, {
+		query,
+		...options,
+	});
+};
+
 /**
  * 批量删除项目（支持单个或批量）
- * 根据同步状态决定删除方式：
- * - 已同步 (sync_status == "synced")：软删除，标记 deleted=1
- * - 未同步 (sync_status != "synced")：硬删除，直接从数据库删除
+ * 删除的记录放入回收站（软删除，标记 deleted=1），可以恢复，最近一次删除可以撤销
  */
 export const backendDeleteItems = (ids: string[]) => {
 	return invoke<DeleteResult>(COMMAND.DELETE_ITEMS, { ids });
@@ -135,15 +352,40 @@ export const backendUpdateField = async (
 	);
 };
 
+/**
+ * 根据历史记录设置生成清理规则
+ */
+export const createCleanupRule = (
+	history: ClipboardStore["history"],
+): CleanupRule => {
+	return {
+		retain_days: history.duration,
+		retain_count: history.maxCount,
+		policies: history.retentionPolicies ?? [],
+		maxMediaBytes: (history.maxMediaSize ?? 0) * 1024 * 1024,
+	};
+};
+
 /**
  * 执行历史记录清理（后台自动清理）
+ * 返回被清理的记录及原因
  */
 export const backendCleanupHistory = (rule: CleanupRule) => {
-	return invoke<void>(COMMAND.CLEANUP_HISTORY, {
+	return invoke<CleanupReport>(COMMAND.CLEANUP_HISTORY, {
 		rule,
 	});
 };
 
+/**
+ * 回收孤儿媒体文件（图片、文件缓存、同步下载的文件）
+ * @param dryRun 只统计可回收的文件和空间，不删除
+ */
+export const backendCollectMediaGarbage = (dryRun = false) => {
+	return invoke<MediaGcReport>(COMMAND.COLLECT_MEDIA_GARBAGE, {
+		dryRun,
+	});
+};
+
 /**
  * 重置数据库（调试用）
  */
@@ -157,3 +399,152 @@ export const backendResetDatabase = () => {
 export const backendGetDatabaseInfo = () => {
 	return invoke<DatabaseInfo>(COMMAND.GET_DATABASE_INFO, {});
 };
+
+/**
+ * 列出数据库快照（按创建时间降序）
+ */
+export const backendListSnapshots = () => {
+	return invoke<SnapshotInfo[]>(COMMAND.LIST_SNAPSHOTS);
+};
+
+/**
+ * 将数据库回滚到指定快照（回滚前会自动为当前数据生成安全快照）
+ */
+export const backendRollbackSnapshot = (name: string) => {
+	return invoke<SnapshotInfo>(COMMAND.ROLLBACK_SNAPSHOT, { name });
+};
+
+/**
+ * 获取所有标签（按名称排序）
+ */
+export const backendListTags = () => {
+	return invoke<Tag[]>(COMMAND.LIST_TAGS);
+};
+
+/**
+ * 创建标签（名称不区分大小写，已存在时报错）
+ */
+export const backendCreateTag = (name: string, color?: string) => {
+	return invoke<Tag>(COMMAND.CREATE_TAG, { name, color });
+};
+
+/**
+ * 修改标签名称或颜色（未传入的字段保持不变）
+ */
+export const backendUpdateTag = (
+	id: string,
+	changes: { name?: string; color?: string },
+) => {
+	return invoke<Tag>(COMMAND.UPDATE_TAG, { id, ...changes });
+};
+
+/**
+ * 删除标签（记录本身保留），返回受影响的记录数量
+ */
+export const backendDeleteTag = (id: string) => {
+	return invoke<number>(COMMAND.DELETE_TAG, { id });
+};
+
+/**
+ * 获取记录的标签
+ */
+export const backendGetItemTags = (itemId: string) => {
+	return invoke<Tag[]>(COMMAND.GET_ITEM_TAGS, { itemId });
+};
+
+/**
+ * 设置记录的标签（替换原有标签）
+ */
+export const backendSetItemTags = (itemId: string, tagIds: string[]) => {
+	return invoke<Tag[]>(COMMAND.SET_ITEM_TAGS, { itemId, tagIds });
+};
+
+/**
+ * 获取所有片段（按名称排序）
+ */
+export const backendListSnippets = () => {
+	return invoke<Snippet[]>(COMMAND.LIST_SNIPPETS);
+};
+
+/**
+ * 创建片段
+ */
+export const backendCreateSnippet = (name: string, content: string) => {
+	return invoke<Snippet>(COMMAND.CREATE_SNIPPET, { name, content });
+};
+
+/**
+ * 修改片段名称或内容（未传入的字段保持不变）
+ */
+export const backendUpdateSnippet = (
+	id: string,
+	changes: { name?: string; content?: string },
+) => {
+	return invoke<Snippet>(COMMAND.UPDATE_SNIPPET, { id, ...changes });
+};
+
+/**
+ * 删除片段
+ */
+export const backendDeleteSnippet = (id: string) => {
+	return invoke<void>(COMMAND.DELETE_SNIPPET, { id });
+};
+
+/**
+ * 读取敏感内容的原始值（查询结果中的敏感内容已被遮盖）
+ */
+export const backendRevealSensitive = (id: string) => {
+	return invoke<string>(COMMAND.REVEAL_SENSITIVE, { id });
+};
+
+/**
+ * 设置或清除记录的过期时间
+ * @param expiresAt 过期时间（毫秒时间戳），不传表示不按时间过期
+ * @param expireOnPaste 是否在粘贴一次后过期
+ */
+export const backendSetItemExpiry = (
+	id: string,
+	expiry: { expiresAt?: number; expireOnPaste?: boolean } = {},
+) => {
+	return invoke<void>(COMMAND.SET_ITEM_EXPIRY, { id, ...expiry });
+};
+
+/**
+ * 通知后端记录已被粘贴（粘贴后过期的记录随即删除）
+ * @returns 删除的记录数量
+ */
+export const backendMarkItemsPasted = (ids: string[]) => {
+	return invoke<number>(COMMAND.MARK_ITEMS_PASTED, { ids });
+};
+
+/**
+ * 分页查询回收站（按删除时间从新到旧）
+ */
+export const backendListTrash = (limit?: number, offset?: number) => {
+	return invoke<HistoryItem[]>(COMMAND.LIST_TRASH, { limit, offset });
+};
+
+/**
+ * 从回收站恢复记录
+ * @returns 恢复的记录 ID
+ */
+export const backendRestoreItems = (ids: string[]) => {
+	return invoke<string[]>(COMMAND.RESTORE_ITEMS, { ids });
+};
+
+/**
+ * 彻底删除回收站中的记录
+ * @param ids 要删除的记录，不传时清空整个回收站
+ * @returns 删除的记录数量
+ */
+export const backendEmptyTrash = (ids?: string[]) => {
+	return invoke<number>(COMMAND.EMPTY_TRASH, { ids });
+};
+
+/**
+ * 撤销最近一次删除（仅在删除后的短时间内有效）
+ * @returns 恢复的记录 ID，没有可撤销的删除时为空
+ */
+export const backendUndoDelete = () => {
+	return invoke<string[]>(COMMAND.UNDO_DELETE);
+};
@@ -1,4 +1,12 @@
 import { invoke } from "@tauri-apps/api/core";
+import { backendMarkItemsPasted } from "./database";
+
+// 片段展开结果
+export interface SnippetExpansion {
+	text: string;
+	// 光标位置距文本末尾的字符数（片段中没有 {cursor} 时为空）
+	cursorOffset?: number;
+}
 
 export const COMMAND = {
 	PASTE: "plugin:eco-paste|paste",
@@ -6,6 +14,9 @@ export const COMMAND = {
 	BATCH_PASTE: "plugin:eco-paste|batch_paste",
 	SINGLE_PASTE: "plugin:eco-paste|single_paste",
 	PASTE_COLOR: "plugin:eco-paste|paste_color",
+	PASTE_SNIPPET: "plugin:eco-paste|paste_snippet",
+	EXPAND_SNIPPET: "plugin:eco-paste|expand_snippet",
+	GET_SNIPPET_INPUTS: "plugin:eco-paste|get_snippet_inputs",
 };
 
 /**
@@ -31,6 +42,7 @@ export const pasteWithFocus = () => {
  */
 export const singlePasteById = async (id: string, plain = false) => {
 	await invoke(COMMAND.SINGLE_PASTE, { id, plain });
+	await backendMarkItemsPasted([id]);
 };
 
 /**
@@ -47,6 +59,7 @@ export const batchPasteByIds = async (
 	prependNewline = false,
 ) => {
 	await invoke(COMMAND.BATCH_PASTE, { ids, plain, skipFirst, prependNewline });
+	await backendMarkItemsPasted(skipFirst ? ids.slice(1) : ids);
 };
 
 /**
@@ -56,3 +69,32 @@ export const batchPasteByIds = async (
 export const pasteColor = async (colorValue: string) => {
 	await invoke(COMMAND.PASTE_COLOR, { colorValue });
 };
+
+/**
+ * 获取片段中需要填写的输入名称（{input:名称}）
+ * @param id 片段 ID
+ */
+export const getSnippetInputs = (id: string) => {
+	return invoke<string[]>(COMMAND.GET_SNIPPET_INPUTS, { id });
+};
+
+/**
+ * 展开片段中的占位符（不写入剪贴板）
+ * @param id 片段 ID
+ * @param inputs 用户填写的输入
+ */
+export const expandSnippet = (id: string, inputs?: Record<string, string>) => {
+	return invoke<SnippetExpansion>(COMMAND.EXPAND_SNIPPET, { id, inputs });
+};
+
+/**
+ * 展开片段并粘贴（后端实现）
+ * @param id 片段 ID
+ * @param inputs 用户填写的输入
+ */
+export const pasteSnippet = async (
+	id: string,
+	inputs?: Record<string, string>,
+) => {
+	await invoke(COMMAND.PASTE_SNIPPET, { id, inputs });
+};
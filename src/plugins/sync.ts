@@ -12,7 +12,15 @@ export interface BackendSyncConfig {
 	include_images: boolean;
 	include_files: boolean;
 	include_bookmarks: boolean;
+	// 是否同步敏感内容（默认不同步）
+	include_sensitive?: boolean;
 	timeout: number;
+	// 删除记录保留天数（默认 30）
+	tombstone_retention_days?: number;
+	// 存储后端（未传时沿用服务器配置）
+	backend?: "webdav" | "local" | "s3";
+	bucket?: string;
+	region?: string;
 }
 
 // 连接测试结果
@@ -54,6 +62,9 @@ const COMMAND = {
 	// 服务器配置本地管理命令（不参与云同步）
 	SAVE_SERVER_CONFIG: "plugin:eco-sync|save_server_config",
 	LOAD_SERVER_CONFIG: "plugin:eco-sync|load_server_config",
+	// 备份与恢复
+	CREATE_BACKUP: "plugin:eco-sync|create_backup",
+	RESTORE_BACKUP: "plugin:eco-sync|restore_backup",
 } as const;
 
 /**
@@ -160,6 +171,7 @@ export const backendApplyRemoteConfig = () => {
 export interface BackendServerConfigData {
 	url: string;
 	username: string;
+	/** 加载时已保存的密码以占位符返回，保存时传回占位符表示不修改 */
 	password: string;
 	path: string;
 	timeout: number;
@@ -181,3 +193,61 @@ export const backendSaveServerConfig = (config: BackendServerConfigData) => {
 export const backendLoadServerConfig = () => {
 	return invoke<BackendServerConfigData>(COMMAND.LOAD_SERVER_CONFIG);
 };
+
+// ================================
+// 备份与恢复
+// ================================
+
+/**
+ * 备份结果
+ */
+export interface BackendBackupResult {
+	path: string;
+	itemCount: number;
+	imageCount: number;
+	size: number;
+}
+
+/**
+ * 恢复模式：merge 合并到本地，replace 替换本地数据（只影响本机，不会删除云端和其他设备上的记录）
+ */
+export type BackendRestoreMode = "merge" | "replace";
+
+/**
+ * 恢复结果
+ */
+export interface BackendRestoreResult {
+	inserted: number;
+	updated: number;
+	unchanged: number;
+	removed: number;
+	imageCount: number;
+	bookmarksRestored: boolean;
+	/** 设置已替换，需要重启应用生效 */
+	settingsRestored: boolean;
+}
+
+/**
+ * 创建备份（历史记录、图片、书签和设置）
+ */
+export const backendCreateBackup = (path: string, password?: string) => {
+	return invoke<BackendBackupResult>(COMMAND.CREATE_BACKUP, {
+		path,
+		password,
+	});
+};
+
+/**
+ * 从备份恢复
+ */
+export const backendRestoreBackup = (
+	path: string,
+	mode: BackendRestoreMode,
+	password?: string,
+) => {
+	return invoke<BackendRestoreResult>(COMMAND.RESTORE_BACKUP, {
+		path,
+		mode,
+		password,
+	});
+};
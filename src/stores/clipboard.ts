@@ -43,12 +43,16 @@ export const clipboardStore = proxy<ClipboardStore>({
 		codeDetection: true, // 新增：代码检测开关
 		showSourceApp: true, // 新增：显示来源应用开关
 		colorDetection: true, // 新增：颜色识别开关
+		sensitiveDetection: true, // 敏感内容检测开关
 	},
 
 	history: {
 		duration: 0,
 		unit: 1,
 		maxCount: 0,
+		sensitiveTtl: 60, // 敏感内容保留时长（分钟），0 表示不自动删除
+		retentionPolicies: [], // 按类型、子类型和来源应用的保留策略
+		maxMediaSize: 0, // 图片和文件缓存的磁盘配额（MB），0 表示不限制
 	},
 
 	// 多选状态
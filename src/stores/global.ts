@@ -45,6 +45,10 @@ export const globalStore = proxy<GlobalStore>({
 			password: "",
 			path: "/EcoPaste-Sync",
 			timeout: 60000, // 默认60秒超时
+			encryptionPassphrase: "", // 为空时不加密
+			backend: "webdav", // 存储后端：webdav / local（本地或网络文件夹）/ s3
+			bucket: "",
+			region: "",
 		},
 		// 自动同步配置
 		autoSyncSettings: {
@@ -62,6 +66,8 @@ export const globalStore = proxy<GlobalStore>({
 				includeFiles: false, // 文件模式开关，默认关闭
 				onlyFavorites: false, // 收藏模式开关，默认关闭
 				includeBookmarks: false, // 书签同步开关，默认关闭
+				includeSensitive: false, // 敏感内容同步开关，默认关闭
+				tombstoneRetentionDays: 30, // 删除记录保留天数
 			},
 		},
 		// 文件同步设置
@@ -29,6 +29,10 @@ export interface HistoryTablePayload extends ClipboardPayload {
 	sourceAppIcon?: string;
 	// 位置字段，用于手动排序模式下保持项目位置
 	position?: number;
+	// 过期时间（毫秒时间戳）
+	expiresAt?: number;
+	// 是否在粘贴一次后过期
+	expireOnPaste?: boolean;
 }
 
 export type TablePayload = Partial<HistoryTablePayload>;
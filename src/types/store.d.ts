@@ -1,3 +1,4 @@
+import type { RetentionPolicy } from "@/plugins/database";
 import type { Platform } from "@tauri-apps/plugin-os";
 
 export type Theme = "auto" | "light" | "dark";
@@ -66,6 +67,13 @@ export interface GlobalStore {
 			password: string;
 			path: string;
 			timeout: number;
+			// 端到端加密口令（为空时不加密）
+			encryptionPassphrase?: string;
+			// 存储后端（webdav / local / s3）
+			backend?: "webdav" | "local" | "s3";
+			// S3 存储桶与区域
+			bucket?: string;
+			region?: string;
 		};
 		// 自动同步配置
 		autoSyncSettings: {
@@ -83,6 +91,10 @@ export interface GlobalStore {
 				includeFiles: boolean;
 				onlyFavorites: boolean;
 				includeBookmarks: boolean;
+				// 是否同步敏感内容（默认不同步）
+				includeSensitive?: boolean;
+				// 删除记录保留天数
+				tombstoneRetentionDays?: number;
 			};
 		};
 		// 文件同步设置
@@ -147,6 +159,7 @@ export interface ClipboardStore {
 		codeDetection: boolean; // 新增：代码检测开关
 		showSourceApp: boolean; // 新增：显示来源应用开关
 		colorDetection: boolean; // 新增：颜色识别开关
+		sensitiveDetection: boolean; // 敏感内容检测开关
 	};
 
 	// 历史记录
@@ -154,6 +167,12 @@ export interface ClipboardStore {
 		duration: number;
 		unit: number;
 		maxCount: number;
+		// 敏感内容保留时长（分钟），0 表示不自动删除
+		sensitiveTtl: number;
+		// 按类型、子类型和来源应用的保留策略
+		retentionPolicies: RetentionPolicy[];
+		// 图片和文件缓存的磁盘配额（MB），0 表示不限制
+		maxMediaSize: number;
 	};
 
 	// 多选状态
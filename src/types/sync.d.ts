@@ -25,6 +25,8 @@ export interface SyncModeConfig {
 	includeFiles: boolean;
 	/// 是否包含书签
 	includeBookmarks: boolean;
+	/// 是否包含敏感内容
+	includeSensitive?: boolean;
 	/// 内容类型设置
 	contentTypes: {
 		includeText: boolean;